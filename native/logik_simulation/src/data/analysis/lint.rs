@@ -0,0 +1,287 @@
+use crate::data::{Data, EdgeDirection};
+use crate::data::component::{Component, ComponentId, PortType, Strength};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The kinds of problems `Data::lint` finds, the numbers are the ones the FFI reports
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+pub(crate) enum DiagnosticKind {
+    /// Subnets that feed back into themselves through components that don't wait for a clock
+    CombinationalLoop = 0,
+    /// A subnet that more than one component drives at full strength all the time
+    MultipleDrivers = 1,
+    /// A subnet that nothing drives, so it floats
+    Undriven = 2,
+    /// An input port that isn't linked to a subnet
+    UnconnectedInput = 3,
+    /// An output port that isn't linked, or whose subnet nothing reads
+    UnusedOutput = 4,
+}
+
+/// A problem with how a circuit is wired, found without simulating it. For a loop these are the
+/// subnets on it and the output ports driving them, for drivers the subnet and the ports driving
+/// it, for an undriven subnet the ports reading it and for unused ports the port and its subnet
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
+pub(crate) struct Diagnostic {
+    pub(crate) kind: DiagnosticKind,
+    pub(crate) subnets: Vec<i32>,
+    pub(crate) ports: Vec<(i32, usize)>, // <component, port>
+}
+
+impl Diagnostic {
+    fn new(kind: DiagnosticKind, subnets: Vec<i32>, ports: Vec<(i32, usize)>) -> Self {
+        Self { kind, subnets, ports }
+    }
+}
+
+/// Every bit of every subnet, the bits that a bidirectional component joins are merged into one
+/// wire. The edges are the wires a component lets change other wires without waiting for a clock
+pub(super) struct Wires {
+    offsets: HashMap<i32, usize>, // <subnet, index of its first bit>
+    pub(super) subnets: Vec<i32>, // the subnet of every bit
+    parents: Vec<usize>,
+    pub(super) edges: BTreeSet<(usize, usize, i32, usize)>, // <from wire, to wire, component, output port>
+}
+
+impl Wires {
+    pub(super) fn find(&self, mut bit: usize) -> usize {
+        while self.parents[bit] != bit {
+            bit = self.parents[bit];
+        }
+        bit
+    }
+    
+    /// The wires of the bits of a subnet
+    pub(super) fn of(&self, subnet: i32, width: usize) -> Vec<usize> {
+        (0..width).map(|bit| self.find(self.offsets[&subnet] + bit)).collect()
+    }
+    
+    /// The groups of wires that can reach each other along the edges, found the Kosaraju way
+    /// without recursing so that long chains of gates don't overflow the stack
+    fn loops(&self) -> Vec<HashSet<usize>> {
+        let mut forward = HashMap::<usize, Vec<usize>>::new();
+        let mut backward = HashMap::<usize, Vec<usize>>::new();
+        for (from, to, _, _) in &self.edges {
+            forward.entry(*from).or_default().push(*to);
+            backward.entry(*to).or_default().push(*from);
+        }
+        let mut nodes = forward.keys().chain(backward.keys()).copied().collect::<Vec<_>>();
+        nodes.sort_unstable();
+        nodes.dedup();
+        
+        let mut visited = HashSet::new();
+        let mut order = Vec::with_capacity(nodes.len());
+        for node in &nodes {
+            if !visited.insert(*node) {
+                continue;
+            }
+            let mut stack = vec![(*node, 0)];
+            while let Some((node, next)) = stack.pop() {
+                match forward.get(&node).and_then(|targets| targets.get(next)) {
+                    Some(target) => {
+                        stack.push((node, next + 1));
+                        if visited.insert(*target) {
+                            stack.push((*target, 0));
+                        }
+                    }
+                    None => order.push(node),
+                }
+            }
+        }
+        
+        let mut assigned = HashSet::new();
+        let mut loops = Vec::new();
+        for node in order.into_iter().rev() {
+            if !assigned.insert(node) {
+                continue;
+            }
+            let mut group = HashSet::new();
+            let mut stack = vec![node];
+            while let Some(node) = stack.pop() {
+                group.insert(node);
+                for source in backward.get(&node).into_iter().flatten() {
+                    if assigned.insert(*source) {
+                        stack.push(*source);
+                    }
+                }
+            }
+            if group.len() > 1 || forward.get(&node).is_some_and(|targets| targets.contains(&node)) {
+                loops.push(group);
+            }
+        }
+        loops
+    }
+}
+
+impl Data {
+    /// Checks the circuit for combinational loops, subnets with conflicting drivers or none at all,
+    /// inputs that aren't linked and outputs that drive nothing. Flip-flops, registers and counters
+    /// break loops since their outputs only change on a clock edge, and so does a RAM for
+    /// everything but the address. Subcircuits are looked into to see which of their outputs follow
+    /// which inputs. Tri-state drivers, pull resistors and subcircuit outputs that can float don't
+    /// conflict with other drivers, and ports of splitters and transmission gates count as both
+    /// driving and reading their subnet
+    pub(crate) fn lint(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        
+        let wires = self.wires();
+        for group in wires.loops() {
+            let subnets = (0..wires.subnets.len())
+                .filter(|bit| group.contains(&wires.find(*bit)))
+                .map(|bit| wires.subnets[bit])
+                .collect::<BTreeSet<_>>();
+            let ports = wires.edges.iter()
+                .filter(|(from, to, _, _)| group.contains(from) && group.contains(to))
+                .map(|(_, _, component, port)| (*component, *port))
+                .collect::<BTreeSet<_>>();
+            diagnostics.push(Diagnostic::new(DiagnosticKind::CombinationalLoop, subnets.into_iter().collect(), ports.into_iter().collect()));
+        }
+        
+        let mut subnets = self.subnets.keys().copied().collect::<Vec<_>>();
+        subnets.sort_unstable();
+        for subnet in subnets {
+            let mut edges = self.subnet_edges.get(&subnet).into_iter().flatten().collect::<Vec<_>>();
+            edges.sort_unstable_by_key(|edge| (edge.component, edge.port));
+            if edges.iter().all(|edge| edge.direction == EdgeDirection::ToComponent) {
+                let readers = edges.iter().map(|edge| (edge.component, edge.port)).collect();
+                diagnostics.push(Diagnostic::new(DiagnosticKind::Undriven, vec![subnet], readers));
+            }
+            let drivers = edges.iter()
+                .filter(|edge| edge.direction == EdgeDirection::ToSubnet)
+                .filter(|edge| always_drives(self.components[&edge.component].0.as_ref(), edge.port))
+                .map(|edge| (edge.component, edge.port))
+                .collect::<Vec<_>>();
+            if drivers.len() > 1 {
+                diagnostics.push(Diagnostic::new(DiagnosticKind::MultipleDrivers, vec![subnet], drivers));
+            }
+        }
+        
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        for id in ids {
+            let component = &self.components[&id].0;
+            for port in 0..component.ports() {
+                let subnet = self.linked_subnet(id, port);
+                match (component.port_type(port), subnet) {
+                    (Some(PortType::Input), None) => diagnostics.push(Diagnostic::new(DiagnosticKind::UnconnectedInput, Vec::new(), vec![(id, port)])),
+                    (Some(PortType::Output), None) => diagnostics.push(Diagnostic::new(DiagnosticKind::UnusedOutput, Vec::new(), vec![(id, port)])),
+                    (Some(PortType::Output), Some(subnet)) => {
+                        let read = self.subnet_edges[&subnet].iter().any(|edge| edge.direction != EdgeDirection::ToSubnet);
+                        if !read {
+                            diagnostics.push(Diagnostic::new(DiagnosticKind::UnusedOutput, vec![subnet], vec![(id, port)]));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        
+        diagnostics.sort();
+        diagnostics
+    }
+    
+    pub(super) fn linked_subnet(&self, component: i32, port: usize) -> Option<i32> {
+        self.component_edges.get(&component)?
+            .iter()
+            .find(|edge| edge.port == port)
+            .map(|edge| edge.subnet)
+    }
+    
+    pub(super) fn wires(&self) -> Wires {
+        let mut subnets = self.subnets.iter().map(|(id, subnet)| (*id, subnet.width() as usize)).collect::<Vec<_>>();
+        subnets.sort_unstable();
+        let mut wires = Wires { offsets: HashMap::new(), subnets: Vec::new(), parents: Vec::new(), edges: BTreeSet::new() };
+        for (subnet, width) in &subnets {
+            wires.offsets.insert(*subnet, wires.subnets.len());
+            wires.subnets.extend(std::iter::repeat_n(*subnet, *width));
+        }
+        wires.parents = (0..wires.subnets.len()).collect();
+        
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        for id in &ids {
+            for ((a_port, a_bit), (b_port, b_bit)) in self.components[id].0.joined_bits() {
+                if let (Some(a), Some(b)) = (self.linked_subnet(*id, a_port), self.linked_subnet(*id, b_port)) {
+                    let (a, b) = (wires.find(wires.offsets[&a] + a_bit), wires.find(wires.offsets[&b] + b_bit));
+                    wires.parents[a] = b;
+                }
+            }
+        }
+        
+        let width = |subnet: i32| self.subnets[&subnet].width() as usize;
+        for id in &ids {
+            for (input, output) in combinational_paths(self.components[id].0.as_ref()) {
+                if let (Some(from), Some(to)) = (self.linked_subnet(*id, input), self.linked_subnet(*id, output)) {
+                    let targets = wires.of(to, width(to));
+                    for source in wires.of(from, width(from)) {
+                        for target in &targets {
+                            wires.edges.insert((source, *target, *id, output));
+                        }
+                    }
+                }
+            }
+        }
+        wires
+    }
+}
+
+/// The pairs of input and output ports where a change of the input can change the output without
+/// waiting for a clock edge
+pub(super) fn combinational_paths(component: &dyn Component) -> Vec<(usize, usize)> {
+    if component.stored_value().is_some() {
+        return Vec::new();
+    }
+    let ports_of = |kinds: &[PortType]| (0..component.ports())
+        .filter(|port| component.port_type(*port).is_some_and(|kind| kinds.contains(&kind)))
+        .collect::<Vec<_>>();
+    let mut inputs = ports_of(&[PortType::Input]);
+    let outputs = ports_of(&[PortType::Output, PortType::Bidirectional]);
+    if component.memory().is_some() {
+        inputs.retain(|port| *port == 0);
+    }
+    
+    match component.subcircuit() {
+        Some(subcircuit) => {
+            let circuit = subcircuit.circuit();
+            let wires = circuit.wires();
+            let pin_wires = |port: usize| {
+                let subnet = subcircuit.pin(port).and_then(|pin| circuit.linked_subnet(pin, 0));
+                subnet.map(|subnet| wires.of(subnet, circuit.subnets[&subnet].width() as usize)).unwrap_or_default()
+            };
+            let mut paths = Vec::new();
+            for input in inputs {
+                let mut reached = pin_wires(input).into_iter().collect::<HashSet<_>>();
+                let mut stack = reached.iter().copied().collect::<Vec<_>>();
+                while let Some(wire) = stack.pop() {
+                    for (_, to, _, _) in wires.edges.range((wire, 0, i32::MIN, 0)..=(wire, usize::MAX, i32::MAX, usize::MAX)) {
+                        if reached.insert(*to) {
+                            stack.push(*to);
+                        }
+                    }
+                }
+                paths.extend(outputs.iter()
+                    .filter(|output| pin_wires(**output).iter().any(|wire| reached.contains(wire)))
+                    .map(|output| (input, *output)));
+            }
+            paths
+        }
+        None => inputs.iter().flat_map(|input| outputs.iter().map(move |output| (*input, *output))).collect(),
+    }
+}
+
+/// Whether a port drives its subnet at full strength no matter what, instead of letting it float
+/// some of the time
+fn always_drives(component: &dyn Component, port: usize) -> bool {
+    if component.strength() == Strength::Weak || matches!(component.id(), ComponentId::TriStateBuffer | ComponentId::TriStateInverter) {
+        return false;
+    }
+    match component.subcircuit() {
+        Some(subcircuit) => {
+            let circuit = subcircuit.circuit();
+            let subnet = subcircuit.pin(port).and_then(|pin| circuit.linked_subnet(pin, 0));
+            subnet.is_some_and(|subnet| circuit.subnet_edges[&subnet].iter()
+                .filter(|edge| edge.direction == EdgeDirection::ToSubnet)
+                .any(|edge| always_drives(circuit.components[&edge.component].0.as_ref(), edge.port)))
+        }
+        None => true,
+    }
+}
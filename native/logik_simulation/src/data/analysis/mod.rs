@@ -0,0 +1,4 @@
+pub(crate) mod lint;
+pub(crate) mod synthesis;
+pub(crate) mod timing;
+pub(crate) mod truth_table;
@@ -0,0 +1,513 @@
+use crate::data::Data;
+use crate::data::analysis::truth_table::{TruthTable, MAX_TABLE_INPUTS};
+use crate::data::component::ComponentId;
+use crate::data::component::components::MAX_INPUTS;
+use crate::data::component::parameter::{set_parameter, ParameterValue};
+use crate::data::error::LogikError;
+use crate::data::subnet::SubnetState;
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
+
+/// The two ways a minimized function can be built out of two levels of gates
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(crate) enum Form {
+    /// An OR of ANDs, found by covering the rows where the function is on
+    SumOfProducts,
+    /// An AND of ORs, found by covering the rows where the function is off
+    ProductOfSums,
+}
+
+impl TryFrom<i32> for Form {
+    type Error = LogikError;
+    
+    fn try_from(form: i32) -> Result<Self, Self::Error> {
+        match form {
+            0 => Ok(Form::SumOfProducts),
+            1 => Ok(Form::ProductOfSums),
+            _ => Err(LogikError::InvalidParameter(format!("{} is not a form", form))),
+        }
+    }
+}
+
+/// A boolean function of named variables, given by its value for every combination of them. The
+/// first variable is the most significant bit of a row, like in a truth table. Rows without a
+/// value are don't-cares, the minimization picks whatever makes the circuit smaller
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct Function {
+    pub(crate) variables: Vec<String>,
+    pub(crate) output: String,
+    pub(crate) values: Vec<Option<bool>>,
+}
+
+fn invalid(column: usize, reason: String) -> LogikError {
+    LogikError::InvalidExpression { column, reason }
+}
+
+impl Function {
+    /// Reads an expression like `(a & !b) | c`, optionally named like `y = (a & !b) | c`. `!` and
+    /// `~` invert, `&` binds tighter than `^`, which binds tighter than `|`, and `0` and `1` are
+    /// constants. The variables are ordered by where they first appear
+    pub(crate) fn parse(expression: &str) -> Result<Function, LogikError> {
+        let tokens = tokenize(expression)?;
+        let (output, start) = match tokens.as_slice() {
+            [(_, Token::Name(name)), (_, Token::Symbol('=')), ..] => (name.clone(), 2),
+            _ => (String::from("out"), 0),
+        };
+        let mut parser = Parser { tokens: &tokens, position: start, variables: Vec::new(), end: expression.chars().count() + 1 };
+        let expr = parser.or()?;
+        if let Some((column, token)) = tokens.get(parser.position) {
+            return Err(invalid(*column, format!("unexpected {}", token.describe())));
+        }
+        
+        let count = parser.variables.len() as u32;
+        if count > MAX_TABLE_INPUTS {
+            return Err(LogikError::TooManyInputs(count));
+        }
+        let values = (0..1u64 << count).map(|row| Some(expr.evaluate(row, count))).collect();
+        Ok(Function { variables: parser.variables, output, values })
+    }
+    
+    /// Takes one output of a truth table as a function of its inputs. Every bit of a wider input
+    /// is a variable of its own, named after the input and the bit, and rows where the output is
+    /// floating or an error are don't-cares
+    pub(crate) fn from_truth_table(table: &TruthTable, output: usize) -> Result<Function, LogikError> {
+        let name = table.outputs.get(output)
+            .ok_or_else(|| LogikError::InvalidParameter(format!("the table has no output {}", output)))?;
+        if table.rows.iter().any(|(_, outputs)| outputs[output].width() != 1) {
+            return Err(LogikError::InvalidParameter(format!("output {} is more than one bit wide", name)));
+        }
+        
+        let mut variables = Vec::new();
+        if let Some((inputs, _)) = table.rows.first() {
+            for (input, value) in table.inputs.iter().zip(inputs) {
+                let input = label(input);
+                match value.width() {
+                    1 => variables.push(input),
+                    width => variables.extend((0..width).rev().map(|bit| format!("{}_{}", input, bit))),
+                }
+            }
+        }
+        let values = table.rows.iter()
+            .map(|(_, outputs)| match outputs[output].get(0) {
+                SubnetState::On => Some(true),
+                SubnetState::Off => Some(false),
+                _ => None,
+            })
+            .collect();
+        Ok(Function { variables, output: label(name), values })
+    }
+    
+    /// Finds a small set of implicants that together are on exactly where the function is on, or
+    /// for the product of sums where it is off. Every prime implicant is found the Quine-McCluskey
+    /// way, the essential ones are taken and the rest is covered by repeatedly taking the prime
+    /// implicant that covers the most rows that are still left
+    pub(crate) fn minimize(&self, form: Form) -> Vec<Implicant> {
+        let wanted = form == Form::SumOfProducts;
+        let rows = self.values.iter()
+            .enumerate()
+            .filter(|(_, value)| **value != Some(!wanted))
+            .map(|(row, _)| Implicant { value: row as u64, mask: 0 })
+            .collect::<BTreeSet<_>>();
+        let mut left = self.values.iter()
+            .enumerate()
+            .filter(|(_, value)| **value == Some(wanted))
+            .map(|(row, _)| row as u64)
+            .collect::<BTreeSet<_>>();
+        
+        let mut primes = Vec::new();
+        let mut current = rows;
+        while !current.is_empty() {
+            let mut merged = BTreeSet::new();
+            let mut used = BTreeSet::new();
+            for implicant in &current {
+                for bit in 0..self.variables.len() {
+                    let bit = 1 << bit;
+                    let other = Implicant { value: implicant.value ^ bit, mask: implicant.mask };
+                    if implicant.mask & bit == 0 && current.contains(&other) {
+                        merged.insert(Implicant { value: implicant.value & !bit, mask: implicant.mask | bit });
+                        used.insert(*implicant);
+                    }
+                }
+            }
+            primes.extend(current.difference(&used).copied());
+            current = merged;
+        }
+        
+        let mut chosen = Vec::new();
+        for row in left.clone() {
+            let mut covering = primes.iter().filter(|prime| prime.covers(row));
+            if let (Some(prime), None) = (covering.next(), covering.next()) {
+                if !chosen.contains(prime) {
+                    chosen.push(*prime);
+                }
+            }
+        }
+        left.retain(|row| !chosen.iter().any(|prime| prime.covers(*row)));
+        while let Some(best) = primes.iter()
+            .filter(|prime| !chosen.contains(prime))
+            .max_by_key(|prime| (left.iter().filter(|row| prime.covers(**row)).count(), prime.mask.count_ones()))
+        {
+            if !left.iter().any(|row| best.covers(*row)) {
+                break;
+            }
+            left.retain(|row| !best.covers(*row));
+            chosen.push(*best);
+        }
+        chosen.sort();
+        chosen
+    }
+}
+
+/// Pins can't have whitespace in their label
+fn label(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// A product of some of the variables, the rows it covers have the bits of `value` everywhere but
+/// where `mask` is set
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Hash)]
+pub(crate) struct Implicant {
+    pub(crate) value: u64,
+    pub(crate) mask: u64,
+}
+
+impl Implicant {
+    fn covers(&self, row: u64) -> bool {
+        row & !self.mask == self.value
+    }
+    
+    /// The variables it depends on, as their index and whether they have to be on
+    fn literals(&self, count: usize) -> Vec<(usize, bool)> {
+        (0..count)
+            .map(|variable| (variable, 1u64 << (count - 1 - variable)))
+            .filter(|(_, bit)| self.mask & bit == 0)
+            .map(|(variable, bit)| (variable, self.value & bit != 0))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Constant(bool),
+    Symbol(char),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Name(name) => format!("'{}'", name),
+            Token::Constant(value) => format!("'{}'", *value as u8),
+            Token::Symbol(symbol) => format!("'{}'", symbol),
+        }
+    }
+}
+
+/// Splits an expression into tokens with the column they start at
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>, LogikError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().enumerate().peekable();
+    while let Some((index, c)) = chars.next() {
+        let column = index + 1;
+        match c {
+            c if c.is_whitespace() => {}
+            '0' | '1' => tokens.push((column, Token::Constant(c == '1'))),
+            '!' | '~' | '&' | '|' | '^' | '(' | ')' | '=' => tokens.push((column, Token::Symbol(c))),
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = String::from(c);
+                while let Some((_, c)) = chars.peek().filter(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    name.push(*c);
+                    chars.next();
+                }
+                tokens.push((column, Token::Name(name)));
+            }
+            c => return Err(invalid(column, format!("unexpected '{}'", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Expr {
+    Variable(usize),
+    Constant(bool),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Xor(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// The value in the row of a truth table over `count` variables
+    fn evaluate(&self, row: u64, count: u32) -> bool {
+        match self {
+            Expr::Variable(variable) => row >> (count - 1 - *variable as u32) & 1 == 1,
+            Expr::Constant(value) => *value,
+            Expr::Not(a) => !a.evaluate(row, count),
+            Expr::And(a, b) => a.evaluate(row, count) && b.evaluate(row, count),
+            Expr::Or(a, b) => a.evaluate(row, count) || b.evaluate(row, count),
+            Expr::Xor(a, b) => a.evaluate(row, count) != b.evaluate(row, count),
+        }
+    }
+}
+
+struct Parser<'t> {
+    tokens: &'t [(usize, Token)],
+    position: usize,
+    variables: Vec<String>,
+    end: usize, // the column after the expression, where a missing token is reported
+}
+
+impl<'t> Parser<'t> {
+    fn eat(&mut self, symbol: char) -> bool {
+        if let Some((_, Token::Symbol(next))) = self.tokens.get(self.position) {
+            if *next == symbol {
+                self.position += 1;
+                return true;
+            }
+        }
+        false
+    }
+    
+    fn or(&mut self) -> Result<Expr, LogikError> {
+        let mut expr = self.xor()?;
+        while self.eat('|') {
+            expr = Expr::Or(Box::new(expr), Box::new(self.xor()?));
+        }
+        Ok(expr)
+    }
+    
+    fn xor(&mut self) -> Result<Expr, LogikError> {
+        let mut expr = self.and()?;
+        while self.eat('^') {
+            expr = Expr::Xor(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+    
+    fn and(&mut self) -> Result<Expr, LogikError> {
+        let mut expr = self.unary()?;
+        while self.eat('&') {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+    
+    fn unary(&mut self) -> Result<Expr, LogikError> {
+        if self.eat('!') || self.eat('~') {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat('(') {
+            let expr = self.or()?;
+            if !self.eat(')') {
+                let column = self.tokens.get(self.position).map(|(column, _)| *column).unwrap_or(self.end);
+                return Err(invalid(column, String::from("expected ')'")));
+            }
+            return Ok(expr);
+        }
+        
+        match self.tokens.get(self.position) {
+            Some((_, Token::Name(name))) => {
+                self.position += 1;
+                let variable = match self.variables.iter().position(|variable| variable == name) {
+                    Some(variable) => variable,
+                    None => {
+                        self.variables.push(name.clone());
+                        self.variables.len() - 1
+                    }
+                };
+                Ok(Expr::Variable(variable))
+            }
+            Some((_, Token::Constant(value))) => {
+                self.position += 1;
+                Ok(Expr::Constant(*value))
+            }
+            Some((column, token)) => Err(invalid(*column, format!("expected a variable but found {}", token.describe()))),
+            None => Err(invalid(self.end, String::from("the expression ends too early"))),
+        }
+    }
+}
+
+/// What `Data::synthesize` added to a circuit
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct Synthesis {
+    /// The subnet of every variable, driven by an `Input` labelled with its name
+    pub(crate) inputs: Vec<i32>,
+    /// The subnet of the function, read by an `Output` labelled with its name
+    pub(crate) output: i32,
+    pub(crate) components: Vec<i32>,
+    pub(crate) subnets: Vec<i32>,
+}
+
+/// A signal of the circuit being built, which might still have to be inverted. Inverting is only
+/// done once the signal is used, so that double inversions never make it into the circuit
+#[derive(Debug, Clone, Copy)]
+struct Signal {
+    subnet: i32,
+    inverted: bool,
+}
+
+impl Signal {
+    fn invert(self) -> Self {
+        Signal { subnet: self.subnet, inverted: !self.inverted }
+    }
+}
+
+struct Builder<'d> {
+    data: &'d mut Data,
+    nand_only: bool,
+    next_subnet: i32,
+    inverted: HashMap<i32, i32>, // <subnet, subnet with its inverse>
+    synthesis: Synthesis,
+}
+
+impl<'d> Builder<'d> {
+    fn subnet(&mut self) -> Result<i32, LogikError> {
+        let subnet = self.next_subnet;
+        self.data.add_subnet(subnet)?;
+        self.next_subnet += 1;
+        self.synthesis.subnets.push(subnet);
+        Ok(subnet)
+    }
+    
+    fn component(&mut self, kind: ComponentId, params: &[i32], links: Vec<Option<i32>>) -> Result<i32, LogikError> {
+        let id = self.data.add_component(kind.build_with_params(params)?, links)?;
+        self.synthesis.components.push(id);
+        Ok(id)
+    }
+    
+    /// A gate of the given kind with a fresh subnet on its output
+    fn gate(&mut self, kind: ComponentId, inputs: &[i32]) -> Result<i32, LogikError> {
+        let output = self.subnet()?;
+        let mut links = inputs.iter().map(|input| Some(*input)).collect::<Vec<_>>();
+        links.push(Some(output));
+        match inputs.len() {
+            1 => self.component(kind, &[], links)?,
+            count => self.component(kind, &[count as i32], links)?,
+        };
+        Ok(output)
+    }
+    
+    /// The subnet carrying the signal, adding an inverter the first time an inverse is needed
+    fn plain(&mut self, signal: Signal) -> Result<i32, LogikError> {
+        if !signal.inverted {
+            return Ok(signal.subnet);
+        }
+        if let Some(inverse) = self.inverted.get(&signal.subnet) {
+            return Ok(*inverse);
+        }
+        
+        let inverse = if self.nand_only {
+            self.gate(ComponentId::Nand, &[signal.subnet, signal.subnet])?
+        } else {
+            self.gate(ComponentId::Not, &[signal.subnet])?
+        };
+        self.inverted.insert(signal.subnet, inverse);
+        self.inverted.insert(inverse, signal.subnet);
+        Ok(inverse)
+    }
+    
+    /// Ands the signals together, with a tree of gates if there are more of them than a gate has
+    /// inputs. With NAND gates only the result is the inverse of a NAND
+    fn and(&mut self, signals: Vec<Signal>) -> Result<Signal, LogikError> {
+        if signals.len() == 1 {
+            return Ok(signals[0]);
+        }
+        if signals.len() > MAX_INPUTS {
+            let parts = signals.chunks(MAX_INPUTS).map(|chunk| self.and(chunk.to_vec())).collect::<Result<Vec<_>, _>>()?;
+            return self.and(parts);
+        }
+        
+        let inputs = signals.into_iter().map(|signal| self.plain(signal)).collect::<Result<Vec<_>, _>>()?;
+        Ok(if self.nand_only {
+            Signal { subnet: self.gate(ComponentId::Nand, &inputs)?, inverted: true }
+        } else {
+            Signal { subnet: self.gate(ComponentId::And, &inputs)?, inverted: false }
+        })
+    }
+    
+    /// Ors the signals together like `and`. With NAND gates only it's a NAND of the inverses
+    fn or(&mut self, signals: Vec<Signal>) -> Result<Signal, LogikError> {
+        if signals.len() == 1 {
+            return Ok(signals[0]);
+        }
+        if signals.len() > MAX_INPUTS {
+            let parts = signals.chunks(MAX_INPUTS).map(|chunk| self.or(chunk.to_vec())).collect::<Result<Vec<_>, _>>()?;
+            return self.or(parts);
+        }
+        
+        if self.nand_only {
+            let inputs = signals.into_iter().map(|signal| self.plain(signal.invert())).collect::<Result<Vec<_>, _>>()?;
+            Ok(Signal { subnet: self.gate(ComponentId::Nand, &inputs)?, inverted: false })
+        } else {
+            let inputs = signals.into_iter().map(|signal| self.plain(signal)).collect::<Result<Vec<_>, _>>()?;
+            Ok(Signal { subnet: self.gate(ComponentId::Or, &inputs)?, inverted: false })
+        }
+    }
+    
+    fn constant(&mut self, value: bool) -> Result<i32, LogikError> {
+        let subnet = self.subnet()?;
+        let mut constant = ComponentId::Constant.build()?;
+        let state = if value { SubnetState::On } else { SubnetState::Off };
+        set_parameter(constant.as_mut(), "value", ParameterValue::Bits(state.into()))?;
+        let id = self.data.add_component(constant, vec![Some(subnet)])?;
+        self.synthesis.components.push(id);
+        Ok(subnet)
+    }
+    
+    fn pin(&mut self, kind: ComponentId, name: &str, subnet: i32) -> Result<(), LogikError> {
+        let mut pin = kind.build()?;
+        set_parameter(pin.as_mut(), "label", ParameterValue::String(String::from(name)))?;
+        let id = self.data.add_component(pin, vec![Some(subnet)])?;
+        self.synthesis.components.push(id);
+        Ok(())
+    }
+}
+
+impl Data {
+    /// Minimizes the function and adds it as a two level circuit of `And`, `Or` and `Not` gates,
+    /// or of `Nand` gates only. Every variable gets an `Input` and the function an `Output`, the
+    /// new subnets are numbered from one above the highest subnet there already is. Inverted
+    /// variables are shared between the terms that need them
+    pub(crate) fn synthesize(&mut self, function: &Function, form: Form, nand_only: bool) -> Result<Synthesis, LogikError> {
+        let count = function.variables.len();
+        let next_subnet = self.subnets.keys().max().map(|max| max + 1).unwrap_or(1);
+        let terms = function.minimize(form);
+        let mut builder = Builder {
+            data: self,
+            nand_only,
+            next_subnet,
+            inverted: HashMap::new(),
+            synthesis: Synthesis { inputs: Vec::new(), output: 0, components: Vec::new(), subnets: Vec::new() },
+        };
+        
+        for variable in &function.variables {
+            let subnet = builder.subnet()?;
+            builder.pin(ComponentId::Input, variable, subnet)?;
+            builder.synthesis.inputs.push(subnet);
+        }
+        
+        // a product of sums is built from the implicants of the inverse, so every literal of a
+        // term is inverted and the term becomes a sum
+        let sum_of_products = form == Form::SumOfProducts;
+        let output = match terms.first() {
+            None => builder.constant(!sum_of_products)?,
+            Some(term) if term.mask.count_ones() as usize == count => builder.constant(sum_of_products)?,
+            _ => {
+                let mut signals = Vec::with_capacity(terms.len());
+                for term in &terms {
+                    let literals = term.literals(count)
+                        .into_iter()
+                        .map(|(variable, on)| Signal { subnet: builder.synthesis.inputs[variable], inverted: on != sum_of_products })
+                        .collect::<Vec<_>>();
+                    signals.push(if sum_of_products { builder.and(literals)? } else { builder.or(literals)? });
+                }
+                let result = if sum_of_products { builder.or(signals)? } else { builder.and(signals)? };
+                builder.plain(result)?
+            }
+        };
+        builder.pin(ComponentId::Output, &function.output, output)?;
+        builder.synthesis.output = output;
+        Ok(builder.synthesis)
+    }
+}
@@ -0,0 +1,207 @@
+use crate::data::Data;
+use crate::data::analysis::lint::{combinational_paths, Wires};
+use crate::data::component::subcircuit::Subcircuit;
+use crate::data::component::{ComponentId, PortType};
+use crate::data::error::LogikError;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// The slowest way a change takes through the circuit. `components[i]` drives `subnets[i]`, so the
+/// path starts at an input or a flip-flop and the last subnet is read by an output or a flip-flop
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub(crate) struct CriticalPath {
+    pub(crate) components: Vec<i32>,
+    pub(crate) subnets: Vec<i32>,
+    pub(crate) delay: u64,
+}
+
+/// How long the subnets of a circuit take to settle after a clock edge or a change of its inputs
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct Timing {
+    pub(crate) arrivals: BTreeMap<i32, u64>, // <subnet, when its last bit settles>
+    pub(crate) critical_path: CriticalPath,
+    pub(crate) clock_period: Option<u64>,
+}
+
+impl Timing {
+    /// The fastest the clocks can run with every flip-flop seeing its inputs settled before the
+    /// next edge, in cycles per unit of time
+    pub(crate) fn max_frequency(&self) -> Option<f64> {
+        self.clock_period.filter(|period| *period > 0).map(|period| 1.0 / period as f64)
+    }
+}
+
+/// How the latest change reaches a wire, along with the component and output port driving it
+#[derive(Debug, Clone, Copy)]
+struct Arrival {
+    time: u64,
+    from: Option<usize>, // the wire it came from, or none where the path starts
+    component: i32,
+    port: usize,
+}
+
+fn arrive(arrivals: &mut HashMap<usize, Arrival>, wire: usize, arrival: Arrival) {
+    if arrivals.get(&wire).is_none_or(|latest| arrival.time > latest.time) {
+        arrivals.insert(wire, arrival);
+    }
+}
+
+impl Data {
+    /// Works out when every subnet settles, the way the lint follows the circuit. Paths start at
+    /// time 0 at components without inputs, like pins, constants and clocks, and after their own
+    /// delay at flip-flops and other outputs that don't follow an input right away. They end at
+    /// inputs that no output follows right away, like those of output pins, probes and flip-flops.
+    /// Every component takes the delay given for its kind in `delays`, or its own delay, and a
+    /// subcircuit takes the time a change takes through it on top of that. The clock period is
+    /// the latest any flip-flop input settles, there is none without flip-flops
+    pub(crate) fn timing(&self, delays: &HashMap<ComponentId, u32>) -> Result<Timing, LogikError> {
+        let wires = self.wires();
+        let delay = |id: i32| self.component_delay(id, delays);
+        let width = |subnet: i32| self.subnets[&subnet].width() as usize;
+        
+        let mut arrivals = HashMap::new();
+        let mut endpoints = Vec::new(); // <wire, whether a flip-flop reads it>
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        for id in ids {
+            let component = &self.components[&id].0;
+            let paths = combinational_paths(component.as_ref());
+            let has_inputs = (0..component.ports()).any(|port| component.port_type(port) == Some(PortType::Input));
+            for port in 0..component.ports() {
+                let subnet = match self.linked_subnet(id, port) {
+                    Some(subnet) => subnet,
+                    None => continue,
+                };
+                match component.port_type(port) {
+                    Some(PortType::Output) if paths.iter().all(|(_, output)| *output != port) => {
+                        let time = if has_inputs { delay(id) } else { 0 };
+                        for wire in wires.of(subnet, width(subnet)) {
+                            arrive(&mut arrivals, wire, Arrival { time, from: None, component: id, port });
+                        }
+                    }
+                    Some(PortType::Input) if paths.iter().all(|(input, _)| *input != port) => {
+                        let stored = component.stored_value().is_some();
+                        endpoints.extend(wires.of(subnet, width(subnet)).into_iter().map(|wire| (wire, stored)));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        
+        self.propagate(&wires, &mut arrivals, delays)?;
+        
+        let mut subnet_arrivals = BTreeMap::new();
+        for (bit, subnet) in wires.subnets.iter().enumerate() {
+            if let Some(arrival) = arrivals.get(&wires.find(bit)) {
+                let latest = subnet_arrivals.entry(*subnet).or_insert(0);
+                *latest = arrival.time.max(*latest);
+            }
+        }
+        
+        let mut critical = None::<usize>;
+        let mut clock_period = None;
+        for (wire, stored) in endpoints {
+            if let Some(arrival) = arrivals.get(&wire) {
+                if critical.is_none_or(|critical| arrival.time > arrivals[&critical].time) {
+                    critical = Some(wire);
+                }
+                if stored {
+                    clock_period = Some(clock_period.unwrap_or(0).max(arrival.time));
+                }
+            }
+        }
+        let mut critical_path = CriticalPath::default();
+        if let Some(wire) = critical {
+            critical_path.delay = arrivals[&wire].time;
+            let mut next = Some(wire);
+            while let Some(wire) = next {
+                let arrival = arrivals[&wire];
+                critical_path.components.push(arrival.component);
+                critical_path.subnets.push(self.linked_subnet(arrival.component, arrival.port).unwrap());
+                next = arrival.from;
+            }
+            critical_path.components.reverse();
+            critical_path.subnets.reverse();
+        }
+        
+        Ok(Timing { arrivals: subnet_arrivals, critical_path, clock_period })
+    }
+    
+    fn component_delay(&self, id: i32, delays: &HashMap<ComponentId, u32>) -> u64 {
+        delays.get(&self.components[&id].0.id()).copied().unwrap_or_else(|| self.simulation.delay(id)) as u64
+    }
+    
+    /// Carries the arrivals on along every path through the circuit
+    fn propagate(&self, wires: &Wires, arrivals: &mut HashMap<usize, Arrival>, delays: &HashMap<ComponentId, u32>) -> Result<(), LogikError> {
+        // <(subcircuit, wire it reads, output port), how long a change takes through it>
+        let mut through = HashMap::new();
+        for (id, (component, _)) in &self.components {
+            if let Some(subcircuit) = component.subcircuit() {
+                for ((input, output), time) in subcircuit.circuit().pin_delays(subcircuit, delays)? {
+                    if let Some(subnet) = self.linked_subnet(*id, input) {
+                        for wire in wires.of(subnet, self.subnets[&subnet].width() as usize) {
+                            let longest = through.entry((*id, wire, output)).or_insert(0);
+                            *longest = time.max(*longest);
+                        }
+                    }
+                }
+            }
+        }
+        
+        // the wires are visited in topological order, anything left over is part of a loop
+        let mut incoming = HashMap::<usize, usize>::new();
+        let mut outgoing = HashMap::<usize, Vec<_>>::new();
+        for edge in &wires.edges {
+            incoming.entry(edge.0).or_default();
+            *incoming.entry(edge.1).or_default() += 1;
+            outgoing.entry(edge.0).or_default().push(*edge);
+        }
+        let mut ready = incoming.iter().filter(|(_, count)| **count == 0).map(|(wire, _)| *wire).collect::<Vec<_>>();
+        ready.sort_unstable();
+        let mut ready = VecDeque::from(ready);
+        while let Some(wire) = ready.pop_front() {
+            for (from, to, component, port) in outgoing.remove(&wire).unwrap_or_default() {
+                if let Some(time) = arrivals.get(&from).map(|arrival| arrival.time) {
+                    let delay = self.component_delay(component, delays) + through.get(&(component, from, port)).copied().unwrap_or(0);
+                    arrive(arrivals, to, Arrival { time: time + delay, from: Some(from), component, port });
+                }
+                let count = incoming.get_mut(&to).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push_back(to);
+                }
+            }
+        }
+        let looping = (0..wires.subnets.len())
+            .filter(|bit| incoming.get(&wires.find(*bit)).is_some_and(|count| *count > 0))
+            .map(|bit| wires.subnets[bit])
+            .min();
+        match looping {
+            Some(subnet) => Err(LogikError::CombinationalLoop(subnet)),
+            None => Ok(()),
+        }
+    }
+    
+    /// How long a change takes from every input of a subcircuit to every output it reaches, by
+    /// their ports. This starts with the delay of the input pin, just like in the simulation
+    fn pin_delays(&self, subcircuit: &Subcircuit, delays: &HashMap<ComponentId, u32>) -> Result<HashMap<(usize, usize), u64>, LogikError> {
+        let wires = self.wires();
+        let pin_wires = |port: usize| subcircuit.pin(port)
+            .and_then(|pin| self.linked_subnet(pin, 0))
+            .map(|subnet| wires.of(subnet, self.subnets[&subnet].width() as usize))
+            .unwrap_or_default();
+        
+        let mut pin_delays = HashMap::new();
+        for (input, output) in combinational_paths(subcircuit) {
+            let pin = subcircuit.pin(input).unwrap();
+            let mut arrivals = HashMap::new();
+            for wire in pin_wires(input) {
+                arrive(&mut arrivals, wire, Arrival { time: self.component_delay(pin, delays), from: None, component: pin, port: 0 });
+            }
+            self.propagate(&wires, &mut arrivals, delays)?;
+            if let Some(time) = pin_wires(output).iter().filter_map(|wire| arrivals.get(wire)).map(|arrival| arrival.time).max() {
+                pin_delays.insert((input, output), time);
+            }
+        }
+        Ok(pin_delays)
+    }
+}
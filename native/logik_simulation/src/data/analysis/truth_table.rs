@@ -0,0 +1,188 @@
+use crate::data::{Data, EdgeDirection};
+use crate::data::component::ComponentId;
+use crate::data::component::parameter::{set_parameter, ParameterValue};
+use crate::data::error::LogikError;
+use crate::data::subnet::{SubnetState, Value};
+use std::collections::HashMap;
+
+/// The most input bits a truth table can have, it has a row for every combination of them
+pub(crate) const MAX_TABLE_INPUTS: u32 = 16;
+
+/// What the outputs of a circuit are for every combination of its inputs. The first input is the
+/// most significant one, so the rows count up from all inputs off to all of them on
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct TruthTable {
+    pub(crate) inputs: Vec<String>,
+    pub(crate) outputs: Vec<String>,
+    pub(crate) rows: Vec<(Vec<Value>, Vec<Value>)>, // <input values, output values>
+}
+
+impl TruthTable {
+    /// Writes a header with the names of the columns and a line for every row
+    pub(crate) fn to_csv(&self) -> String {
+        let mut csv = self.header().join(",");
+        csv.push('\n');
+        for row in self.cells() {
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+    
+    /// Writes a table with the columns padded to the same width
+    pub(crate) fn to_markdown(&self) -> String {
+        let header = self.header();
+        let cells = self.cells();
+        let widths = (0..header.len())
+            .map(|column| cells.iter().map(|row| row[column].len()).chain(Some(header[column].len())).max().unwrap_or(1).max(3))
+            .collect::<Vec<_>>();
+        let line = |row: &[String]| {
+            let cells = row.iter().zip(&widths).map(|(cell, width)| format!(" {:<1$} ", cell, width)).collect::<Vec<_>>();
+            format!("|{}|\n", cells.join("|"))
+        };
+        
+        let mut markdown = line(&header);
+        let rules = widths.iter().map(|width| format!("-{}-", "-".repeat(*width))).collect::<Vec<_>>();
+        markdown.push_str(&format!("|{}|\n", rules.join("|")));
+        for row in &cells {
+            markdown.push_str(&line(row));
+        }
+        markdown
+    }
+    
+    fn header(&self) -> Vec<String> {
+        self.inputs.iter().chain(&self.outputs).cloned().collect()
+    }
+    
+    fn cells(&self) -> Vec<Vec<String>> {
+        self.rows.iter()
+            .map(|(inputs, outputs)| inputs.iter().chain(outputs).map(bits).collect())
+            .collect()
+    }
+}
+
+/// The bits of a value with the most significant one first, `z` is floating and `x` an error
+fn bits(value: &Value) -> String {
+    value.bits()
+        .iter()
+        .rev()
+        .map(|bit| match bit {
+            SubnetState::Off => '0',
+            SubnetState::On => '1',
+            SubnetState::Floating => 'z',
+            SubnetState::Error => 'x',
+        })
+        .collect()
+}
+
+impl Data {
+    /// Drives every combination of values onto the `inputs` and records what the `outputs` settle
+    /// to. The inputs have to be driven by `Input` or `Constant` components, which are set for
+    /// every row. Whatever the circuit was doing is restored afterwards, including the state of
+    /// stateful components, pending changes and a recording, which doesn't see the rows
+    pub(crate) fn truth_table(&mut self, inputs: &[i32], outputs: &[i32]) -> Result<TruthTable, LogikError> {
+        let mut drivers = Vec::with_capacity(inputs.len());
+        for subnet in inputs {
+            let width = self.subnets.get(subnet).ok_or(LogikError::UnknownSubnet(*subnet))?.width();
+            let mut ids = self.subnet_edges.get(subnet).into_iter().flatten()
+                .filter(|edge| edge.direction == EdgeDirection::ToSubnet)
+                .map(|edge| edge.component)
+                .filter(|id| matches!(self.components[id].0.id(), ComponentId::Input | ComponentId::Constant))
+                .collect::<Vec<_>>();
+            if ids.is_empty() {
+                return Err(LogikError::NotAnInput(*subnet));
+            }
+            ids.sort_unstable();
+            drivers.push((ids, width));
+        }
+        for subnet in outputs {
+            self.subnets.get(subnet).ok_or(LogikError::UnknownSubnet(*subnet))?;
+        }
+        let bits = drivers.iter().map(|(_, width)| *width as u32).sum::<u32>();
+        if bits > MAX_TABLE_INPUTS {
+            return Err(LogikError::TooManyInputs(bits));
+        }
+        
+        let trace = self.simulation.trace.take();
+        let simulation = self.simulation.clone();
+        let subnets = self.subnets.clone();
+        let ports = self.components.iter().map(|(id, (_, ports))| (*id, ports.clone())).collect::<HashMap<_, _>>();
+        let states = self.save_states();
+        let constants = drivers.iter()
+            .flat_map(|(ids, _)| ids)
+            .filter_map(|id| Some((*id, self.parameter(*id, "value").ok()?.value)))
+            .collect::<Vec<_>>();
+        self.simulation.stepping = false;
+        
+        let mut rows = Vec::with_capacity(1 << bits);
+        for combination in 0..1u64 << bits {
+            let mut shift = bits;
+            let mut values = Vec::with_capacity(drivers.len());
+            for (ids, width) in &drivers {
+                shift -= *width as u32;
+                let value = Value::from_u64(combination >> shift, *width);
+                for id in ids {
+                    self.set_driver(*id, value.clone());
+                }
+                values.push(value);
+            }
+            self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+            let results = outputs.iter().map(|subnet| self.subnets[subnet].val()).collect();
+            rows.push((values, results));
+        }
+        
+        for (ids, _) in &drivers {
+            for id in ids {
+                let component = &self.components[id].0;
+                if component.id() == ComponentId::Input {
+                    component.drive(ports[id][0].clone());
+                }
+            }
+        }
+        for (id, value) in constants {
+            set_parameter(self.components.get_mut(&id).unwrap().0.as_mut(), "value", value)?;
+        }
+        self.load_states(&states);
+        for (id, driving) in ports {
+            self.components.get_mut(&id).unwrap().1 = driving;
+        }
+        self.subnets = subnets;
+        self.simulation = simulation;
+        self.simulation.trace = trace;
+        
+        let table = TruthTable {
+            inputs: inputs.iter().map(|subnet| self.column_name(*subnet, ComponentId::Input)).collect(),
+            outputs: outputs.iter().map(|subnet| self.column_name(*subnet, ComponentId::Output)).collect(),
+            rows,
+        };
+        Ok(table)
+    }
+    
+    /// Makes an `Input` or `Constant` drive `value` and simulates it
+    fn set_driver(&mut self, id: i32, value: Value) {
+        let component = &mut self.components.get_mut(&id).unwrap().0;
+        if component.id() == ComponentId::Input {
+            component.drive(value);
+        } else {
+            set_parameter(component.as_mut(), "value", ParameterValue::Bits(value)).unwrap();
+        }
+        self.simulation.update_component(id, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+    }
+    
+    /// The label of the first pin of the given kind on the subnet, or the subnet id without one
+    fn column_name(&self, subnet: i32, kind: ComponentId) -> String {
+        let mut ids = self.subnet_edges.get(&subnet).into_iter().flatten().map(|edge| edge.component).collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.into_iter()
+            .filter(|id| self.components[id].0.id() == kind)
+            .filter_map(|id| match self.parameter(id, "label") {
+                Ok(parameter) => match parameter.value {
+                    ParameterValue::String(label) if !label.is_empty() => Some(label),
+                    _ => None,
+                },
+                Err(_) => None,
+            })
+            .next()
+            .unwrap_or_else(|| format!("subnet {}", subnet))
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use crate::data::component::{StateChange, PortType, Component, ComponentId};
+use crate::data::component::parameter::{Parameter, ParameterValue};
+use crate::data::error::LogikError;
+use crate::data::subnet::{SubnetState, Value, MAX_WIDTH};
+use crate::map;
+
+/// The numbers on the operand ports, unless one of their bits isn't known
+fn operands(data: &HashMap<usize, StateChange>, ports: &[usize]) -> Option<Vec<u64>> {
+    ports.iter()
+        .map(|port| data.get(port).and_then(|e| e.current.to_u64()))
+        .collect()
+}
+
+/// A carry or borrow coming in, which counts as 0 when it isn't driven at all
+fn carry_in(data: &HashMap<usize, StateChange>, port: usize) -> Option<u64> {
+    match data.get(&port) {
+        Some(carry) if carry.current.bits().iter().all(|bit| *bit == SubnetState::Floating) => Some(0),
+        Some(carry) => carry.current.to_u64(),
+        None => Some(0),
+    }
+}
+
+fn bit(on: bool) -> Value {
+    if on { SubnetState::On } else { SubnetState::Off }.into()
+}
+
+/// Reads the lowest `width` bits of a number as a two's complement number
+fn signed(number: u64, width: u8) -> i64 {
+    let shift = 64 - width as u32;
+    ((number << shift) as i64) >> shift
+}
+
+fn width_parameter(width: u8) -> Parameter {
+    Parameter::int("width", 1, MAX_WIDTH as i64, width as i64)
+}
+
+fn set_width(width: &mut u8, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+    match (name, value) {
+        ("width", ParameterValue::Int(value)) => *width = value as u8,
+        _ => return Err(LogikError::UnknownParameter(String::from(name))),
+    }
+    Ok(())
+}
+
+/// Every port up to `inputs` is an input and the rest up to `ports` are outputs
+fn operator_port_type(inputs: usize, ports: usize, port: usize) -> Option<PortType> {
+    match port {
+        p if p < inputs => Some(PortType::Input),
+        p if p < ports => Some(PortType::Output),
+        _ => None,
+    }
+}
+
+/// Adds two numbers and a carry. Ports are 0 and 1 for the numbers, 2 for the carry in, 3 for the
+/// sum and 4 for the carry out
+#[derive(Debug)]
+pub(crate) struct Adder {
+    width: u8,
+}
+
+impl Component for Adder {
+    fn id(&self) -> ComponentId {
+        ComponentId::Adder
+    }
+    
+    fn ports(&self) -> usize {
+        5
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        operator_port_type(3, 5, port)
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 | 1 | 3 => Some(self.width),
+            2 | 4 => Some(1),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        match (operands(&data, &[0, 1]), carry_in(&data, 2)) {
+            (Some(numbers), Some(carry)) => {
+                let sum = numbers[0] as u128 + numbers[1] as u128 + carry as u128;
+                map!(
+                    3 => Value::from_u64(sum as u64, self.width),
+                    4 => bit(sum >> self.width != 0)
+                )
+            }
+            _ => map!(3 => Value::error(self.width), 4 => Value::error(1)),
+        }
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![width_parameter(self.width)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_width(&mut self.width, name, value)
+    }
+}
+
+impl Adder {
+    pub(crate) fn new() -> Self {
+        Self { width: 8 }
+    }
+}
+
+/// Subtracts the second number and a borrow from the first one. Ports are 0 and 1 for the numbers,
+/// 2 for the borrow in, 3 for the difference and 4 for the borrow out
+#[derive(Debug)]
+pub(crate) struct Subtractor {
+    width: u8,
+}
+
+impl Component for Subtractor {
+    fn id(&self) -> ComponentId {
+        ComponentId::Subtractor
+    }
+    
+    fn ports(&self) -> usize {
+        5
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        operator_port_type(3, 5, port)
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 | 1 | 3 => Some(self.width),
+            2 | 4 => Some(1),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        match (operands(&data, &[0, 1]), carry_in(&data, 2)) {
+            (Some(numbers), Some(borrow)) => {
+                let subtrahend = numbers[1] as u128 + borrow as u128;
+                let difference = (numbers[0] as u128).wrapping_sub(subtrahend);
+                map!(
+                    3 => Value::from_u64(difference as u64, self.width),
+                    4 => bit((numbers[0] as u128) < subtrahend)
+                )
+            }
+            _ => map!(3 => Value::error(self.width), 4 => Value::error(1)),
+        }
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![width_parameter(self.width)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_width(&mut self.width, name, value)
+    }
+}
+
+impl Subtractor {
+    pub(crate) fn new() -> Self {
+        Self { width: 8 }
+    }
+}
+
+/// Multiplies two numbers and adds a carry to the product. Ports are 0 and 1 for the numbers, 2 for
+/// the carry in, 3 for the lower half of the product and 4 for the upper half
+#[derive(Debug)]
+pub(crate) struct Multiplier {
+    width: u8,
+}
+
+impl Component for Multiplier {
+    fn id(&self) -> ComponentId {
+        ComponentId::Multiplier
+    }
+    
+    fn ports(&self) -> usize {
+        5
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        operator_port_type(3, 5, port)
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        self.port_type(port).map(|_| self.width)
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        match (operands(&data, &[0, 1]), carry_in(&data, 2)) {
+            (Some(numbers), Some(carry)) => {
+                let product = numbers[0] as u128 * numbers[1] as u128 + carry as u128;
+                map!(
+                    3 => Value::from_u64(product as u64, self.width),
+                    4 => Value::from_u64((product >> self.width) as u64, self.width)
+                )
+            }
+            _ => map!(3 => Value::error(self.width), 4 => Value::error(self.width)),
+        }
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![width_parameter(self.width)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_width(&mut self.width, name, value)
+    }
+}
+
+impl Multiplier {
+    pub(crate) fn new() -> Self {
+        Self { width: 8 }
+    }
+}
+
+/// Divides the first number by the second one. Ports are 0 for the dividend, 1 for the divisor, 2
+/// for the quotient and 3 for the remainder. Dividing by zero is an error
+#[derive(Debug)]
+pub(crate) struct Divider {
+    width: u8,
+}
+
+impl Component for Divider {
+    fn id(&self) -> ComponentId {
+        ComponentId::Divider
+    }
+    
+    fn ports(&self) -> usize {
+        4
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        operator_port_type(2, 4, port)
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        self.port_type(port).map(|_| self.width)
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        match operands(&data, &[0, 1]) {
+            Some(numbers) if numbers[1] != 0 => map!(
+                2 => Value::from_u64(numbers[0] / numbers[1], self.width),
+                3 => Value::from_u64(numbers[0] % numbers[1], self.width)
+            ),
+            _ => map!(2 => Value::error(self.width), 3 => Value::error(self.width)),
+        }
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![width_parameter(self.width)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_width(&mut self.width, name, value)
+    }
+}
+
+impl Divider {
+    pub(crate) fn new() -> Self {
+        Self { width: 8 }
+    }
+}
+
+/// Compares two numbers, as two's complement numbers when `signed` is set. Ports are 0 and 1 for
+/// the numbers and 2, 3 and 4 for whether the first one is less than, equal to or greater than the
+/// second one
+#[derive(Debug)]
+pub(crate) struct Comparator {
+    width: u8,
+    signed: bool,
+}
+
+impl Component for Comparator {
+    fn id(&self) -> ComponentId {
+        ComponentId::Comparator
+    }
+    
+    fn ports(&self) -> usize {
+        5
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        operator_port_type(2, 5, port)
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 | 1 => Some(self.width),
+            2..=4 => Some(1),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let ordering = operands(&data, &[0, 1]).map(|numbers| if self.signed {
+            signed(numbers[0], self.width).cmp(&signed(numbers[1], self.width))
+        } else {
+            numbers[0].cmp(&numbers[1])
+        });
+        
+        match ordering {
+            Some(ordering) => map!(
+                2 => bit(ordering.is_lt()),
+                3 => bit(ordering.is_eq()),
+                4 => bit(ordering.is_gt())
+            ),
+            None => map!(2 => Value::error(1), 3 => Value::error(1), 4 => Value::error(1)),
+        }
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![width_parameter(self.width), Parameter::bool("signed", self.signed)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("signed", ParameterValue::Bool(signed)) => self.signed = signed,
+            (name, value) => return set_width(&mut self.width, name, value),
+        }
+        Ok(())
+    }
+}
+
+impl Comparator {
+    pub(crate) fn new() -> Self {
+        Self { width: 8, signed: false }
+    }
+}
@@ -1,12 +1,28 @@
 use std::collections::HashMap;
-use crate::data::component::{StateChange, PortType, Component};
-use crate::data::subnet::SubnetState;
+use std::cell::{Cell, RefCell};
+use crate::data::component::{StateChange, PortType, Component, ComponentId, Strength};
+use crate::data::component::parameter::{Parameter, ParameterValue};
+use crate::data::error::LogikError;
+use crate::data::subnet::{SubnetState, Value, MAX_WIDTH};
 use crate::{map, port_or_default};
 
+/// The parameters of the pins of a circuit, the label names the pin when the circuit is exported
+fn pin_parameters(label: &str, width: u8) -> Vec<Parameter> {
+    vec![Parameter::string("label", label), Parameter::int("width", 1, MAX_WIDTH as i64, width as i64)]
+}
+
+/// The output of a circuit, a subcircuit drives the value it reads on the matching port
 #[derive(Debug)]
-pub(crate) struct OutputGate {}
+pub(crate) struct OutputGate {
+    label: String,
+    width: u8,
+}
 
 impl Component for OutputGate {
+    fn id(&self) -> ComponentId {
+        ComponentId::Output
+    }
+    
     fn ports(&self) -> usize {
         1
     }
@@ -18,17 +34,47 @@ impl Component for OutputGate {
         }
     }
     
-    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn port_width(&self, port: usize) -> Option<u8> {
+        self.port_type(port).map(|_| self.width)
+    }
+    
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         map!()
     }
 
+    fn parameters(&self) -> Vec<Parameter> {
+        pin_parameters(&self.label, self.width)
+    }
+
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("label", ParameterValue::String(label)) => self.label = label,
+            ("width", ParameterValue::Int(width)) => self.width = width as u8,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        Ok(())
+    }
+}
 
+impl OutputGate {
+    pub(crate) fn new() -> Self {
+        Self { label: String::new(), width: 1 }
+    }
 }
 
+/// The input of a circuit, it drives a constant high value unless the circuit is used as a
+/// subcircuit, in which case it drives whatever the instance is given on the matching port
 #[derive(Debug)]
-pub(crate) struct InputGate {}
+pub(crate) struct InputGate {
+    label: String,
+    value: RefCell<Value>,
+}
 
 impl Component for InputGate {
+    fn id(&self) -> ComponentId {
+        ComponentId::Input
+    }
+    
     fn ports(&self) -> usize {
         1
     }
@@ -40,8 +86,35 @@ impl Component for InputGate {
         }
     }
     
-    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
-        map!(0 => SubnetState::On)
+    fn port_width(&self, port: usize) -> Option<u8> {
+        self.port_type(port).map(|_| self.value.borrow().width())
+    }
+    
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(0 => self.value.borrow().clone())
+    }
+    
+    fn drive(&self, value: Value) {
+        *self.value.borrow_mut() = value;
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        pin_parameters(&self.label, self.value.borrow().width())
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("label", ParameterValue::String(label)) => self.label = label,
+            ("width", ParameterValue::Int(width)) => *self.value.borrow_mut() = vec![SubnetState::On; width as usize].into(),
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        Ok(())
+    }
+}
+
+impl InputGate {
+    pub(crate) fn new() -> Self {
+        Self { label: String::new(), value: RefCell::new(SubnetState::On.into()) }
     }
 }
 
@@ -49,6 +122,10 @@ impl Component for InputGate {
 pub(crate) struct Buffer {}
 
 impl Component for Buffer {
+    fn id(&self) -> ComponentId {
+        ComponentId::Buffer
+    }
+    
     fn ports(&self) -> usize {
         2
     }
@@ -61,10 +138,10 @@ impl Component for Buffer {
         }
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let input = port_or_default!(data, 0);
         
-        map!(1 => input)
+        map!(1 => input.into())
     }
 }
 
@@ -72,6 +149,10 @@ impl Component for Buffer {
 pub(crate) struct NOT {}
 
 impl Component for NOT {
+    fn id(&self) -> ComponentId {
+        ComponentId::Not
+    }
+    
     fn ports(&self) -> usize {
         2
     }
@@ -84,211 +165,328 @@ impl Component for NOT {
         }
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let input = port_or_default!(data, 0);
         
         map!(1 => match input {
             SubnetState::Off => SubnetState::On,
             SubnetState::On => SubnetState::Off,
             _ => SubnetState::Error
-        })
+        }.into())
+    }
+}
+
+/// The fewest inputs a gate can have
+pub(crate) const MIN_INPUTS: usize = 2;
+/// The most inputs a gate can have
+pub(crate) const MAX_INPUTS: usize = 32;
+
+/// Every port but the last one of a gate is an input
+fn gate_port_type(inputs: usize, port: usize) -> Option<PortType> {
+    match port {
+        p if p < inputs => Some(PortType::Input),
+        p if p == inputs => Some(PortType::Output),
+        _ => None,
+    }
+}
+
+fn gate_inputs(data: &HashMap<usize, StateChange>, inputs: usize) -> Vec<SubnetState> {
+    (0..inputs).map(|port| port_or_default!(data, port)).collect()
+}
+
+fn inputs_parameter(inputs: usize) -> Parameter {
+    Parameter::int("inputs", MIN_INPUTS as i64, MAX_INPUTS as i64, inputs as i64)
+}
+
+/// Off for odd parity and on for one-hot
+fn one_hot_parameter(one_hot: bool) -> Parameter {
+    Parameter::bool("one_hot", one_hot)
+}
+
+fn set_inputs(inputs: &mut usize, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+    match (name, value) {
+        ("inputs", ParameterValue::Int(value)) => *inputs = value as usize,
+        _ => return Err(LogikError::UnknownParameter(String::from(name))),
+    }
+    Ok(())
+}
+
+/// Off as soon as one input is off
+fn and(inputs: &[SubnetState]) -> SubnetState {
+    if inputs.contains(&SubnetState::Off) {
+        SubnetState::Off
+    } else if inputs.iter().all(|input| *input == SubnetState::On) {
+        SubnetState::On
+    } else {
+        SubnetState::Error
+    }
+}
+
+/// On as soon as one input is on
+fn or(inputs: &[SubnetState]) -> SubnetState {
+    if inputs.contains(&SubnetState::On) {
+        SubnetState::On
+    } else if inputs.iter().all(|input| *input == SubnetState::Off) {
+        SubnetState::Off
+    } else {
+        SubnetState::Error
+    }
+}
+
+/// On for an odd number of inputs that are on, or for exactly one of them when `one_hot` is set
+fn xor(inputs: &[SubnetState], one_hot: bool) -> SubnetState {
+    if inputs.iter().any(|input| *input == SubnetState::Floating || *input == SubnetState::Error) {
+        return SubnetState::Error;
+    }
+    
+    let on = inputs.iter().filter(|input| **input == SubnetState::On).count();
+    if (one_hot && on == 1) || (!one_hot && on % 2 == 1) {
+        SubnetState::On
+    } else {
+        SubnetState::Off
+    }
+}
+
+fn invert(state: SubnetState) -> SubnetState {
+    match state {
+        SubnetState::Off => SubnetState::On,
+        SubnetState::On => SubnetState::Off,
+        _ => SubnetState::Error,
     }
 }
 
-/// Placeholder for now
 #[derive(Debug)]
-pub(crate) struct AND {}
+pub(crate) struct AND {
+    inputs: usize,
+}
 
 impl Component for AND {
+    fn id(&self) -> ComponentId {
+        ComponentId::And
+    }
+    
     fn ports(&self) -> usize {
-        3
+        self.inputs + 1
     }
     
     fn port_type(&self, port: usize) -> Option<PortType> {
-        match port {
-            0 | 1 => Some(PortType::Input),
-            2 => Some(PortType::Output),
-            _ => None,
-        }
+        gate_port_type(self.inputs, port)
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
-        let a = port_or_default!(data, 0);
-        let b = port_or_default!(data, 1);
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(self.inputs => and(&gate_inputs(&data, self.inputs)).into())
+    }
         
-        map!(
-                2 =>
-            if a == SubnetState::Off || b == SubnetState::Off {
-                SubnetState::Off
-            } else if b == SubnetState::On && b == SubnetState::On {
-                SubnetState::On
-            } else {
-                SubnetState::Error
-            }
-        )
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![inputs_parameter(self.inputs)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_inputs(&mut self.inputs, name, value)
+    }
+}
+
+impl AND {
+    pub(crate) fn new(inputs: usize) -> Self {
+        Self { inputs }
     }
 }
 
 #[derive(Debug)]
-pub(crate) struct NAND {}
+pub(crate) struct NAND {
+    inputs: usize,
+}
 
 impl Component for NAND {
+    fn id(&self) -> ComponentId {
+        ComponentId::Nand
+    }
+    
     fn ports(&self) -> usize {
-        3
+        self.inputs + 1
     }
     
     fn port_type(&self, port: usize) -> Option<PortType> {
-        match port {
-            0 | 1 => Some(PortType::Input),
-            2 => Some(PortType::Output),
-            _ => None,
-        }
+        gate_port_type(self.inputs, port)
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
-        let a = port_or_default!(data, 0);
-        let b = port_or_default!(data, 1);
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(self.inputs => invert(and(&gate_inputs(&data, self.inputs))).into())
+    }
         
-        map!( 2 =>
-            if a == SubnetState::Off || b == SubnetState::Off {
-                SubnetState::On
-            } else if a == SubnetState::On && b == SubnetState::On {
-                SubnetState::Off
-            } else {
-                SubnetState::Error
-            }
-        )
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![inputs_parameter(self.inputs)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_inputs(&mut self.inputs, name, value)
+    }
+}
+
+impl NAND {
+    pub(crate) fn new(inputs: usize) -> Self {
+        Self { inputs }
     }
 }
 
 #[derive(Debug)]
-pub(crate) struct OR {}
+pub(crate) struct OR {
+    inputs: usize,
+}
 
 impl Component for OR {
+    fn id(&self) -> ComponentId {
+        ComponentId::Or
+    }
+    
     fn ports(&self) -> usize {
-        3
+        self.inputs + 1
     }
     
     fn port_type(&self, port: usize) -> Option<PortType> {
-        match port {
-            0 | 1 => Some(PortType::Input),
-            2 => Some(PortType::Output),
-            _ => None,
-        }
+        gate_port_type(self.inputs, port)
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
-        let a = port_or_default!(data, 0);
-        let b = port_or_default!(data, 1);
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(self.inputs => or(&gate_inputs(&data, self.inputs)).into())
+    }
         
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![inputs_parameter(self.inputs)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_inputs(&mut self.inputs, name, value)
+    }
+}
         
-        map!(2 =>
-            if a == SubnetState::On || b == SubnetState::On {
-                SubnetState::On
-            } else if a == SubnetState::Off && b == SubnetState::Off {
-                SubnetState::Off
-            } else {
-                SubnetState::Error
-            }
-        )
+impl OR {
+    pub(crate) fn new(inputs: usize) -> Self {
+        Self { inputs }
     }
 }
 
 #[derive(Debug)]
-pub(crate) struct NOR {}
+pub(crate) struct NOR {
+    inputs: usize,
+}
 
 impl Component for NOR {
+    fn id(&self) -> ComponentId {
+        ComponentId::Nor
+    }
+    
     fn ports(&self) -> usize {
-        3
+        self.inputs + 1
     }
     
     fn port_type(&self, port: usize) -> Option<PortType> {
-        match port {
-            0 | 1 => Some(PortType::Input),
-            2 => Some(PortType::Output),
-            _ => None,
-        }
+        gate_port_type(self.inputs, port)
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
-        let a = port_or_default!(data, 0);
-        let b = port_or_default!(data, 1);
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(self.inputs => invert(or(&gate_inputs(&data, self.inputs))).into())
+    }
         
-        map!(2 =>
-            if a == SubnetState::On || b == SubnetState::On {
-                SubnetState::Off
-            } else if a == SubnetState::Off && b == SubnetState::Off {
-                SubnetState::On
-            } else {
-                SubnetState::Error
-            }
-        )
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![inputs_parameter(self.inputs)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_inputs(&mut self.inputs, name, value)
     }
 }
 
+impl NOR {
+    pub(crate) fn new(inputs: usize) -> Self {
+        Self { inputs }
+    }
+}
+
+/// With more than two inputs it's either on for an odd number of inputs that are on, or for
+/// exactly one of them in one-hot mode
 #[derive(Debug)]
-pub(crate) struct XOR {}
+pub(crate) struct XOR {
+    inputs: usize,
+    one_hot: bool,
+}
 
 impl Component for XOR {
+    fn id(&self) -> ComponentId {
+        ComponentId::Xor
+    }
+    
     fn ports(&self) -> usize {
-        3
+        self.inputs + 1
     }
     
     fn port_type(&self, port: usize) -> Option<PortType> {
-        match port {
-            0 | 1 => Some(PortType::Input),
-            2 => Some(PortType::Output),
-            _ => None,
-        }
+        gate_port_type(self.inputs, port)
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
-        let a = port_or_default!(data, 0);
-        let b = port_or_default!(data, 1);
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(self.inputs => xor(&gate_inputs(&data, self.inputs), self.one_hot).into())
+    }
         
-        map!(2 =>
-            if a == SubnetState::Floating || a == SubnetState::Error ||
-                b == SubnetState::Floating || b == SubnetState::Error {
-                SubnetState::Error
-            } else if a == b {
-                SubnetState::Off
-            } else {
-                SubnetState::On
-            }
-        )
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![inputs_parameter(self.inputs), one_hot_parameter(self.one_hot)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("one_hot", ParameterValue::Bool(one_hot)) => self.one_hot = one_hot,
+            (name, value) => return set_inputs(&mut self.inputs, name, value),
+        }
+        Ok(())
+    }
+}
+
+impl XOR {
+    pub(crate) fn new(inputs: usize, one_hot: bool) -> Self {
+        Self { inputs, one_hot }
     }
 }
 
+/// The inverse of `XOR` in either mode
 #[derive(Debug)]
-pub(crate) struct XNOR {}
+pub(crate) struct XNOR {
+    inputs: usize,
+    one_hot: bool,
+}
 
 impl Component for XNOR {
+    fn id(&self) -> ComponentId {
+        ComponentId::Xnor
+    }
+    
     fn ports(&self) -> usize {
-        3
+        self.inputs + 1
     }
     
     fn port_type(&self, port: usize) -> Option<PortType> {
-        match port {
-            0 | 1 => Some(PortType::Input),
-            2 => Some(PortType::Output),
-            _ => None,
-        }
+        gate_port_type(self.inputs, port)
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
-        let a = port_or_default!(data, 0);
-        let b = port_or_default!(data, 1);
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(self.inputs => invert(xor(&gate_inputs(&data, self.inputs), self.one_hot)).into())
+    }
         
-        map!(2 =>
-            if a == SubnetState::Floating || a == SubnetState::Error ||
-                b == SubnetState::Floating || b == SubnetState::Error {
-                SubnetState::Error
-            } else if a == b {
-                SubnetState::On
-            } else {
-                SubnetState::Off
-            }
-        )
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![inputs_parameter(self.inputs), one_hot_parameter(self.one_hot)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("one_hot", ParameterValue::Bool(one_hot)) => self.one_hot = one_hot,
+            (name, value) => return set_inputs(&mut self.inputs, name, value),
+        }
+        Ok(())
+    }
+}
+
+impl XNOR {
+    pub(crate) fn new(inputs: usize, one_hot: bool) -> Self {
+        Self { inputs, one_hot }
     }
 }
 
@@ -296,6 +494,10 @@ impl Component for XNOR {
 pub(crate) struct TriBuffer {}
 
 impl Component for TriBuffer {
+    fn id(&self) -> ComponentId {
+        ComponentId::TriStateBuffer
+    }
+    
     fn ports(&self) -> usize {
         3 // 0 is input, 1 is enable, 2 is output
     }
@@ -308,14 +510,14 @@ impl Component for TriBuffer {
         }
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let input = port_or_default!(data, 0);
         let enable = port_or_default!(data, 1);
         
         if enable.truthy() {
-            map!(2 => input)
+            map!(2 => input.into())
         } else {
-            map!(2 => SubnetState::Floating)
+            map!(2 => SubnetState::Floating.into())
         }
     }
 }
@@ -324,6 +526,10 @@ impl Component for TriBuffer {
 pub(crate) struct TriInverter {}
 
 impl Component for TriInverter {
+    fn id(&self) -> ComponentId {
+        ComponentId::TriStateInverter
+    }
+    
     fn ports(&self) -> usize {
         3 // 0 is input, 1 is enable, 2 is output
     }
@@ -336,7 +542,7 @@ impl Component for TriInverter {
         }
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let input = port_or_default!(data, 0);
         let enable = port_or_default!(data, 1);
         
@@ -346,17 +552,100 @@ impl Component for TriInverter {
                 SubnetState::Off => SubnetState::On,
                 _ => SubnetState::Error,
             };
-            map!(2 => input)
+            map!(2 => input.into())
         } else {
-            map!(2 => SubnetState::Floating)
+            map!(2 => SubnetState::Floating.into())
+        }
+    }
+}
+
+/// Connects the subnets on ports 0 and 1 while port 2 is on, values pass through it either way.
+/// While it isn't known whether the gate is on the bits that would pass are errors
+#[derive(Debug)]
+pub(crate) struct TransmissionGate {
+    width: u8,
+    enable: Cell<SubnetState>,
+}
+
+impl Component for TransmissionGate {
+    fn id(&self) -> ComponentId {
+        ComponentId::TransmissionGate
+    }
+    
+    fn ports(&self) -> usize {
+        3
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            0 | 1 => Some(PortType::Bidirectional),
+            2 => Some(PortType::Input),
+            _ => None,
         }
     }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 | 1 => Some(self.width),
+            2 => Some(1),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let enable = port_or_default!(data, 2);
+        self.enable.set(enable);
+        let pass = |from: usize| {
+            let value = data.get(&from).map(|e| e.current.clone()).unwrap_or_else(|| Value::floating(self.width));
+            match enable {
+                SubnetState::On => value,
+                SubnetState::Off => Value::floating(self.width),
+                _ => value.bits()
+                    .iter()
+                    .map(|bit| if *bit == SubnetState::Floating { SubnetState::Floating } else { SubnetState::Error })
+                    .collect::<Vec<_>>()
+                    .into(),
+            }
+        };
+        
+        map!(0 => pass(1), 1 => pass(0))
+    }
+    
+    fn joined_bits(&self) -> Vec<((usize, usize), (usize, usize))> {
+        (0..self.width as usize).map(|bit| ((0, bit), (1, bit))).collect()
+    }
+    
+    fn joining(&self) -> SubnetState {
+        self.enable.get()
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::int("width", 1, MAX_WIDTH as i64, self.width as i64)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("width", ParameterValue::Int(width)) => self.width = width as u8,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        Ok(())
+    }
+}
+
+impl TransmissionGate {
+    pub(crate) fn new() -> Self {
+        Self { width: 1, enable: Cell::new(SubnetState::Floating) }
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct Probe {}
 
 impl Component for Probe {
+    fn id(&self) -> ComponentId {
+        ComponentId::Probe
+    }
+    
     fn ports(&self) -> usize {
         1
     }
@@ -368,7 +657,7 @@ impl Component for Probe {
         }
     }
     
-    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         map!()
     }
 }
@@ -377,6 +666,10 @@ impl Component for Probe {
 pub(crate) struct LED {}
 
 impl Component for LED {
+    fn id(&self) -> ComponentId {
+        ComponentId::LED
+    }
+    
     fn ports(&self) -> usize {
         1
     }
@@ -388,7 +681,201 @@ impl Component for LED {
         }
     }
     
-    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         map!()
     }
-}
\ No newline at end of file
+}
+/// Splits a bus into branches and joins them back together, every port is bidirectional so values
+/// flow either way. Port 0 is the bus and ports 1 to `fan_out` are the branches. Every bit of the
+/// bus belongs to at most one branch, the bits of a branch are the bits of the bus that belong to
+/// it in the same order
+#[derive(Debug)]
+pub(crate) struct Splitter {
+    fan_out: usize,
+    bits: Vec<Option<usize>>, // the branch of every bit of the bus
+}
+
+impl Component for Splitter {
+    fn id(&self) -> ComponentId {
+        ComponentId::Splitter
+    }
+    
+    fn ports(&self) -> usize {
+        self.fan_out + 1
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            p if p <= self.fan_out => Some(PortType::Bidirectional),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 => Some(self.bits.len() as u8),
+            p if p <= self.fan_out => Some(self.bits.iter().filter(|branch| **branch == Some(p - 1)).count() as u8),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let read = |port: usize| data.get(&port)
+            .map(|e| e.current.clone())
+            .unwrap_or_else(|| Value::floating(self.port_width(port).unwrap()));
+        let bus = read(0);
+        let branches = (1..=self.fan_out).map(read).collect::<Vec<_>>();
+        
+        let mut to_bus = Vec::with_capacity(self.bits.len());
+        let mut to_branches = vec![Vec::new(); self.fan_out];
+        for (bit, branch) in self.bits.iter().enumerate() {
+            match branch {
+                Some(branch) => {
+                    to_bus.push(branches[*branch].get(to_branches[*branch].len()));
+                    to_branches[*branch].push(bus.get(bit));
+                }
+                None => to_bus.push(SubnetState::Floating),
+            }
+        }
+        
+        let mut result = map!(0 => to_bus.into());
+        result.extend(to_branches.into_iter()
+            .enumerate()
+            .map(|(branch, bits)| (branch + 1, bits.into())));
+        result
+    }
+    
+    fn joined_bits(&self) -> Vec<((usize, usize), (usize, usize))> {
+        let mut branch_bits = vec![0; self.fan_out];
+        let mut joined = Vec::with_capacity(self.bits.len());
+        for (bit, branch) in self.bits.iter().enumerate() {
+            if let Some(branch) = branch {
+                joined.push(((0, bit), (branch + 1, branch_bits[*branch])));
+                branch_bits[*branch] += 1;
+            }
+        }
+        joined
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::int("width", 1, MAX_WIDTH as i64, self.bits.len() as i64),
+            Parameter::int("fan_out", 1, self.bits.len() as i64, self.fan_out as i64),
+            Parameter::string("bits", &self.bits_text()),
+        ]
+    }
+    
+    /// Changing the width or the fan out spreads the bits of the bus evenly over the branches again
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("width", ParameterValue::Int(width)) => {
+                self.fan_out = self.fan_out.min(width as usize);
+                self.bits = Splitter::spread(width as usize, self.fan_out);
+            }
+            ("fan_out", ParameterValue::Int(fan_out)) => {
+                self.fan_out = fan_out as usize;
+                self.bits = Splitter::spread(self.bits.len(), self.fan_out);
+            }
+            ("bits", ParameterValue::String(text)) => self.bits = self.parse_bits(&text)?,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        Ok(())
+    }
+}
+
+impl Splitter {
+    pub(crate) fn new() -> Self {
+        Self { fan_out: 2, bits: Splitter::spread(2, 2) }
+    }
+    
+    /// Gives every branch the same number of neighbouring bits, the lowest bits go to branch 0
+    fn spread(width: usize, fan_out: usize) -> Vec<Option<usize>> {
+        (0..width).map(|bit| Some(bit * fan_out / width)).collect()
+    }
+    
+    /// The branch of every bit separated by commas, least significant bit first and `-` for bits
+    /// that don't belong to a branch
+    fn bits_text(&self) -> String {
+        self.bits.iter()
+            .map(|branch| branch.map(|branch| branch.to_string()).unwrap_or_else(|| String::from("-")))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+    
+    fn parse_bits(&self, text: &str) -> Result<Vec<Option<usize>>, LogikError> {
+        let invalid = |reason: String| LogikError::InvalidParameter(reason);
+        let bits = text.split(',')
+            .map(|branch| match branch {
+                "-" => Ok(None),
+                branch => match branch.parse::<usize>() {
+                    Ok(branch) if branch < self.fan_out => Ok(Some(branch)),
+                    _ => Err(invalid(format!("{} is not a branch of the splitter", branch))),
+                },
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        if bits.len() != self.bits.len() {
+            return Err(invalid(format!("bits needs a branch for each of the {} bits, got {}", self.bits.len(), bits.len())));
+        }
+        if let Some(empty) = (0..self.fan_out).find(|branch| !bits.contains(&Some(*branch))) {
+            return Err(invalid(format!("branch {} has no bits", empty)));
+        }
+        Ok(bits)
+    }
+}
+
+/// Weakly pulls every bit of a subnet on or off, so that the subnet has a value while nothing else
+/// drives it
+#[derive(Debug)]
+pub(crate) struct Pull {
+    up: bool,
+    width: u8,
+}
+
+impl Component for Pull {
+    fn id(&self) -> ComponentId {
+        if self.up { ComponentId::PullUp } else { ComponentId::PullDown }
+    }
+    
+    fn ports(&self) -> usize {
+        1
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            0 => Some(PortType::Output),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        self.port_type(port).map(|_| self.width)
+    }
+    
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let state = if self.up { SubnetState::On } else { SubnetState::Off };
+        map!(0 => vec![state; self.width as usize].into())
+    }
+    
+    fn strength(&self) -> Strength {
+        Strength::Weak
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::int("width", 1, MAX_WIDTH as i64, self.width as i64)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("width", ParameterValue::Int(width)) => self.width = width as u8,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        Ok(())
+    }
+}
+
+impl Pull {
+    pub(crate) fn new(up: bool) -> Self {
+        Self { up, width: 1 }
+    }
+}
@@ -0,0 +1,419 @@
+use crate::data::component::{Component, ComponentId, PortType, StateChange};
+use crate::data::component::parameter::{Parameter, ParameterValue};
+use crate::data::error::LogikError;
+use crate::data::subnet::{mask, SubnetState, Value, MAX_WIDTH};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use crate::map;
+
+/// The widest address a memory can have, words that were never written take no space so this is
+/// only limited by what an address can hold
+pub(crate) const MAX_ADDRESS_WIDTH: u8 = 32;
+
+/// The most words that aren't 0 a hex text image can expand to, a short run like `100000000*ff`
+/// would otherwise fill a memory with a 32 bit address
+pub(crate) const MAX_IMAGE_WORDS: u64 = 1 << 20;
+
+/// The formats memory contents can be loaded from, the numbers are what the FFI takes
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(crate) enum ImageFormat {
+    /// Every word in as few bytes as it fits into, least significant byte first
+    Binary = 0,
+    /// Words written in hexadecimal separated by whitespace, as Logisim writes them
+    HexText = 1,
+    IntelHex = 2,
+}
+
+impl TryFrom<i32> for ImageFormat {
+    type Error = LogikError;
+    
+    fn try_from(format: i32) -> Result<Self, Self::Error> {
+        match format {
+            0 => Ok(ImageFormat::Binary),
+            1 => Ok(ImageFormat::HexText),
+            2 => Ok(ImageFormat::IntelHex),
+            _ => Err(LogikError::InvalidImage(format!("{} is not an image format", format))),
+        }
+    }
+}
+
+/// The words of a RAM or ROM, every word that was never written is 0
+#[derive(Debug)]
+pub(crate) struct Memory {
+    address_width: u8,
+    data_width: u8,
+    words: RefCell<BTreeMap<u64, u64>>,
+}
+
+impl Memory {
+    fn new() -> Self {
+        Self { address_width: 8, data_width: 8, words: RefCell::new(BTreeMap::new()) }
+    }
+    
+    pub(crate) fn read(&self, address: u64) -> Result<u64, LogikError> {
+        self.check_address(address)?;
+        Ok(self.words.borrow().get(&address).copied().unwrap_or(0))
+    }
+    
+    /// The words that aren't 0 with their addresses, lowest address first
+    pub(crate) fn words(&self) -> Vec<(u64, u64)> {
+        self.words.borrow()
+            .iter()
+            .filter(|(_, word)| **word != 0)
+            .map(|(address, word)| (*address, *word))
+            .collect()
+    }
+    
+    pub(crate) fn write(&self, address: u64, word: u64) -> Result<(), LogikError> {
+        self.check_address(address)?;
+        if word & !mask(self.data_width) != 0 {
+            return Err(LogikError::WordTooWide { word, width: self.data_width });
+        }
+        
+        self.store(address, word);
+        Ok(())
+    }
+    
+    /// Replaces the whole contents with an image, nothing changes if the image can't be read
+    pub(crate) fn load(&self, image: &[u8], format: ImageFormat) -> Result<(), LogikError> {
+        let words = match format {
+            ImageFormat::Binary => self.words_from_bytes(image.iter().copied().enumerate().map(|(i, byte)| (i as u64, byte))),
+            ImageFormat::HexText => self.words_from_hex_text(image)?,
+            ImageFormat::IntelHex => self.words_from_bytes(intel_hex_bytes(image)?.into_iter()),
+        };
+        
+        if let Some(address) = words.keys().copied().find(|address| self.check_address(*address).is_err()) {
+            return Err(LogikError::InvalidImage(format!("the image doesn't fit, it has a word at {:#x}", address)));
+        }
+        *self.words.borrow_mut() = words.into_iter().filter(|(_, word)| *word != 0).collect();
+        Ok(())
+    }
+    
+    fn check_address(&self, address: u64) -> Result<(), LogikError> {
+        if address > mask(self.address_width) {
+            return Err(LogikError::AddressOutOfRange(address));
+        }
+        Ok(())
+    }
+    
+    fn store(&self, address: u64, word: u64) {
+        let mut words = self.words.borrow_mut();
+        if word == 0 {
+            words.remove(&address);
+        } else {
+            words.insert(address, word);
+        }
+    }
+    
+    /// The value on the data port for the value on the address port, every bit is in the error
+    /// state if the address isn't fully known
+    fn output(&self, address: &Value) -> Value {
+        match address.to_u64().map(|address| self.read(address)) {
+            Some(Ok(word)) => Value::from_u64(word, self.data_width),
+            _ => Value::error(self.data_width),
+        }
+    }
+    
+    fn bytes_per_word(&self) -> u64 {
+        (self.data_width as u64).div_ceil(8)
+    }
+    
+    /// Groups bytes at byte addresses into words, bits that don't fit into a word are dropped
+    fn words_from_bytes(&self, bytes: impl Iterator<Item = (u64, u8)>) -> BTreeMap<u64, u64> {
+        let size = self.bytes_per_word();
+        let mut words = BTreeMap::new();
+        for (address, byte) in bytes {
+            *words.entry(address / size).or_insert(0) |= (byte as u64) << (8 * (address % size));
+        }
+        for word in words.values_mut() {
+            *word &= mask(self.data_width);
+        }
+        words
+    }
+    
+    /// Reads whitespace separated words, `<count>*<word>` repeats a word and `#` starts a comment.
+    /// Logisim puts `v2.0 raw` at the top, which is skipped
+    fn words_from_hex_text(&self, image: &[u8]) -> Result<BTreeMap<u64, u64>, LogikError> {
+        let text = std::str::from_utf8(image)
+            .map_err(|_| LogikError::InvalidImage(String::from("the image is not text")))?;
+        let invalid = |line: usize, reason: String| LogikError::InvalidImage(format!("line {}: {}", line + 1, reason));
+        
+        // counted wider than an address, so a memory that is full doesn't wrap around
+        let size = mask(self.address_width) as u128 + 1;
+        let mut words = BTreeMap::new();
+        let mut address = 0u128;
+        for (line, text) in text.lines().enumerate() {
+            let text = text.split('#').next().unwrap().trim();
+            if line == 0 && text == "v2.0 raw" {
+                continue;
+            }
+            
+            for token in text.split_whitespace() {
+                let (count, word): (u64, _) = match token.split_once('*') {
+                    Some((count, word)) => (count.parse().map_err(|_| invalid(line, format!("'{}' is not a count", count)))?, word),
+                    None => (1, token),
+                };
+                let word = u64::from_str_radix(word, 16)
+                    .map_err(|_| invalid(line, format!("'{}' is not a hexadecimal word", word)))?;
+                if word & !mask(self.data_width) != 0 {
+                    return Err(invalid(line, LogikError::WordTooWide { word, width: self.data_width }.to_string()));
+                }
+                
+                if count as u128 > size - address {
+                    return Err(invalid(line, String::from("the image has more words than the memory")));
+                }
+                if word != 0 {
+                    if words.len() as u64 + count > MAX_IMAGE_WORDS {
+                        return Err(invalid(line, format!("the image has more than {} words that aren't 0", MAX_IMAGE_WORDS)));
+                    }
+                    words.extend((address as u64..).take(count as usize).map(|address| (address, word)));
+                }
+                address += count as u128;
+            }
+        }
+        
+        Ok(words)
+    }
+    
+    /// The contents as `<address>:<word>` pairs in hexadecimal separated by `;`, only the words
+    /// that aren't 0 are written
+    fn save(&self) -> Option<String> {
+        let words = self.words.borrow();
+        if words.is_empty() {
+            return None;
+        }
+        
+        Some(words.iter().map(|(address, word)| format!("{:x}:{:x}", address, word)).collect::<Vec<_>>().join(";"))
+    }
+    
+    fn restore(&self, state: &str) -> bool {
+        let mut words = BTreeMap::new();
+        for pair in state.split(';') {
+            let parsed = pair.split_once(':').and_then(|(address, word)| {
+                Some((u64::from_str_radix(address, 16).ok()?, u64::from_str_radix(word, 16).ok()?))
+            });
+            match parsed {
+                Some((address, word)) if self.check_address(address).is_ok() && word & !mask(self.data_width) == 0 => {
+                    words.insert(address, word);
+                }
+                _ => return false,
+            }
+        }
+        
+        *self.words.borrow_mut() = words;
+        true
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::int("address_width", 1, MAX_ADDRESS_WIDTH as i64, self.address_width as i64),
+            Parameter::int("data_width", 1, MAX_WIDTH as i64, self.data_width as i64),
+        ]
+    }
+    
+    /// Changing a width drops the words that no longer fit
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("address_width", ParameterValue::Int(width)) => self.address_width = width as u8,
+            ("data_width", ParameterValue::Int(width)) => self.data_width = width as u8,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        
+        let (addresses, data) = (mask(self.address_width), mask(self.data_width));
+        let mut words = self.words.borrow_mut();
+        words.retain(|address, _| *address <= addresses);
+        for word in words.values_mut() {
+            *word &= data;
+        }
+        words.retain(|_, word| *word != 0);
+        Ok(())
+    }
+}
+
+/// Reads the data records of an Intel HEX image into the bytes they put at each address
+fn intel_hex_bytes(image: &[u8]) -> Result<Vec<(u64, u8)>, LogikError> {
+    let text = std::str::from_utf8(image)
+        .map_err(|_| LogikError::InvalidImage(String::from("the image is not text")))?;
+    let invalid = |line: usize, reason: &str| LogikError::InvalidImage(format!("line {}: {}", line + 1, reason));
+    
+    let mut bytes = Vec::new();
+    let mut base = 0u64;
+    for (line, text) in text.lines().enumerate() {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        
+        let record = text.strip_prefix(':').ok_or_else(|| invalid(line, "a record has to start with ':'"))?;
+        if record.len() % 2 != 0 || !record.is_ascii() {
+            return Err(invalid(line, "the record is not made out of hexadecimal bytes"));
+        }
+        let record = (0..record.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&record[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid(line, "the record is not made out of hexadecimal bytes"))?;
+        if record.len() < 5 || record.len() != record[0] as usize + 5 {
+            return Err(invalid(line, "the length of the record doesn't match its data"));
+        }
+        if record.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(invalid(line, "the checksum doesn't match"));
+        }
+        
+        let address = u16::from_be_bytes([record[1], record[2]]) as u64;
+        let data = &record[4..record.len() - 1];
+        match record[3] {
+            0 => bytes.extend(data.iter().enumerate().map(|(i, byte)| (base + address + i as u64, *byte))),
+            1 => break,
+            2 if data.len() == 2 => base = (u16::from_be_bytes([data[0], data[1]]) as u64) << 4,
+            4 if data.len() == 2 => base = (u16::from_be_bytes([data[0], data[1]]) as u64) << 16,
+            // start addresses don't mean anything for a memory
+            3 | 5 => {},
+            _ => return Err(invalid(line, "the record type is not supported")),
+        }
+    }
+    
+    Ok(bytes)
+}
+
+/// Read-only memory, ports are 0 for the address and 1 for the word stored there
+#[derive(Debug)]
+pub(crate) struct ROM {
+    memory: Memory,
+}
+
+impl Component for ROM {
+    fn id(&self) -> ComponentId {
+        ComponentId::ROM
+    }
+    
+    fn ports(&self) -> usize {
+        2
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            0 => Some(PortType::Input),
+            1 => Some(PortType::Output),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 => Some(self.memory.address_width),
+            1 => Some(self.memory.data_width),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let address = data.get(&0).map(|e| e.current.clone()).unwrap_or_else(|| Value::floating(self.memory.address_width));
+        
+        map!(1 => self.memory.output(&address))
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        self.memory.save()
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        self.memory.restore(state)
+    }
+    
+    fn memory(&self) -> Option<&Memory> {
+        Some(&self.memory)
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        self.memory.parameters()
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        self.memory.set_parameter(name, value)
+    }
+}
+
+impl ROM {
+    pub(crate) fn new() -> Self {
+        Self { memory: Memory::new() }
+    }
+}
+
+/// Memory that can be written, ports are 0 for the address, 1 for the word to write, 2 to enable
+/// writing, 3 for the clock and 4 for the word stored at the address. The word is written on the
+/// rising edge of the clock and can be read at any time
+#[derive(Debug)]
+pub(crate) struct RAM {
+    memory: Memory,
+}
+
+impl Component for RAM {
+    fn id(&self) -> ComponentId {
+        ComponentId::RAM
+    }
+    
+    fn ports(&self) -> usize {
+        5
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            0..=3 => Some(PortType::Input),
+            4 => Some(PortType::Output),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 => Some(self.memory.address_width),
+            1 | 4 => Some(self.memory.data_width),
+            2 | 3 => Some(1),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let value = |port| data.get(&port).map(|e| e.current.clone()).unwrap_or_else(|| Value::floating(self.port_width(port).unwrap()));
+        let address = value(0);
+        let clock = data.get(&3).map(|e| e.rising()).unwrap_or(false);
+        let enable = data.get(&2).map(|e| e.current.get(0)).unwrap_or(SubnetState::Floating);
+        
+        if clock && enable == SubnetState::On {
+            // a word that isn't fully known can't be stored, the old one is kept
+            if let (Some(address), Some(word)) = (address.to_u64(), value(1).to_u64()) {
+                self.memory.store(address, word);
+            }
+        }
+        
+        map!(4 => self.memory.output(&address))
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        self.memory.save()
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        self.memory.restore(state)
+    }
+    
+    fn memory(&self) -> Option<&Memory> {
+        Some(&self.memory)
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        self.memory.parameters()
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        self.memory.set_parameter(name, value)
+    }
+}
+
+impl RAM {
+    pub(crate) fn new() -> Self {
+        Self { memory: Memory::new() }
+    }
+}
@@ -1,18 +1,40 @@
 use std::fmt::Debug;
-use crate::data::subnet::SubnetState;
+use crate::data::subnet::{SubnetState, Value};
 use std::collections::HashMap;
 use crate::data::EdgeDirection;
+use crate::data::error::LogikError;
+use std::convert::TryFrom;
+use components::*;
+use statefuls::*;
+use subcircuit::Subcircuit;
+use memory::{Memory, RAM, ROM};
+use plexers::{Mux, Demux, Decoder, PriorityEncoder};
+use arithmetic::{Adder, Subtractor, Multiplier, Divider, Comparator};
+use parameter::{Parameter, ParameterKind, ParameterValue};
 
 pub(crate) mod statefuls;
 pub(crate) mod components;
+pub(crate) mod subcircuit;
+pub(crate) mod memory;
+pub(crate) mod plexers;
+pub(crate) mod arithmetic;
+pub(crate) mod parameter;
 
 /// A trait to define common behaviour between the components
 pub(crate) trait Component: Debug {
     fn ports(&self) -> usize;
     fn port_type(&self, port: usize) -> Option<PortType>;
     // requires that data has a value for every input or bidirectional port
-    // and in turn guarantees that the return value has a value for every output or bidirectional port
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState>;
+    // and in turn guarantees that the return value has a value for every output or bidirectional port.
+    // A bidirectional port reads what the other drivers of its subnet drive, without its own value
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value>;
+    /// The kind of component, used to recreate it when a netlist is loaded
+    fn id(&self) -> ComponentId;
+    
+    /// The amount of bits a port carries, the subnet it is linked to needs to have the same width
+    fn port_width(&self, port: usize) -> Option<u8> {
+        self.port_type(port).map(|_| 1)
+    }
     
     fn ports_type(&self) -> Vec<PortType> {
         (0..self.ports())
@@ -20,11 +42,69 @@ pub(crate) trait Component: Debug {
             .collect()
     }
     fn pressed(&self) -> SubnetState{
-        return SubnetState::Error
+        SubnetState::Error
     }
 
     fn released(&self) -> SubnetState{
-        return SubnetState::Error
+        SubnetState::Error
+    }
+    
+    /// The internal state of the component in a form that `load_state` understands, stateless
+    /// components have none
+    fn save_state(&self) -> Option<String> {
+        None
+    }
+    
+    /// Restores a state created by `save_state`, returns whether the state was understood
+    fn load_state(&self, _state: &str) -> bool {
+        false
+    }
+    
+    /// Sets the value a component drives on behalf of something outside of the circuit, like the
+    /// inputs of a subcircuit or the level a clock has according to its schedule
+    fn drive(&self, _value: Value) {}
+    
+    fn subcircuit(&self) -> Option<&Subcircuit> {
+        None
+    }
+    
+    /// The value a register or flip-flop holds, which is what it drives unless it is being cleared
+    fn stored_value(&self) -> Option<Value> {
+        None
+    }
+    
+    /// How strongly the component drives its outputs, weak drivers give way to strong ones
+    fn strength(&self) -> Strength {
+        Strength::Strong
+    }
+    
+    /// The words of a RAM or ROM, which can be read and written from outside of the simulation
+    fn memory(&self) -> Option<&Memory> {
+        None
+    }
+    
+    /// The bits of bidirectional ports that are the same wire, as pairs of `(port, bit)`. A value
+    /// on one of them can show up on the other, so they are traced as one by the static checks
+    fn joined_bits(&self) -> Vec<((usize, usize), (usize, usize))> {
+        Vec::new()
+    }
+    
+    /// Whether the bits of `joined_bits` are connected right now. While this is `On` the subnets on
+    /// both sides resolve as one, otherwise every port drives a value of its own
+    fn joining(&self) -> SubnetState {
+        SubnetState::On
+    }
+    
+    /// The settings of the component with their current values, in the order `build_with_params`
+    /// takes them
+    fn parameters(&self) -> Vec<Parameter> {
+        Vec::new()
+    }
+    
+    /// Changes a setting, this can change the number and width of the ports as well. The value has
+    /// already been checked against the parameter when this is called through `parameter::set_parameter`
+    fn set_parameter(&mut self, name: &str, _value: ParameterValue) -> Result<(), LogikError> {
+        Err(LogikError::UnknownParameter(String::from(name)))
     }
 
 }
@@ -56,32 +136,77 @@ impl From<PortType> for EdgeDirection {
     }
 }
 
+/// How strongly a component drives a subnet. A bit that any strong driver drives takes the value
+/// the strong drivers agree on, the weak drivers only decide the bits that would float otherwise
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(crate) enum Strength {
+    Strong,
+    Weak,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct StateChange {
-    old: SubnetState,
-    current: SubnetState,
+    old: Value,
+    current: Value,
 }
 
 impl StateChange {
-    pub(crate) fn new(old: SubnetState, current: SubnetState) -> Self {
+    pub(crate) fn new(old: Value, current: Value) -> Self {
         Self { old, current }
     }
     
     pub(crate) fn rising(&self) -> bool {
-        self.old == SubnetState::Off &&
-            self.current == SubnetState::On
+        self.old.get(0) == SubnetState::Off &&
+            self.current.get(0) == SubnetState::On
     }
     
+    #[allow(dead_code)]
     pub(crate) fn falling(&self) -> bool {
-        self.old == SubnetState::On &&
-            self.current == SubnetState::Off
+        self.old.get(0) == SubnetState::On &&
+            self.current.get(0) == SubnetState::Off
     }
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
-#[repr(i32)]
-pub enum ComponentId {
+/// Declares `ComponentId` together with the number the GUI uses for every kind of component
+/// and the name it has in saved netlists
+macro_rules! component_ids {
+    ($($name:ident = $id:literal,)*) => {
+        #[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+        #[repr(i32)]
+        pub enum ComponentId {
+            $($name = $id,)*
+        }
+        
+        impl ComponentId {
+            pub(crate) fn name(self) -> &'static str {
+                match self {
+                    $(ComponentId::$name => stringify!($name),)*
+                }
+            }
+            
+            pub(crate) fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($name) => Some(ComponentId::$name),)*
+                    _ => None,
+                }
+            }
+        }
+        
+        impl TryFrom<i32> for ComponentId {
+            type Error = LogikError;
+            
+            fn try_from(id: i32) -> Result<Self, Self::Error> {
+                match id {
+                    $($id => Ok(ComponentId::$name),)*
+                    _ => Err(LogikError::UnknownComponentType(id)),
+                }
+            }
+        }
+    };
+}
+
+component_ids! {
+    Custom = 0,
     Constant = 1,
     Output = 2,
     Input = 3,
@@ -96,19 +221,111 @@ pub enum ComponentId {
     Nor = 55,
     Xor = 56,
     Xnor = 57,
-    TriStateBuffer   = 60,
+    TriStateBuffer = 60,
     TriStateInverter = 61,
-    DFlipFlop     = 100,
-    TFlipFlop     = 101,
-    JKFlipFlop    = 102,
-    SRFlipFlop    = 103,
+    TransmissionGate = 62,
+    DFlipFlop = 100,
+    TFlipFlop = 101,
+    JKFlipFlop = 102,
+    SRFlipFlop = 103,
+    Register = 104,
+    ShiftRegister = 105,
+    Counter = 106,
+    RAM = 110,
+    ROM = 111,
+    Mux = 120,
+    Demux = 121,
+    Decoder = 122,
+    PriorityEncoder = 123,
+    Adder = 130,
+    Subtractor = 131,
+    Multiplier = 132,
+    Divider = 133,
+    Comparator = 134,
     Probe = 300,
-    Clock    = 302,
+    Splitter = 301,
+    PullUp = 304,
+    PullDown = 305,
+    Clock = 302,
+}
+
+impl ComponentId {
+    /// Creates a new component of this kind and sets its parameters in the order `parameters` lists
+    /// them, the ones left out keep their default. Only integer and boolean parameters can be set
+    /// this way, booleans are given as 0 or 1
+    pub(crate) fn build_with_params(self, params: &[i32]) -> Result<Box<dyn Component>, LogikError> {
+        let mut component = self.build()?;
+        let parameters = component.parameters();
+        if params.len() > parameters.len() {
+            let reason = format!("{} takes at most {} parameters, got {}", self.name(), parameters.len(), params.len());
+            return Err(LogikError::InvalidParameter(reason));
+        }
+        
+        for (parameter, value) in parameters.iter().zip(params) {
+            let value = match (parameter.kind, value) {
+                (ParameterKind::Int { .. }, value) => ParameterValue::Int(*value as i64),
+                (ParameterKind::Bool, 0) => ParameterValue::Bool(false),
+                (ParameterKind::Bool, 1) => ParameterValue::Bool(true),
+                _ => return Err(LogikError::InvalidParameter(format!("{} can't be set to {}", parameter.name, value))),
+            };
+            parameter::set_parameter(component.as_mut(), parameter.name, value)?;
+        }
+        
+        Ok(component)
+    }
+    
+    /// Creates a new component of this kind in its default configuration, custom components are
+    /// instances of a subcircuit and can't be created without its definition
+    pub(crate) fn build(self) -> Result<Box<dyn Component>, LogikError> {
+        Ok(match self {
+            ComponentId::Custom => return Err(LogikError::UnknownComponentType(self as i32)),
+            ComponentId::Constant => Box::new(Constant::new()),
+            ComponentId::Output => Box::new(OutputGate::new()),
+            ComponentId::Input => Box::new(InputGate::new()),
+            ComponentId::LED => Box::new(LED {}),
+            ComponentId::Button => Box::new(Button::new()),
+            ComponentId::Switch => Box::new(Switch::new()),
+            ComponentId::Buffer => Box::new(Buffer {}),
+            ComponentId::Not => Box::new(NOT {}),
+            ComponentId::And => Box::new(AND::new(MIN_INPUTS)),
+            ComponentId::Nand => Box::new(NAND::new(MIN_INPUTS)),
+            ComponentId::Or => Box::new(OR::new(MIN_INPUTS)),
+            ComponentId::Nor => Box::new(NOR::new(MIN_INPUTS)),
+            ComponentId::Xor => Box::new(XOR::new(MIN_INPUTS, false)),
+            ComponentId::Xnor => Box::new(XNOR::new(MIN_INPUTS, false)),
+            ComponentId::TriStateBuffer => Box::new(TriBuffer {}),
+            ComponentId::TriStateInverter => Box::new(TriInverter {}),
+            ComponentId::TransmissionGate => Box::new(TransmissionGate::new()),
+            ComponentId::DFlipFlop => Box::new(DFlipFlop::new()),
+            ComponentId::TFlipFlop => Box::new(TFlipFlop::new()),
+            ComponentId::JKFlipFlop => Box::new(JKFlipFlop::new()),
+            ComponentId::SRFlipFlop => Box::new(SRFlipFlop::new()),
+            ComponentId::Register => Box::new(Register::new()),
+            ComponentId::ShiftRegister => Box::new(ShiftRegister::new()),
+            ComponentId::Counter => Box::new(Counter::new()),
+            ComponentId::RAM => Box::new(RAM::new()),
+            ComponentId::ROM => Box::new(ROM::new()),
+            ComponentId::Mux => Box::new(Mux::new()),
+            ComponentId::Demux => Box::new(Demux::new()),
+            ComponentId::Decoder => Box::new(Decoder::new()),
+            ComponentId::PriorityEncoder => Box::new(PriorityEncoder::new()),
+            ComponentId::Adder => Box::new(Adder::new()),
+            ComponentId::Subtractor => Box::new(Subtractor::new()),
+            ComponentId::Multiplier => Box::new(Multiplier::new()),
+            ComponentId::Divider => Box::new(Divider::new()),
+            ComponentId::Comparator => Box::new(Comparator::new()),
+            ComponentId::Probe => Box::new(Probe {}),
+            ComponentId::Splitter => Box::new(Splitter::new()),
+            ComponentId::PullUp => Box::new(Pull::new(true)),
+            ComponentId::PullDown => Box::new(Pull::new(false)),
+            ComponentId::Clock => Box::new(Clock::new()),
+        })
+    }
 }
 
 #[macro_export]
 macro_rules! port_or_default {
     ($data:ident ,$id:expr) => {
-        $data.get(&($id)).map(|e| e.current).unwrap_or(SubnetState::Floating)
+        $data.get(&($id)).map(|e| e.current.get(0)).unwrap_or(SubnetState::Floating)
     };
 }
\ No newline at end of file
@@ -0,0 +1,158 @@
+use crate::data::component::Component;
+use crate::data::error::LogikError;
+use crate::data::subnet::{SubnetState, Value, MAX_WIDTH};
+
+/// The kind of value a parameter takes, the numbers are what the FFI reports
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(crate) enum ParameterKind {
+    Int { min: i64, max: i64 },
+    Bool,
+    String,
+    Bits,
+}
+
+impl ParameterKind {
+    pub(crate) fn code(self) -> i32 {
+        match self {
+            ParameterKind::Int { .. } => 0,
+            ParameterKind::Bool => 1,
+            ParameterKind::String => 2,
+            ParameterKind::Bits => 3,
+        }
+    }
+    
+    fn name(self) -> &'static str {
+        match self {
+            ParameterKind::Int { .. } => "an integer",
+            ParameterKind::Bool => "a boolean",
+            ParameterKind::String => "a string",
+            ParameterKind::Bits => "bits",
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) enum ParameterValue {
+    Int(i64),
+    Bool(bool),
+    String(String),
+    Bits(Value),
+}
+
+impl ParameterValue {
+    fn kind_name(&self) -> &'static str {
+        match self {
+            ParameterValue::Int(_) => "an integer",
+            ParameterValue::Bool(_) => "a boolean",
+            ParameterValue::String(_) => "a string",
+            ParameterValue::Bits(_) => "bits",
+        }
+    }
+    
+    /// Writes the value the way netlists do, booleans are `0` or `1` and bits are written with the
+    /// most significant bit first
+    pub(crate) fn to_text(&self) -> String {
+        match self {
+            ParameterValue::Int(value) => value.to_string(),
+            ParameterValue::Bool(value) => String::from(if *value { "1" } else { "0" }),
+            ParameterValue::String(value) => value.clone(),
+            ParameterValue::Bits(value) => value.bits()
+                .iter()
+                .rev()
+                .map(|bit| if *bit == SubnetState::On { '1' } else { '0' })
+                .collect(),
+        }
+    }
+    
+    /// Reads a value of the given kind written by `to_text`
+    pub(crate) fn from_text(kind: ParameterKind, text: &str) -> Option<Self> {
+        Some(match kind {
+            ParameterKind::Int { .. } => ParameterValue::Int(text.parse().ok()?),
+            ParameterKind::Bool => match text {
+                "0" => ParameterValue::Bool(false),
+                "1" => ParameterValue::Bool(true),
+                _ => return None,
+            },
+            ParameterKind::String => ParameterValue::String(String::from(text)),
+            ParameterKind::Bits => {
+                let bits = text.chars()
+                    .rev()
+                    .map(|bit| match bit {
+                        '0' => Some(SubnetState::Off),
+                        '1' => Some(SubnetState::On),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                ParameterValue::Bits(bits.into())
+            }
+        })
+    }
+}
+
+/// A setting of a component together with the value it currently has
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct Parameter {
+    pub(crate) name: &'static str,
+    pub(crate) kind: ParameterKind,
+    pub(crate) value: ParameterValue,
+}
+
+impl Parameter {
+    pub(crate) fn int(name: &'static str, min: i64, max: i64, value: i64) -> Self {
+        Self { name, kind: ParameterKind::Int { min, max }, value: ParameterValue::Int(value) }
+    }
+    
+    pub(crate) fn bool(name: &'static str, value: bool) -> Self {
+        Self { name, kind: ParameterKind::Bool, value: ParameterValue::Bool(value) }
+    }
+    
+    pub(crate) fn string(name: &'static str, value: &str) -> Self {
+        Self { name, kind: ParameterKind::String, value: ParameterValue::String(String::from(value)) }
+    }
+    
+    pub(crate) fn bits(name: &'static str, value: Value) -> Self {
+        Self { name, kind: ParameterKind::Bits, value: ParameterValue::Bits(value) }
+    }
+    
+    /// Checks that a value has the right kind and is in range. Strings can't contain whitespace
+    /// so that they can be written to a netlist, and bits have to be on or off
+    pub(crate) fn check(&self, value: &ParameterValue) -> Result<(), LogikError> {
+        let invalid = |reason: String| Err(LogikError::InvalidParameter(reason));
+        
+        match (self.kind, value) {
+            (ParameterKind::Int { min, max }, ParameterValue::Int(value)) => if *value < min || *value > max {
+                return invalid(format!("{} has to be between {} and {}, got {}", self.name, min, max, value));
+            },
+            (ParameterKind::Bool, ParameterValue::Bool(_)) => {},
+            (ParameterKind::String, ParameterValue::String(value)) => if value.contains(char::is_whitespace) {
+                return invalid(format!("{} can't contain whitespace", self.name));
+            },
+            (ParameterKind::Bits, ParameterValue::Bits(value)) => {
+                let width = value.bits().len();
+                if width == 0 || width > MAX_WIDTH as usize {
+                    return invalid(format!("{} can't be {} bits wide", self.name, width));
+                }
+                if value.bits().iter().any(|bit| *bit != SubnetState::On && *bit != SubnetState::Off) {
+                    return invalid(format!("every bit of {} has to be on or off", self.name));
+                }
+            }
+            (kind, value) => return invalid(format!("{} takes {}, got {}", self.name, kind.name(), value.kind_name())),
+        }
+        
+        Ok(())
+    }
+}
+
+/// Looks up a parameter of a component by name
+pub(crate) fn find_parameter(component: &dyn Component, name: &str) -> Result<Parameter, LogikError> {
+    component.parameters()
+        .into_iter()
+        .find(|parameter| parameter.name == name)
+        .ok_or_else(|| LogikError::UnknownParameter(String::from(name)))
+}
+
+/// Changes a parameter of a component once the value has been checked against it
+pub(crate) fn set_parameter(component: &mut dyn Component, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+    find_parameter(component, name)?.check(&value)?;
+    component.set_parameter(name, value)
+}
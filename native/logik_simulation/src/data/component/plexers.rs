@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use crate::data::component::{StateChange, PortType, Component, ComponentId};
+use crate::data::component::parameter::{Parameter, ParameterValue};
+use crate::data::error::LogikError;
+use crate::data::subnet::{SubnetState, Value, MAX_WIDTH};
+use crate::{map, port_or_default};
+
+/// The most select bits a plexer can have, which gives it as many data ports as a gate has inputs
+pub(crate) const MAX_SELECT: u8 = 5;
+
+/// The number a select port picks, or the state every output takes when it doesn't pick one. An
+/// error on any bit is an error, a select that is only partly driven leaves the outputs floating
+fn selected(data: &HashMap<usize, StateChange>, port: usize) -> Result<usize, SubnetState> {
+    let select = match data.get(&port) {
+        Some(select) => &select.current,
+        None => return Err(SubnetState::Floating),
+    };
+    
+    match select.to_u64() {
+        Some(index) => Ok(index as usize),
+        None if select.bits().contains(&SubnetState::Error) => Err(SubnetState::Error),
+        None => Err(SubnetState::Floating),
+    }
+}
+
+/// Whether a plexer with an enable port is enabled, a plexer that is turned off leaves its outputs
+/// floating and one with an unknown enable passes the unknown state on
+fn enabled(data: &HashMap<usize, StateChange>, port: usize, enable: bool) -> Result<(), SubnetState> {
+    if !enable {
+        return Ok(());
+    }
+    
+    match port_or_default!(data, port) {
+        SubnetState::On => Ok(()),
+        SubnetState::Off => Err(SubnetState::Floating),
+        state => Err(state),
+    }
+}
+
+fn select_parameter(select: u8) -> Parameter {
+    Parameter::int("select", 1, MAX_SELECT as i64, select as i64)
+}
+
+fn width_parameter(width: u8) -> Parameter {
+    Parameter::int("width", 1, MAX_WIDTH as i64, width as i64)
+}
+
+fn set_plexer_parameter(select: &mut u8, width: Option<&mut u8>, enable: &mut bool, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+    match (name, value, width) {
+        ("select", ParameterValue::Int(value), _) => *select = value as u8,
+        ("width", ParameterValue::Int(value), Some(width)) => *width = value as u8,
+        ("enable", ParameterValue::Bool(value), _) => *enable = value,
+        _ => return Err(LogikError::UnknownParameter(String::from(name))),
+    }
+    Ok(())
+}
+
+/// Passes on the data port the select port picks. Ports are the `2^select` data ports, the select
+/// port, the output and the enable port when the multiplexer has one
+#[derive(Debug)]
+pub(crate) struct Mux {
+    select: u8,
+    width: u8,
+    enable: bool,
+}
+
+impl Component for Mux {
+    fn id(&self) -> ComponentId {
+        ComponentId::Mux
+    }
+    
+    fn ports(&self) -> usize {
+        (1 << self.select) + 2 + self.enable as usize
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        let inputs = 1 << self.select;
+        match port {
+            p if p <= inputs => Some(PortType::Input),
+            p if p == inputs + 1 => Some(PortType::Output),
+            p if p == inputs + 2 && self.enable => Some(PortType::Input),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        let inputs = 1 << self.select;
+        match port {
+            p if p == inputs => Some(self.select),
+            p if p == inputs + 2 => self.port_type(port).map(|_| 1),
+            _ => self.port_type(port).map(|_| self.width),
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let inputs = 1 << self.select;
+        let output = enabled(&data, inputs + 2, self.enable)
+            .and_then(|_| selected(&data, inputs))
+            .map(|index| data.get(&index).map(|e| e.current.clone()).unwrap_or_else(|| Value::floating(self.width)))
+            .unwrap_or_else(|state| vec![state; self.width as usize].into());
+        
+        map!(inputs + 1 => output)
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![select_parameter(self.select), width_parameter(self.width), Parameter::bool("enable", self.enable)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_plexer_parameter(&mut self.select, Some(&mut self.width), &mut self.enable, name, value)
+    }
+}
+
+impl Mux {
+    pub(crate) fn new() -> Self {
+        Self { select: 1, width: 1, enable: false }
+    }
+}
+
+/// Passes its data port on to the output the select port picks, the other outputs are off. Ports are
+/// the data port, the select port, the `2^select` outputs and the enable port when the
+/// demultiplexer has one
+#[derive(Debug)]
+pub(crate) struct Demux {
+    select: u8,
+    width: u8,
+    enable: bool,
+}
+
+impl Component for Demux {
+    fn id(&self) -> ComponentId {
+        ComponentId::Demux
+    }
+    
+    fn ports(&self) -> usize {
+        (1 << self.select) + 2 + self.enable as usize
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        let outputs = 1 << self.select;
+        match port {
+            0 | 1 => Some(PortType::Input),
+            p if p < outputs + 2 => Some(PortType::Output),
+            p if p == outputs + 2 && self.enable => Some(PortType::Input),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        let outputs = 1 << self.select;
+        match port {
+            1 => Some(self.select),
+            p if p == outputs + 2 => self.port_type(port).map(|_| 1),
+            _ => self.port_type(port).map(|_| self.width),
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let outputs = 1 << self.select;
+        let selected = enabled(&data, outputs + 2, self.enable).and_then(|_| selected(&data, 1));
+        let input = data.get(&0).map(|e| e.current.clone()).unwrap_or_else(|| Value::floating(self.width));
+        
+        (0..outputs)
+            .map(|output| (output + 2, match selected {
+                Ok(index) if index == output => input.clone(),
+                Ok(_) => Value::from_u64(0, self.width),
+                Err(state) => vec![state; self.width as usize].into(),
+            }))
+            .collect()
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![select_parameter(self.select), width_parameter(self.width), Parameter::bool("enable", self.enable)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_plexer_parameter(&mut self.select, Some(&mut self.width), &mut self.enable, name, value)
+    }
+}
+
+impl Demux {
+    pub(crate) fn new() -> Self {
+        Self { select: 1, width: 1, enable: false }
+    }
+}
+
+/// Turns on the output the select port picks and turns off the others. Ports are the select port,
+/// the `2^select` outputs and the enable port when the decoder has one
+#[derive(Debug)]
+pub(crate) struct Decoder {
+    select: u8,
+    enable: bool,
+}
+
+impl Component for Decoder {
+    fn id(&self) -> ComponentId {
+        ComponentId::Decoder
+    }
+    
+    fn ports(&self) -> usize {
+        (1 << self.select) + 1 + self.enable as usize
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        let outputs = 1 << self.select;
+        match port {
+            0 => Some(PortType::Input),
+            p if p <= outputs => Some(PortType::Output),
+            p if p == outputs + 1 && self.enable => Some(PortType::Input),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 => Some(self.select),
+            _ => self.port_type(port).map(|_| 1),
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let outputs = 1 << self.select;
+        let selected = enabled(&data, outputs + 1, self.enable).and_then(|_| selected(&data, 0));
+        
+        (0..outputs)
+            .map(|output| (output + 1, match selected {
+                Ok(index) if index == output => SubnetState::On.into(),
+                Ok(_) => SubnetState::Off.into(),
+                Err(state) => state.into(),
+            }))
+            .collect()
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![select_parameter(self.select), Parameter::bool("enable", self.enable)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_plexer_parameter(&mut self.select, None, &mut self.enable, name, value)
+    }
+}
+
+impl Decoder {
+    pub(crate) fn new() -> Self {
+        Self { select: 1, enable: false }
+    }
+}
+
+/// Outputs the number of the highest input that is on, and whether there is one at all. Ports are
+/// the `2^select` inputs, the number, the valid output and the enable port when the encoder has
+/// one. Floating inputs count as off so that unused inputs can be left unlinked, but an error
+/// above the first input that is on makes both outputs an error. An encoder that is turned off
+/// has no valid number
+#[derive(Debug)]
+pub(crate) struct PriorityEncoder {
+    select: u8,
+    enable: bool,
+}
+
+impl Component for PriorityEncoder {
+    fn id(&self) -> ComponentId {
+        ComponentId::PriorityEncoder
+    }
+    
+    fn ports(&self) -> usize {
+        (1 << self.select) + 2 + self.enable as usize
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        let inputs = 1 << self.select;
+        match port {
+            p if p < inputs => Some(PortType::Input),
+            p if p <= inputs + 1 => Some(PortType::Output),
+            p if p == inputs + 2 && self.enable => Some(PortType::Input),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            p if p == 1 << self.select => Some(self.select),
+            _ => self.port_type(port).map(|_| 1),
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let inputs = 1 << self.select;
+        let enable = if self.enable { port_or_default!(data, inputs + 2) } else { SubnetState::On };
+        
+        // turned off it reports that no input is on
+        let highest = match enable {
+            SubnetState::On => (0..inputs).rev()
+                .map(|input| (input, port_or_default!(data, input)))
+                .find(|(_, state)| *state == SubnetState::On || *state == SubnetState::Error)
+                .map_or(Ok(None), |(input, state)| if state == SubnetState::On { Ok(Some(input)) } else { Err(state) }),
+            SubnetState::Off => Ok(None),
+            state => Err(state),
+        };
+        
+        let (number, valid) = match highest {
+            Ok(Some(input)) => (Value::from_u64(input as u64, self.select), SubnetState::On),
+            Ok(None) => (Value::floating(self.select), SubnetState::Off),
+            Err(state) => (vec![state; self.select as usize].into(), state),
+        };
+        
+        map!(inputs => number, inputs + 1 => valid.into())
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![select_parameter(self.select), Parameter::bool("enable", self.enable)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        set_plexer_parameter(&mut self.select, None, &mut self.enable, name, value)
+    }
+}
+
+impl PriorityEncoder {
+    pub(crate) fn new() -> Self {
+        Self { select: 1, enable: false }
+    }
+}
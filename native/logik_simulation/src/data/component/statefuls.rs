@@ -1,18 +1,39 @@
-use crate::data::component::{Component, PortType, StateChange};
-use crate::data::subnet::SubnetState;
+use crate::data::component::{Component, ComponentId, PortType, StateChange};
+use crate::data::subnet::{mask, SubnetState, Value, MAX_WIDTH};
 use std::collections::HashMap;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use crate::data::component::parameter::{Parameter, ParameterValue};
+use crate::data::error::LogikError;
 use crate::{map, port_or_default};
 
+fn save_bool(state: bool) -> Option<String> {
+    Some(String::from(if state { "1" } else { "0" }))
+}
+
+fn bool_value(state: bool) -> Value {
+    if state { SubnetState::On } else { SubnetState::Off }.into()
+}
+
+fn load_bool(cell: &Cell<bool>, state: &str) -> bool {
+    match state {
+        "0" => cell.set(false),
+        "1" => cell.set(true),
+        _ => return false,
+    }
+    true
+}
+
+/// Drives the value it was given as its `value` parameter, pressing it inverts every bit
 #[derive(Debug)]
 pub(crate) struct Constant {
-    #[cfg(not(test))]
-    state: Cell<bool>,
-    #[cfg(test)]
-    pub state: Cell<bool>,
+    value: RefCell<Value>,
 }
 
 impl Component for Constant {
+    fn id(&self) -> ComponentId {
+        ComponentId::Constant
+    }
+    
     fn ports(&self) -> usize {
         1
     }
@@ -24,38 +45,53 @@ impl Component for Constant {
         }
     }
     
-    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
-        let val = match self.state.get() {
-            true => SubnetState::On,
-            false => SubnetState::Off,
-        };
-        map!(0 => val)
+    fn port_width(&self, port: usize) -> Option<u8> {
+        self.port_type(port).map(|_| self.value.borrow().width())
     }
 
-    fn pressed(&self) -> SubnetState{
-        match self.state.get(){
-            true => {
-                self.state.set(false);
-                return SubnetState::Off
-            }
-            false => {
-                self.state.set(true);
-                return SubnetState::On
-            }
-        }
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(0 => self.value.borrow().clone())
+    }
+    
+    fn pressed(&self) -> SubnetState {
+        let inverted = self.value.borrow()
+            .bits()
+            .iter()
+            .map(|bit| if *bit == SubnetState::On { SubnetState::Off } else { SubnetState::On })
+            .collect::<Vec<_>>();
+        *self.value.borrow_mut() = inverted.into();
+        self.value.borrow().get(0)
     }
 
     fn released(&self) -> SubnetState {
-        match self.state.get(){
-            true => SubnetState::On,
-            false => SubnetState::Off,
+        self.value.borrow().get(0)
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::bits("value", self.value.borrow().clone())]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("value", ParameterValue::Bits(value)) => *self.value.borrow_mut() = value,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
         }
+        Ok(())
     }
 }
 
 impl Constant {
     pub(crate) fn new() -> Self {
-        Self { state: Cell::new(false) }
+        Self { value: RefCell::new(SubnetState::Off.into()) }
+    }
+    
+    #[cfg(test)]
+    pub(crate) fn with_state(state: bool) -> Self {
+        let constant = Self::new();
+        if state {
+            constant.pressed();
+        }
+        constant
     }
 }
 
@@ -68,6 +104,10 @@ pub(crate) struct Button {
 }
 
 impl Component for Button {
+    fn id(&self) -> ComponentId {
+        ComponentId::Button
+    }
+    
     fn ports(&self) -> usize {
         1
     }
@@ -79,22 +119,30 @@ impl Component for Button {
         }
     }
     
-    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let val = match self.state.get() {
             true => SubnetState::On,
             false => SubnetState::Off,
         };
-        map!(0 => val)
+        map!(0 => val.into())
     }
 
     fn pressed(&self) -> SubnetState{
         self.state.set(true);
-        return SubnetState::On
+        SubnetState::On
     }
 
     fn released(&self) -> SubnetState {
         self.state.set(false);
-        return SubnetState::Off
+        SubnetState::Off
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_bool(self.state.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_bool(&self.state, state)
     }
 }
 
@@ -113,6 +161,10 @@ pub(crate) struct Switch {
 }
 
 impl Component for Switch {
+    fn id(&self) -> ComponentId {
+        ComponentId::Switch
+    }
+    
     fn ports(&self) -> usize {
         1
     }
@@ -124,23 +176,23 @@ impl Component for Switch {
         }
     }
 
-    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let val = match self.state.get() {
             true => SubnetState::On,
             false => SubnetState::Off,
         };
-        map!(0 => val)
+        map!(0 => val.into())
     }
 
     fn pressed(&self) -> SubnetState{
         match self.state.get(){
             true => {
                 self.state.set(false);
-                return SubnetState::Off
+                SubnetState::Off
             }
             false => {
                 self.state.set(true);
-                return SubnetState::On
+                SubnetState::On
             }
         }
     }
@@ -148,14 +200,22 @@ impl Component for Switch {
     fn released(&self) -> SubnetState {
         match self.state.get(){
             true => {
-                return SubnetState::On
+                SubnetState::On
             }
             false => {
                 self.state.set(true);
-                return SubnetState::Off
+                SubnetState::Off
             }
         }
     }
+    
+    fn save_state(&self) -> Option<String> {
+        save_bool(self.state.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_bool(&self.state, state)
+    }
 }
 
 impl Switch {
@@ -173,6 +233,10 @@ pub(crate) struct DFlipFlop {
 }
 
 impl Component for DFlipFlop {
+    fn id(&self) -> ComponentId {
+        ComponentId::DFlipFlop
+    }
+    
     fn ports(&self) -> usize {
         5 // 0 is D, 1 is clock, 2 is disable, 3 is Q, 4 is Q inverse
     }
@@ -185,7 +249,7 @@ impl Component for DFlipFlop {
         }
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let d = port_or_default!(data, 0);
         let clock = data.get(&1).map(|e| e.rising()).unwrap_or(false);
         let disable = port_or_default!(data, 2);
@@ -205,8 +269,8 @@ impl Component for DFlipFlop {
         };
     
         map!(
-            3 => vals.0,
-            4 => vals.1
+            3 => vals.0.into(),
+            4 => vals.1.into()
         )
     }
 
@@ -214,11 +278,11 @@ impl Component for DFlipFlop {
         match self.state.get(){
             true => {
                 self.state.set(false);
-                return SubnetState::Off
+                SubnetState::Off
             }
             false => {
                 self.state.set(true);
-                return SubnetState::On
+                SubnetState::On
             }
         }
     }
@@ -226,14 +290,26 @@ impl Component for DFlipFlop {
     fn released(&self) -> SubnetState {
         match self.state.get(){
             true => {
-                return SubnetState::On
+                SubnetState::On
             }
             false => {
                 self.state.set(true);
-                return SubnetState::Off
+                SubnetState::Off
             }
         }
     }
+    
+    fn stored_value(&self) -> Option<Value> {
+        Some(bool_value(self.state.get()))
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_bool(self.state.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_bool(&self.state, state)
+    }
 }
 
 impl DFlipFlop {
@@ -251,6 +327,10 @@ pub(crate) struct TFlipFlop {
 }
 
 impl Component for TFlipFlop {
+    fn id(&self) -> ComponentId {
+        ComponentId::TFlipFlop
+    }
+    
     fn ports(&self) -> usize {
         5 // 0 is T, 1 is clock, 2 is disable, 3 is Q, 4 is Q inverse
     }
@@ -263,15 +343,13 @@ impl Component for TFlipFlop {
         }
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let t = port_or_default!(data, 0);
         let clock = data.get(&1).map(|e| e.rising()).unwrap_or(false);
         let disable = port_or_default!(data, 2);
         
-        if clock && disable != SubnetState::On {
-            if t.truthy() {
-                self.state.set(!self.state.get());
-            }
+        if clock && disable != SubnetState::On && t.truthy() {
+            self.state.set(!self.state.get());
         }
         
         let vals = match self.state.get() {
@@ -280,8 +358,8 @@ impl Component for TFlipFlop {
         };
         
         map!(
-            3 => vals.0,
-            4 => vals.1
+            3 => vals.0.into(),
+            4 => vals.1.into()
         )
     }
 
@@ -289,11 +367,11 @@ impl Component for TFlipFlop {
         match self.state.get(){
             true => {
                 self.state.set(false);
-                return SubnetState::Off
+                SubnetState::Off
             }
             false => {
                 self.state.set(true);
-                return SubnetState::On
+                SubnetState::On
             }
         }
     }
@@ -301,14 +379,26 @@ impl Component for TFlipFlop {
     fn released(&self) -> SubnetState {
         match self.state.get(){
             true => {
-                return SubnetState::On
+                SubnetState::On
             }
             false => {
                 self.state.set(true);
-                return SubnetState::Off
+                SubnetState::Off
             }
         }
     }
+    
+    fn stored_value(&self) -> Option<Value> {
+        Some(bool_value(self.state.get()))
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_bool(self.state.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_bool(&self.state, state)
+    }
 }
 
 impl TFlipFlop {
@@ -327,6 +417,10 @@ pub(crate) struct JKFlipFlop {
 }
 
 impl Component for JKFlipFlop {
+    fn id(&self) -> ComponentId {
+        ComponentId::JKFlipFlop
+    }
+    
     fn ports(&self) -> usize {
         6 // 0 is J, 1 is K, 2 is clock, 3 is disable, 4 is Q, 5 is Q inverse
     }
@@ -339,7 +433,7 @@ impl Component for JKFlipFlop {
         }
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let j = port_or_default!(data, 0);
         let k = port_or_default!(data, 1);
         let clock = data.get(&2).map(|e| e.rising()).unwrap_or(false);
@@ -363,8 +457,8 @@ impl Component for JKFlipFlop {
         };
         
         map!(
-            4 => vals.0,
-            5 => vals.1
+            4 => vals.0.into(),
+            5 => vals.1.into()
         )
     }
 
@@ -372,11 +466,11 @@ impl Component for JKFlipFlop {
         match self.state.get(){
             true => {
                 self.state.set(false);
-                return SubnetState::Off
+                SubnetState::Off
             }
             false => {
                 self.state.set(true);
-                return SubnetState::On
+                SubnetState::On
             }
         }
     }
@@ -384,14 +478,26 @@ impl Component for JKFlipFlop {
     fn released(&self) -> SubnetState {
         match self.state.get(){
             true => {
-                return SubnetState::On
+                SubnetState::On
             }
             false => {
                 self.state.set(true);
-                return SubnetState::Off
+                SubnetState::Off
             }
         }
     }
+    
+    fn stored_value(&self) -> Option<Value> {
+        Some(bool_value(self.state.get()))
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_bool(self.state.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_bool(&self.state, state)
+    }
 }
 
 impl JKFlipFlop {
@@ -409,6 +515,10 @@ pub(crate) struct SRFlipFlop {
 }
 
 impl Component for SRFlipFlop {
+    fn id(&self) -> ComponentId {
+        ComponentId::SRFlipFlop
+    }
+    
     fn ports(&self) -> usize {
         6 // 0 is S, 1 is R, 2 is clock, 3 is disable, 4 is Q, 5 is Q inverse
     }
@@ -421,7 +531,7 @@ impl Component for SRFlipFlop {
         }
     }
     
-    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let s = port_or_default!(data, 0);
         let r = port_or_default!(data, 1);
         let clock = data.get(&2).map(|e| e.rising()).unwrap_or(false);
@@ -443,8 +553,8 @@ impl Component for SRFlipFlop {
         };
         
         map!(
-            4 => vals.0,
-            5 => vals.1
+            4 => vals.0.into(),
+            5 => vals.1.into()
         )
     }
 
@@ -452,11 +562,11 @@ impl Component for SRFlipFlop {
         match self.state.get(){
             true => {
                 self.state.set(false);
-                return SubnetState::Off
+                SubnetState::Off
             }
             false => {
                 self.state.set(true);
-                return SubnetState::On
+                SubnetState::On
             }
         }
     }
@@ -464,14 +574,26 @@ impl Component for SRFlipFlop {
     fn released(&self) -> SubnetState {
         match self.state.get(){
             true => {
-                return SubnetState::On
+                SubnetState::On
             }
             false => {
                 self.state.set(true);
-                return SubnetState::Off
+                SubnetState::Off
             }
         }
     }
+    
+    fn stored_value(&self) -> Option<Value> {
+        Some(bool_value(self.state.get()))
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_bool(self.state.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_bool(&self.state, state)
+    }
 }
 
 impl SRFlipFlop {
@@ -486,6 +608,10 @@ pub(crate) struct Clock {
 }
 
 impl Component for Clock {
+    fn id(&self) -> ComponentId {
+        ComponentId::Clock
+    }
+    
     fn ports(&self) -> usize {
         1
     }
@@ -497,15 +623,25 @@ impl Component for Clock {
         }
     }
     
-    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, SubnetState> {
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
         let val = match self.state.get() {
             true => SubnetState::On,
             false => SubnetState::Off
         };
         
-        self.state.set(!self.state.get());
-        
-        map!(0 => val)
+        map!(0 => val.into())
+    }
+    
+    fn drive(&self, value: Value) {
+        self.state.set(value.get(0).truthy());
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_bool(self.state.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_bool(&self.state, state)
     }
 }
 
@@ -513,4 +649,320 @@ impl Clock {
     pub(crate) fn new() -> Self {
         Self { state: Cell::new(false) }
     }
-}
\ No newline at end of file
+}
+/// The number on a port, if every bit of it is known
+fn port_number(data: &HashMap<usize, StateChange>, port: usize) -> Option<u64> {
+    data.get(&port).and_then(|e| e.current.to_u64())
+}
+
+fn save_number(number: u64) -> Option<String> {
+    Some(format!("{:x}", number))
+}
+
+fn load_number(cell: &Cell<u64>, state: &str, max: u64) -> bool {
+    match u64::from_str_radix(state, 16) {
+        Ok(number) if number <= max => cell.set(number),
+        _ => return false,
+    }
+    true
+}
+
+fn width_parameter(width: u8) -> Parameter {
+    Parameter::int("width", 1, MAX_WIDTH as i64, width as i64)
+}
+
+/// Stores a word on the rising edge of the clock. Ports are 0 for the word to store, 1 for the
+/// clock, 2 to enable storing, 3 to clear the register and 4 for the stored word. Clearing doesn't
+/// wait for the clock, and a register is enabled unless its enable port is off
+#[derive(Debug)]
+pub(crate) struct Register {
+    width: u8,
+    value: Cell<u64>,
+}
+
+impl Component for Register {
+    fn id(&self) -> ComponentId {
+        ComponentId::Register
+    }
+    
+    fn ports(&self) -> usize {
+        5
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            0..=3 => Some(PortType::Input),
+            4 => Some(PortType::Output),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 | 4 => Some(self.width),
+            1..=3 => Some(1),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let clock = data.get(&1).map(|e| e.rising()).unwrap_or(false);
+        let enable = port_or_default!(data, 2);
+        let clear = port_or_default!(data, 3);
+        
+        if clear == SubnetState::On {
+            self.value.set(0);
+        } else if clock && enable != SubnetState::Off {
+            // a word that isn't fully known can't be stored, the old one is kept
+            if let Some(word) = port_number(&data, 0) {
+                self.value.set(word);
+            }
+        }
+        
+        map!(4 => Value::from_u64(self.value.get(), self.width))
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_number(self.value.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_number(&self.value, state, mask(self.width))
+    }
+    
+    fn stored_value(&self) -> Option<Value> {
+        Some(Value::from_u64(self.value.get(), self.width))
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![width_parameter(self.width)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("width", ParameterValue::Int(width)) => self.width = width as u8,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        self.value.set(self.value.get() & mask(self.width));
+        Ok(())
+    }
+}
+
+impl Register {
+    pub(crate) fn new() -> Self {
+        Self { width: 8, value: Cell::new(0) }
+    }
+}
+
+/// The widest counter, its `max` is an integer parameter which can't hold every value of 64 bits
+pub(crate) const MAX_COUNTER_WIDTH: u8 = 63;
+
+/// Counts on the rising edge of the clock, from 0 up to `max` and around again. Ports are 0 for the
+/// word to load, 1 for the clock, 2 to load the word instead of counting, 3 to enable counting,
+/// 4 to count up instead of down, 5 to clear the counter, 6 for the count and 7 for the carry. The
+/// carry is on while the count is at `max` counting up or at 0 counting down. Clearing doesn't
+/// wait for the clock, counting is enabled and goes up unless the ports say otherwise
+#[derive(Debug)]
+pub(crate) struct Counter {
+    width: u8,
+    max: u64,
+    value: Cell<u64>,
+}
+
+impl Component for Counter {
+    fn id(&self) -> ComponentId {
+        ComponentId::Counter
+    }
+    
+    fn ports(&self) -> usize {
+        8
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            0..=5 => Some(PortType::Input),
+            6 | 7 => Some(PortType::Output),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            0 | 6 => Some(self.width),
+            1..=5 | 7 => Some(1),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let clock = data.get(&1).map(|e| e.rising()).unwrap_or(false);
+        let load = port_or_default!(data, 2);
+        let enable = port_or_default!(data, 3);
+        let up = port_or_default!(data, 4) != SubnetState::Off;
+        let clear = port_or_default!(data, 5);
+        
+        let value = self.value.get();
+        if clear == SubnetState::On {
+            self.value.set(0);
+        } else if clock && load == SubnetState::On {
+            if let Some(word) = port_number(&data, 0) {
+                self.value.set(word.min(self.max));
+            }
+        } else if clock && enable != SubnetState::Off {
+            self.value.set(match (up, value) {
+                (true, v) if v >= self.max => 0,
+                (true, v) => v + 1,
+                (false, 0) => self.max,
+                (false, v) => v - 1,
+            });
+        }
+        
+        let value = self.value.get();
+        let carry = if up { value == self.max } else { value == 0 };
+        map!(
+            6 => Value::from_u64(value, self.width),
+            7 => if carry { SubnetState::On } else { SubnetState::Off }.into()
+        )
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_number(self.value.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_number(&self.value, state, self.max)
+    }
+    
+    fn stored_value(&self) -> Option<Value> {
+        Some(Value::from_u64(self.value.get(), self.width))
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![
+            Parameter::int("width", 1, MAX_COUNTER_WIDTH as i64, self.width as i64),
+            Parameter::int("max", 0, mask(self.width) as i64, self.max as i64),
+        ]
+    }
+    
+    /// Changing the width lets the counter count through every value of the new width again
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("width", ParameterValue::Int(width)) => {
+                self.width = width as u8;
+                self.max = mask(self.width);
+            }
+            ("max", ParameterValue::Int(max)) => self.max = max as u64,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        self.value.set(self.value.get().min(self.max));
+        Ok(())
+    }
+}
+
+impl Counter {
+    pub(crate) fn new() -> Self {
+        Self { width: 8, max: mask(8), value: Cell::new(0) }
+    }
+}
+
+/// Shifts its bits by one on the rising edge of the clock. Ports are 0 for the bit shifted in, 1
+/// for the clock, 2 to enable shifting, 3 to shift left towards the most significant bit instead of
+/// right, 4 to load the word on port 5 instead of shifting, 6 to clear the register, 7 for all of
+/// its bits and 8 for the bit that is shifted out next. Clearing doesn't wait for the clock,
+/// shifting is enabled and goes left unless the ports say otherwise
+#[derive(Debug)]
+pub(crate) struct ShiftRegister {
+    length: u8,
+    value: Cell<u64>,
+}
+
+impl Component for ShiftRegister {
+    fn id(&self) -> ComponentId {
+        ComponentId::ShiftRegister
+    }
+    
+    fn ports(&self) -> usize {
+        9
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            0..=6 => Some(PortType::Input),
+            7 | 8 => Some(PortType::Output),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        match port {
+            5 | 7 => Some(self.length),
+            0..=4 | 6 | 8 => Some(1),
+            _ => None,
+        }
+    }
+    
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let clock = data.get(&1).map(|e| e.rising()).unwrap_or(false);
+        let enable = port_or_default!(data, 2);
+        let left = port_or_default!(data, 3) != SubnetState::Off;
+        let load = port_or_default!(data, 4);
+        let clear = port_or_default!(data, 6);
+        
+        let value = self.value.get();
+        let top = self.length as u32 - 1;
+        if clear == SubnetState::On {
+            self.value.set(0);
+        } else if clock && load == SubnetState::On {
+            if let Some(word) = port_number(&data, 5) {
+                self.value.set(word);
+            }
+        } else if clock && enable != SubnetState::Off {
+            // a bit that isn't known can't be shifted in, nothing moves
+            if let Some(bit) = port_number(&data, 0) {
+                self.value.set(if left {
+                    (value << 1 | bit) & mask(self.length)
+                } else {
+                    value >> 1 | bit << top
+                });
+            }
+        }
+        
+        let value = self.value.get();
+        let out = if left { value >> top & 1 } else { value & 1 };
+        map!(
+            7 => Value::from_u64(value, self.length),
+            8 => Value::from_u64(out, 1)
+        )
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        save_number(self.value.get())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        load_number(&self.value, state, mask(self.length))
+    }
+    
+    fn stored_value(&self) -> Option<Value> {
+        Some(Value::from_u64(self.value.get(), self.length))
+    }
+    
+    fn parameters(&self) -> Vec<Parameter> {
+        vec![Parameter::int("length", 1, MAX_WIDTH as i64, self.length as i64)]
+    }
+    
+    fn set_parameter(&mut self, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        match (name, value) {
+            ("length", ParameterValue::Int(length)) => self.length = length as u8,
+            _ => return Err(LogikError::UnknownParameter(String::from(name))),
+        }
+        self.value.set(self.value.get() & mask(self.length));
+        Ok(())
+    }
+}
+
+impl ShiftRegister {
+    pub(crate) fn new() -> Self {
+        Self { length: 8, value: Cell::new(0) }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::data::{next_clock_edge, Data};
+use crate::data::component::{Component, ComponentId, PortType, StateChange};
+use crate::data::subnet::Value;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+
+/// An instance of a circuit placed as a component. The `Input` components of the circuit, sorted by
+/// id, are its input ports and are followed by its `Output` components as the output ports. The
+/// circuit is stepped along with the circuit the instance is placed in, so changes take as long to
+/// get through it as the components inside take, plus the delay of the instance itself. Its clocks
+/// tick with the clocks outside
+#[derive(Debug)]
+pub(crate) struct Subcircuit {
+    name: String,
+    circuit: RefCell<Data>,
+    inputs: Vec<i32>,
+    outputs: Vec<i32>,
+}
+
+impl Component for Subcircuit {
+    fn id(&self) -> ComponentId {
+        ComponentId::Custom
+    }
+    
+    fn ports(&self) -> usize {
+        self.inputs.len() + self.outputs.len()
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        if port < self.inputs.len() {
+            Some(PortType::Input)
+        } else if port < self.ports() {
+            Some(PortType::Output)
+        } else {
+            None
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        let circuit = self.circuit.borrow();
+        match self.pin(port) {
+            Some(pin) => circuit.component(pin).ok()?.port_width(0),
+            None => None,
+        }
+    }
+    
+    /// Only schedules the inputs that changed, the outputs are what the circuit drives right now
+    fn evaluate(&self, data: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        let mut circuit = self.circuit.borrow_mut();
+        
+        for (port, input) in self.inputs.iter().enumerate() {
+            let value = match data.get(&port) {
+                Some(change) => change.current.clone(),
+                None => Value::floating(circuit.component(*input).unwrap().port_width(0).unwrap()),
+            };
+            if circuit.components.get(input).unwrap().1[0] != value {
+                circuit.drive_input(*input, value);
+            }
+        }
+        
+        self.outputs.iter()
+            .enumerate()
+            .map(|(i, output)| (self.inputs.len() + i, circuit.input_value(*output, 0)))
+            .collect()
+    }
+    
+    fn save_state(&self) -> Option<String> {
+        Some(self.circuit.borrow().save_states())
+    }
+    
+    fn load_state(&self, state: &str) -> bool {
+        self.circuit.borrow_mut().load_states(state)
+    }
+    
+    fn subcircuit(&self) -> Option<&Subcircuit> {
+        Some(self)
+    }
+}
+
+impl Subcircuit {
+    pub(crate) fn new(name: &str, mut circuit: Data) -> Self {
+        let pins = |id| {
+            let mut pins = circuit.components.iter()
+                .filter(|(_, component)| component.0.id() == id)
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            pins.sort_unstable();
+            pins
+        };
+        let inputs = pins(ComponentId::Input);
+        let outputs = pins(ComponentId::Output);
+        circuit.set_stepping(true);
+        
+        Self {
+            name: String::from(name),
+            circuit: RefCell::new(circuit),
+            inputs,
+            outputs,
+        }
+    }
+    
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+    
+    pub(crate) fn circuit(&self) -> Ref<'_, Data> {
+        self.circuit.borrow()
+    }
+    
+    pub(crate) fn circuit_mut(&self) -> RefMut<'_, Data> {
+        self.circuit.borrow_mut()
+    }
+    
+    /// The next time at which the circuit has something to do
+    pub(crate) fn next_event(&self) -> Option<u64> {
+        let circuit = self.circuit.borrow();
+        circuit.simulation.next_event(&circuit.components)
+    }
+    
+    /// Processes what the circuit has to do up to `time`
+    pub(crate) fn advance_to(&self, time: u64) {
+        self.circuit.borrow_mut().advance_to(time);
+    }
+    
+    /// The first edge after `time` of any clock in the circuit
+    pub(crate) fn next_clock_edge(&self, time: u64) -> Option<u64> {
+        let circuit = self.circuit.borrow();
+        next_clock_edge(time, &circuit.clocks, &circuit.components)
+    }
+    
+    /// Drives the clocks inside with the level they have at a clock edge outside
+    pub(crate) fn clock_edge(&self, clock_time: u64) {
+        self.circuit.borrow_mut().clock_edge(clock_time);
+    }
+    
+    pub(crate) fn watch_toggling(&self) {
+        let circuit = &mut *self.circuit.borrow_mut();
+        circuit.simulation.watch_toggling(&circuit.components);
+    }
+    
+    /// Marks the subnets of the circuit that kept changing while being watched, returns whether
+    /// there were any
+    pub(crate) fn mark_toggling(&self) -> bool {
+        let circuit = &mut *self.circuit.borrow_mut();
+        circuit.simulation.mark_toggling(&circuit.components, &mut circuit.subnets)
+    }
+    
+    /// The `Input` or `Output` component inside the circuit that a port stands for
+    pub(crate) fn pin(&self, port: usize) -> Option<i32> {
+        self.inputs.iter().chain(&self.outputs).nth(port).copied()
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+
+/// Everything that can go wrong when editing or querying a circuit
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum LogikError {
+    UnknownComponent(i32),
+    UnknownSubnet(i32),
+    PortOutOfRange { component: i32, port: usize },
+    PortAlreadyLinked { component: i32, port: usize, subnet: i32 },
+    NotLinked { component: i32, port: usize, subnet: i32 },
+    WidthMismatch { port_width: u8, subnet_width: u8 },
+    InvalidWidth(u8),
+    SubnetAlreadyExists(i32),
+    WrongPortCount { expected: usize, got: usize },
+    InvalidDelay(u32),
+    /// The handle passed over FFI is null or has already been freed
+    InvalidHandle,
+    /// An earlier call on the instance panicked, the only thing left to do with it is freeing it
+    Poisoned,
+    Panicked(String),
+    UnknownComponentType(i32),
+    InvalidNetlist { line: usize, reason: String },
+    Io(String),
+    SubcircuitExists(String),
+    UnknownSubcircuit(String),
+    NotASubcircuit(i32),
+    InvalidName(String),
+    NoTrace,
+    NotAClock(i32),
+    InvalidPeriod { high: u32, low: u32 },
+    InvalidParameter(String),
+    UnknownParameter(String),
+    NotAMemory(i32),
+    AddressOutOfRange(u64),
+    WordTooWide { word: u64, width: u8 },
+    InvalidImage(String),
+    NoStoredValue(i32),
+    InvalidVerilog { line: usize, reason: String },
+    InvalidLogisim(String),
+    NotAnInput(i32),
+    TooManyInputs(u32),
+    InvalidExpression { column: usize, reason: String },
+    CombinationalLoop(i32),
+}
+
+impl LogikError {
+    /// A stable number identifying the kind of error, used across the FFI boundary where 0 means
+    /// that there was no error
+    pub fn code(&self) -> i32 {
+        match self {
+            LogikError::UnknownComponent(_) => 1,
+            LogikError::UnknownSubnet(_) => 2,
+            LogikError::PortOutOfRange { .. } => 3,
+            LogikError::PortAlreadyLinked { .. } => 4,
+            LogikError::NotLinked { .. } => 5,
+            LogikError::WidthMismatch { .. } => 6,
+            LogikError::InvalidWidth(_) => 7,
+            LogikError::SubnetAlreadyExists(_) => 8,
+            LogikError::WrongPortCount { .. } => 9,
+            LogikError::InvalidDelay(_) => 10,
+            LogikError::InvalidHandle => 11,
+            LogikError::Poisoned => 12,
+            LogikError::Panicked(_) => 13,
+            LogikError::UnknownComponentType(_) => 14,
+            LogikError::InvalidNetlist { .. } => 15,
+            LogikError::Io(_) => 16,
+            LogikError::SubcircuitExists(_) => 17,
+            LogikError::UnknownSubcircuit(_) => 18,
+            LogikError::NotASubcircuit(_) => 19,
+            LogikError::InvalidName(_) => 20,
+            LogikError::NoTrace => 21,
+            LogikError::NotAClock(_) => 22,
+            LogikError::InvalidPeriod { .. } => 23,
+            LogikError::InvalidParameter(_) => 24,
+            LogikError::UnknownParameter(_) => 25,
+            LogikError::NotAMemory(_) => 26,
+            LogikError::AddressOutOfRange(_) => 27,
+            LogikError::WordTooWide { .. } => 28,
+            LogikError::InvalidImage(_) => 29,
+            LogikError::NoStoredValue(_) => 30,
+            LogikError::InvalidVerilog { .. } => 31,
+            LogikError::InvalidLogisim(_) => 32,
+            LogikError::NotAnInput(_) => 33,
+            LogikError::TooManyInputs(_) => 34,
+            LogikError::InvalidExpression { .. } => 35,
+            LogikError::CombinationalLoop(_) => 36,
+        }
+    }
+}
+
+impl fmt::Display for LogikError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogikError::UnknownComponent(id) => write!(f, "there is no component with id {}", id),
+            LogikError::UnknownSubnet(id) => write!(f, "there is no subnet with id {}", id),
+            LogikError::PortOutOfRange { component, port } =>
+                write!(f, "component {} has no port {}", component, port),
+            LogikError::PortAlreadyLinked { component, port, subnet } =>
+                write!(f, "port {} of component {} is already linked to subnet {}", port, component, subnet),
+            LogikError::NotLinked { component, port, subnet } =>
+                write!(f, "port {} of component {} is not linked to subnet {}", port, component, subnet),
+            LogikError::WidthMismatch { port_width, subnet_width } =>
+                write!(f, "a port with width {} can't be linked to a subnet with width {}", port_width, subnet_width),
+            LogikError::InvalidWidth(width) => write!(f, "{} is not a valid subnet width", width),
+            LogikError::SubnetAlreadyExists(id) => write!(f, "there already is a subnet with id {}", id),
+            LogikError::WrongPortCount { expected, got } =>
+                write!(f, "the component has {} ports but {} were given", expected, got),
+            LogikError::InvalidDelay(delay) => write!(f, "{} is not a valid delay", delay),
+            LogikError::InvalidHandle => write!(f, "the handle is null or has already been freed"),
+            LogikError::Poisoned => write!(f, "the instance is unusable because an earlier call panicked"),
+            LogikError::Panicked(message) => write!(f, "the simulation panicked: {}", message),
+            LogikError::UnknownComponentType(id) => write!(f, "{} is not a known component type", id),
+            LogikError::InvalidNetlist { line, reason } => write!(f, "invalid netlist on line {}: {}", line, reason),
+            LogikError::Io(message) => write!(f, "{}", message),
+            LogikError::SubcircuitExists(name) => write!(f, "there already is a subcircuit called {}", name),
+            LogikError::UnknownSubcircuit(name) => write!(f, "there is no subcircuit called {}", name),
+            LogikError::NotASubcircuit(id) => write!(f, "component {} is not a subcircuit", id),
+            LogikError::InvalidName(name) => write!(f, "'{}' is not a valid name", name),
+            LogikError::NoTrace => write!(f, "nothing has been recorded"),
+            LogikError::NotAClock(id) => write!(f, "component {} is not a clock", id),
+            LogikError::InvalidPeriod { high, low } =>
+                write!(f, "a clock can't be high for {} and low for {} units of time", high, low),
+            LogikError::InvalidParameter(reason) => write!(f, "invalid parameter, {}", reason),
+            LogikError::UnknownParameter(name) => write!(f, "there is no parameter called {}", name),
+            LogikError::NotAMemory(id) => write!(f, "component {} is not a memory", id),
+            LogikError::AddressOutOfRange(address) => write!(f, "address {:#x} is out of range", address),
+            LogikError::WordTooWide { word, width } => write!(f, "{:#x} doesn't fit into {} bits", word, width),
+            LogikError::InvalidImage(reason) => write!(f, "invalid memory image, {}", reason),
+            LogikError::NoStoredValue(id) => write!(f, "component {} doesn't store a value", id),
+            LogikError::InvalidVerilog { line, reason } => write!(f, "invalid Verilog on line {}: {}", line, reason),
+            LogikError::InvalidLogisim(reason) => write!(f, "invalid Logisim circuit, {}", reason),
+            LogikError::NotAnInput(id) => write!(f, "subnet {} isn't driven by an input or a constant", id),
+            LogikError::TooManyInputs(bits) => write!(f, "a truth table over {} input bits would be too large", bits),
+            LogikError::InvalidExpression { column, reason } =>
+                write!(f, "invalid expression at column {}: {}", column, reason),
+            LogikError::CombinationalLoop(id) => write!(f, "subnet {} is part of a combinational loop", id),
+        }
+    }
+}
+
+impl std::error::Error for LogikError {}
+
+impl From<std::io::Error> for LogikError {
+    fn from(e: std::io::Error) -> Self {
+        LogikError::Io(e.to_string())
+    }
+}
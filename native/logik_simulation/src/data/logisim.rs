@@ -0,0 +1,644 @@
+use crate::data::{ClockPeriod, Data};
+use crate::data::component::{Component, ComponentId};
+use crate::data::component::components::{MAX_INPUTS, MIN_INPUTS};
+use crate::data::component::parameter::{set_parameter, ParameterValue};
+use crate::data::error::LogikError;
+use crate::data::subnet::{Value, MAX_WIDTH};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+fn invalid(reason: String) -> LogikError {
+    LogikError::InvalidLogisim(reason)
+}
+
+impl Data {
+    /// Builds a circuit out of a Logisim `.circ` file. The circuit named by `<main>` is imported,
+    /// wires that share an end are joined into one subnet and so are tunnels with the same label.
+    /// Anything that has no counterpart here, like a subcircuit or a flip-flop with its reset
+    /// wired up, is refused with the name and location of every such component
+    pub(crate) fn from_logisim(text: &str) -> Result<Data, LogikError> {
+        let project = Reader::new(text).document()?;
+        if project.name != "project" {
+            return Err(invalid(format!("expected a <project> but found a <{}>", project.name)));
+        }
+        
+        let libraries = project.children("lib")
+            .filter_map(|lib| Some((lib.attribute("name")?, lib.attribute("desc")?)))
+            .collect::<HashMap<_, _>>();
+        let main = project.children("main").next().and_then(|main| main.attribute("name"));
+        let circuit = project.children("circuit")
+            .find(|circuit| main.is_none() || circuit.attribute("name") == main)
+            .ok_or_else(|| invalid(String::from("there is no circuit to import")))?;
+        
+        let mut importer = Importer::default();
+        for wire in circuit.children("wire") {
+            let from = point(wire.attribute("from"))?;
+            let to = point(wire.attribute("to"))?;
+            importer.wire(from, to);
+        }
+        for component in circuit.children("comp") {
+            let name = component.attribute("name").ok_or_else(|| invalid(String::from("a component has no name")))?;
+            let location = point(component.attribute("loc"))?;
+            match component.attribute("lib") {
+                Some(lib) => {
+                    let library = libraries.get(lib).ok_or_else(|| invalid(format!("there is no library {}", lib)))?;
+                    importer.component(library, name, location, component)?;
+                }
+                None => importer.unsupported(format!("subcircuit {}", name), location),
+            }
+        }
+        importer.finish()
+    }
+}
+
+/// An element of an XML document, text between elements is left out since Logisim keeps
+/// everything that matters in attributes
+#[derive(Debug)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+    
+    fn children<'e>(&'e self, name: &'e str) -> impl Iterator<Item = &'e Element> + 'e {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+    
+    /// The value of a component attribute, which Logisim writes as `<a name="..." val="..."/>`
+    fn setting(&self, name: &str) -> Option<&str> {
+        self.children("a").find(|a| a.attribute("name") == Some(name)).and_then(|a| a.attribute("val"))
+    }
+}
+
+/// How deep elements can be nested, Logisim itself never goes deeper than a handful of levels
+const MAX_DEPTH: usize = 64;
+
+/// Reads just enough of XML for Logisim files: elements, attributes, character references,
+/// comments, CDATA sections, processing instructions and a doctype
+struct Reader<'t> {
+    text: &'t str,
+    position: usize,
+}
+
+impl<'t> Reader<'t> {
+    fn new(text: &'t str) -> Self {
+        Self { text, position: 0 }
+    }
+    
+    fn error(&self, reason: &str) -> LogikError {
+        let line = self.text[..self.position].matches('\n').count() + 1;
+        invalid(format!("{} on line {}", reason, line))
+    }
+    
+    fn rest(&self) -> &'t str {
+        &self.text[self.position..]
+    }
+    
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+    
+    /// Moves past the next `end`
+    fn skip_past(&mut self, end: &str) -> Result<(), LogikError> {
+        match self.rest().find(end) {
+            Some(offset) => {
+                self.position += offset + end.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("'{}' is missing", end))),
+        }
+    }
+    
+    /// Skips a comment, CDATA section, processing instruction or doctype if one starts here
+    fn skip_markup(&mut self) -> Result<bool, LogikError> {
+        for (start, end) in [("<!--", "-->"), ("<![CDATA[", "]]>"), ("<?", "?>"), ("<!", ">")] {
+            if self.rest().starts_with(start) {
+                self.skip_past(end)?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+    
+    fn expect(&mut self, symbol: &str) -> Result<(), LogikError> {
+        if !self.rest().starts_with(symbol) {
+            return Err(self.error(&format!("expected '{}'", symbol)));
+        }
+        self.position += symbol.len();
+        Ok(())
+    }
+    
+    fn name(&mut self) -> Result<String, LogikError> {
+        let rest = self.rest();
+        let length = rest.find(|c: char| !(c.is_alphanumeric() || "_-.:".contains(c))).unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.position += length;
+        Ok(String::from(&rest[..length]))
+    }
+    
+    fn skip_prolog(&mut self) -> Result<(), LogikError> {
+        loop {
+            self.skip_whitespace();
+            if !self.skip_markup()? {
+                return Ok(());
+            }
+        }
+    }
+    
+    /// Reads the root element, only whitespace and markup can come after it
+    fn document(&mut self) -> Result<Element, LogikError> {
+        self.skip_prolog()?;
+        let root = self.element(0)?;
+        self.skip_prolog()?;
+        if !self.rest().is_empty() {
+            return Err(self.error(&format!("expected the end of the file after </{}>", root.name)));
+        }
+        Ok(root)
+    }
+    
+    fn element(&mut self, depth: usize) -> Result<Element, LogikError> {
+        if depth == MAX_DEPTH {
+            return Err(self.error(&format!("elements are nested more than {} deep", MAX_DEPTH)));
+        }
+        self.expect("<")?;
+        let name = self.name()?;
+        let mut element = Element { name, attributes: Vec::new(), children: Vec::new() };
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.position += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.position += 1;
+                break;
+            }
+            
+            let key = self.name()?;
+            if element.attribute(&key).is_some() {
+                return Err(self.error(&format!("<{}> has the attribute {} twice", element.name, key)));
+            }
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => quote,
+                _ => return Err(self.error("expected a quoted value")),
+            };
+            self.position += 1;
+            let length = self.rest().find(quote).ok_or_else(|| self.error("an attribute value isn't closed"))?;
+            let value = unescape(&self.rest()[..length]).ok_or_else(|| self.error("unknown character reference"))?;
+            self.position += length + 1;
+            element.attributes.push((key, value));
+        }
+        
+        loop {
+            let text = self.rest().find('<').ok_or_else(|| self.error(&format!("<{}> isn't closed", element.name)))?;
+            self.position += text;
+            if self.rest().starts_with("</") {
+                self.position += 2;
+                let name = self.name()?;
+                if name != element.name {
+                    return Err(self.error(&format!("</{}> closes <{}>", name, element.name)));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(element);
+            }
+            if !self.skip_markup()? {
+                let child = self.element(depth + 1)?;
+                element.children.push(child);
+            }
+        }
+    }
+}
+
+/// Replaces the character references in an attribute value
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find(';')? + start;
+        let character = match &rest[start + 1..end] {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            reference => {
+                let number = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => reference.strip_prefix('#')?.parse().ok()?,
+                };
+                std::char::from_u32(number)?
+            }
+        };
+        result.push(character);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Some(result)
+}
+
+type Point = (i32, i32);
+
+/// Reads a location like `(120,40)`
+fn point(text: Option<&str>) -> Result<Point, LogikError> {
+    let text = text.ok_or_else(|| invalid(String::from("a location is missing")))?;
+    let coordinates = text.trim()
+        .strip_prefix('(')
+        .and_then(|text| text.strip_suffix(')'))
+        .and_then(|text| text.split_once(','))
+        .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)));
+    coordinates.ok_or_else(|| invalid(format!("'{}' is not a location", text)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Facing {
+    East,
+    West,
+    North,
+    South,
+}
+
+impl Facing {
+    /// Moves `dx` along the way a component faces and `dy` across it, the way Logisim turns
+    /// the ports of a component that faces east
+    fn translate(self, (x, y): Point, dx: i32, dy: i32) -> Point {
+        match self {
+            Facing::East => (x + dx, y + dy),
+            Facing::West => (x - dx, y - dy),
+            Facing::South => (x - dy, y + dx),
+            Facing::North => (x + dy, y - dx),
+        }
+    }
+}
+
+/// Where a port of a component is connected
+#[derive(Debug, Clone, Copy)]
+enum End {
+    At(Point),
+    /// Through a `Not`, for the inputs Logisim inverts inside of the component
+    Inverted(Point),
+}
+
+#[derive(Debug)]
+struct Part {
+    component: Box<dyn Component>,
+    ends: Vec<Option<End>>,
+    /// Takes the width of the wires it is connected to
+    adapts: bool,
+    period: Option<ClockPeriod>,
+}
+
+/// Collects the components and joins wires, points that are joined are nodes of a union-find.
+/// Whether a port is connected to anything is only known once every wire and component has been
+/// seen, so the ports are linked in `finish`
+#[derive(Default)]
+struct Importer {
+    parents: Vec<usize>,
+    nodes: HashMap<Point, usize>,
+    uses: HashMap<Point, usize>, // <point, how many wire ends, tunnels and ports are there>
+    tunnels: HashMap<String, usize>, // <label, node>
+    parts: Vec<Part>,
+    /// Ports that aren't supported when they're connected, with what they'd make unsupported
+    unused: Vec<(Point, String, Point)>,
+    unsupported: Vec<String>,
+}
+
+impl Importer {
+    fn node(&mut self, point: Point) -> usize {
+        let parents = &mut self.parents;
+        *self.nodes.entry(point).or_insert_with(|| {
+            parents.push(parents.len());
+            parents.len() - 1
+        })
+    }
+    
+    fn fresh(&mut self) -> usize {
+        self.parents.push(self.parents.len());
+        self.parents.len() - 1
+    }
+    
+    fn find(&self, mut node: usize) -> usize {
+        while self.parents[node] != node {
+            node = self.parents[node];
+        }
+        node
+    }
+    
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+    
+    fn touch(&mut self, point: Point) -> usize {
+        *self.uses.entry(point).or_insert(0) += 1;
+        self.node(point)
+    }
+    
+    fn connected(&self, point: Point) -> bool {
+        self.uses.get(&point).copied().unwrap_or(0) > 1
+    }
+    
+    fn wire(&mut self, from: Point, to: Point) {
+        let from = self.touch(from);
+        let to = self.touch(to);
+        self.join(from, to);
+    }
+    
+    fn unsupported(&mut self, what: String, (x, y): Point) {
+        self.unsupported.push(format!("{} at ({},{})", what, x, y));
+    }
+    
+    fn add(&mut self, component: Box<dyn Component>, ends: Vec<Option<End>>) {
+        for end in ends.iter().flatten() {
+            match end {
+                End::At(point) | End::Inverted(point) => self.touch(*point),
+            };
+        }
+        self.parts.push(Part { component, ends, adapts: false, period: None });
+    }
+    
+    fn component(&mut self, library: &str, name: &str, location: Point, element: &Element) -> Result<(), LogikError> {
+        let setting = |key: &str| element.setting(key);
+        let number = |key: &str, default: i64| match setting(key) {
+            Some(text) => text.trim().parse::<i64>().map_err(|_| invalid(format!("{} of {} isn't a number", key, name))),
+            None => Ok(default),
+        };
+        let width = number("width", 1)?;
+        if width < 1 || width > MAX_WIDTH as i64 {
+            return Err(invalid(format!("{} at {:?} is {} bits wide", name, location, width)));
+        }
+        let facing = match setting("facing") {
+            None | Some("east") => Facing::East,
+            Some("west") => Facing::West,
+            Some("north") => Facing::North,
+            Some("south") => Facing::South,
+            Some(facing) => return Err(invalid(format!("{} can't face {}", name, facing))),
+        };
+        
+        match (library, name) {
+            ("#Wiring", "Pin") => {
+                let output = setting("output") == Some("true");
+                let kind = if output { ComponentId::Output } else { ComponentId::Input };
+                // labels can't contain whitespace here, while Logisim allows it
+                let label = setting("label").unwrap_or("").split_whitespace().collect::<Vec<_>>().join("_");
+                let mut pin = kind.build()?;
+                set_parameter(pin.as_mut(), "label", ParameterValue::String(label))?;
+                set_parameter(pin.as_mut(), "width", ParameterValue::Int(width))?;
+                if !output {
+                    pin.drive(Value::from_u64(0, width as u8));
+                }
+                self.add(pin, vec![Some(End::At(location))]);
+            }
+            ("#Wiring", "Clock") => {
+                let high = u32::try_from(number("highDuration", 1)?).unwrap_or(0);
+                let low = u32::try_from(number("lowDuration", 1)?).unwrap_or(0);
+                self.add(ComponentId::Clock.build()?, vec![Some(End::At(location))]);
+                self.parts.last_mut().unwrap().period = Some(ClockPeriod { high, low, phase: 0 });
+            }
+            ("#Wiring", "Constant") | ("#Wiring", "Power") | ("#Wiring", "Ground") => {
+                let value = match (name, setting("value")) {
+                    ("Power", _) => u64::MAX,
+                    ("Ground", _) => 0,
+                    (_, None) => 1,
+                    (_, Some(text)) => {
+                        let value = match text.strip_prefix("0x") {
+                            Some(hex) => u64::from_str_radix(hex, 16),
+                            None => text.parse(),
+                        };
+                        value.map_err(|_| invalid(format!("'{}' is not the value of a constant", text)))?
+                    }
+                };
+                let mut constant = ComponentId::Constant.build()?;
+                set_parameter(constant.as_mut(), "value", ParameterValue::Bits(Value::from_u64(value, width as u8)))?;
+                self.add(constant, vec![Some(End::At(location))]);
+            }
+            ("#Wiring", "Probe") => self.add(ComponentId::Probe.build()?, vec![Some(End::At(location))]),
+            ("#Wiring", "Pull Resistor") => {
+                let kind = match setting("pull") {
+                    None | Some("0") => ComponentId::PullDown,
+                    Some("1") => ComponentId::PullUp,
+                    Some(_) => {
+                        self.unsupported(String::from("Pull Resistor to an error"), location);
+                        return Ok(());
+                    }
+                };
+                self.add(kind.build()?, vec![Some(End::At(location))]);
+                self.parts.last_mut().unwrap().adapts = true;
+            }
+            ("#Wiring", "Tunnel") => {
+                let node = self.touch(location);
+                let label = String::from(setting("label").unwrap_or(""));
+                match self.tunnels.get(&label) {
+                    Some(tunnel) => self.join(node, *tunnel),
+                    None => {
+                        self.tunnels.insert(label, node);
+                    }
+                }
+            }
+            ("#Gates", _) if width != 1 => self.unsupported(format!("{} bit {}", width, name), location),
+            ("#Gates", "Buffer") => {
+                let input = facing.translate(location, -20, 0);
+                self.add(ComponentId::Buffer.build()?, vec![Some(End::At(input)), Some(End::At(location))]);
+            }
+            ("#Gates", "NOT Gate") => {
+                let size = number("size", 30)? as i32;
+                let input = facing.translate(location, -size, 0);
+                self.add(ComponentId::Not.build()?, vec![Some(End::At(input)), Some(End::At(location))]);
+            }
+            ("#Gates", "AND Gate") => self.gate(ComponentId::And, location, facing, element, 0)?,
+            ("#Gates", "OR Gate") => self.gate(ComponentId::Or, location, facing, element, 0)?,
+            ("#Gates", "NAND Gate") => self.gate(ComponentId::Nand, location, facing, element, 10)?,
+            ("#Gates", "NOR Gate") => self.gate(ComponentId::Nor, location, facing, element, 10)?,
+            ("#Gates", "XOR Gate") => self.gate(ComponentId::Xor, location, facing, element, 10)?,
+            ("#Gates", "XNOR Gate") => self.gate(ComponentId::Xnor, location, facing, element, 20)?,
+            ("#Memory", "D Flip-Flop") => self.flip_flop(ComponentId::DFlipFlop, name, location, element)?,
+            ("#Memory", "T Flip-Flop") => self.flip_flop(ComponentId::TFlipFlop, name, location, element)?,
+            ("#Memory", "J-K Flip-Flop") => self.flip_flop(ComponentId::JKFlipFlop, name, location, element)?,
+            ("#Memory", "S-R Flip-Flop") => self.flip_flop(ComponentId::SRFlipFlop, name, location, element)?,
+            ("#I/O", "LED") => self.add(ComponentId::LED.build()?, vec![Some(End::At(location))]),
+            ("#I/O", "Button") => self.add(ComponentId::Button.build()?, vec![Some(End::At(location))]),
+            ("#Base", "Text") => {}
+            _ => self.unsupported(String::from(name), location),
+        }
+        Ok(())
+    }
+    
+    /// Adds a gate whose output is at `location`. The inputs are behind the body of the gate, which
+    /// is `offset` longer than its size for the extra curve of XOR gates and the circle of inverted
+    /// outputs. They're spread across it the way Logisim spreads them, leaving the middle free for
+    /// an even number of inputs
+    fn gate(&mut self, kind: ComponentId, location: Point, facing: Facing, element: &Element, offset: i32) -> Result<(), LogikError> {
+        let inputs = match element.setting("inputs") {
+            Some(text) => text.trim().parse::<usize>().map_err(|_| invalid(format!("a gate can't have '{}' inputs", text)))?,
+            None => 5,
+        };
+        if !(MIN_INPUTS..=MAX_INPUTS).contains(&inputs) {
+            return Err(invalid(format!("a gate can't have {} inputs", inputs)));
+        }
+        let size = match element.setting("size") {
+            Some(text) => text.trim().parse::<i32>().map_err(|_| invalid(format!("'{}' isn't the size of a gate", text)))?,
+            None => 50,
+        };
+        
+        let (start, distance, gap) = match inputs {
+            2..=3 if size < 40 => (-5, 10, 10),
+            2..=3 if size < 60 || inputs == 2 => (-10, 20, 20),
+            2..=3 => (-15, 30, 30),
+            _ => (-5, 10, 10),
+        };
+        let mut ends = Vec::with_capacity(inputs + 1);
+        for input in 0..inputs {
+            let dy = if inputs % 2 == 1 {
+                start * (inputs as i32 - 1) + distance * input as i32
+            } else {
+                start * inputs as i32 + distance * input as i32 + if input >= inputs / 2 { gap } else { 0 }
+            };
+            let point = facing.translate(location, -(size + offset), dy);
+            let negated = element.setting(&format!("negate{}", input)) == Some("true");
+            ends.push(Some(if negated { End::Inverted(point) } else { End::At(point) }));
+        }
+        ends.push(Some(End::At(location)));
+        
+        let mut gate = kind.build_with_params(&[inputs as i32])?;
+        if kind == ComponentId::Xor || kind == ComponentId::Xnor {
+            let one_hot = element.setting("xor") != Some("odd");
+            set_parameter(gate.as_mut(), "one_hot", ParameterValue::Bool(one_hot))?;
+        }
+        self.add(gate, ends);
+        Ok(())
+    }
+    
+    /// Adds a flip-flop whose Q output is at `location`. The inputs are on the left with the
+    /// clock below a single input or between two of them, Logisim's enable is the inverse of the
+    /// disable port here. Reset and preset have no counterpart, so they may not be connected
+    fn flip_flop(&mut self, kind: ComponentId, name: &str, location: Point, element: &Element) -> Result<(), LogikError> {
+        let (x, y) = location;
+        let clock = match element.setting("trigger") {
+            None | Some("rise") => End::At,
+            Some("fall") => End::Inverted,
+            Some(_) => {
+                self.unsupported(format!("level triggered {}", name), location);
+                return Ok(());
+            }
+        };
+        
+        let mut ends = match kind {
+            ComponentId::DFlipFlop | ComponentId::TFlipFlop => vec![Some(End::At((x - 40, y))), Some(clock((x - 40, y + 20)))],
+            _ => vec![Some(End::At((x - 40, y))), Some(End::At((x - 40, y + 20))), Some(clock((x - 40, y + 10)))],
+        };
+        ends.push(Some(End::Inverted((x - 20, y + 30))));
+        ends.push(Some(End::At(location)));
+        ends.push(Some(End::At((x, y + 20))));
+        self.unused.push(((x - 10, y + 30), format!("{} with a reset", name), location));
+        self.unused.push(((x - 30, y + 30), format!("{} with a preset", name), location));
+        self.add(kind.build()?, ends);
+        Ok(())
+    }
+    
+    fn finish(mut self) -> Result<Data, LogikError> {
+        for (point, what, location) in std::mem::take(&mut self.unused) {
+            if self.uses.get(&point).copied().unwrap_or(0) > 0 {
+                self.unsupported(what, location);
+            }
+        }
+        if !self.unsupported.is_empty() {
+            return Err(invalid(format!("unsupported components: {}", self.unsupported.join(", "))));
+        }
+        
+        let mut parts = Vec::with_capacity(self.parts.len());
+        let mut inverters = Vec::new();
+        for part in std::mem::take(&mut self.parts) {
+            let mut links = Vec::with_capacity(part.ends.len());
+            for end in &part.ends {
+                links.push(match end {
+                    Some(End::At(point)) if self.connected(*point) => Some(self.nodes[point]),
+                    Some(End::Inverted(point)) if self.connected(*point) => {
+                        let inverted = self.fresh();
+                        inverters.push((ComponentId::Not.build()?, vec![Some(self.nodes[point]), Some(inverted)]));
+                        Some(inverted)
+                    }
+                    _ => None,
+                });
+            }
+            parts.push((part, links));
+        }
+        
+        let mut widths = HashMap::new();
+        let all = parts.iter().map(|(part, links)| (part.component.as_ref(), links, part.adapts))
+            .chain(inverters.iter().map(|(component, links)| (component.as_ref(), links, false)));
+        for (component, links, adapts) in all {
+            for (port, node) in links.iter().enumerate() {
+                let node = match node {
+                    Some(node) if !adapts => *node,
+                    _ => continue,
+                };
+                let width = component.port_width(port).unwrap();
+                let root = self.find(node);
+                match widths.insert(root, width) {
+                    Some(other) if other != width => {
+                        let (x, y) = self.nodes.iter()
+                            .filter(|(_, node)| self.find(**node) == root)
+                            .map(|(point, _)| *point)
+                            .min()
+                            .unwrap();
+                        return Err(invalid(format!("the wires at ({},{}) connect ports that are {} and {} bits wide", x, y, other.min(width), other.max(width))));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        
+        let mut data = Data::new();
+        let mut subnets = HashMap::new();
+        let mut placed = Vec::new();
+        let all = parts.into_iter()
+            .map(|(part, links)| (part.component, links, part.adapts, part.period))
+            .chain(inverters.into_iter().map(|(component, links)| (component, links, false, None)));
+        for (mut component, links, adapts, period) in all {
+            let mut subnet_links = Vec::with_capacity(links.len());
+            for node in links {
+                subnet_links.push(match node {
+                    Some(node) => {
+                        let root = self.find(node);
+                        let width = widths.get(&root).copied().unwrap_or(1);
+                        if adapts {
+                            set_parameter(component.as_mut(), "width", ParameterValue::Int(width as i64))?;
+                        }
+                        let next = subnets.len() as i32 + 1;
+                        let subnet = *subnets.entry(root).or_insert(next);
+                        if subnet == next {
+                            data.add_subnet_with_width(subnet, width)?;
+                        }
+                        Some(subnet)
+                    }
+                    None => None,
+                });
+            }
+            placed.push((component, subnet_links, period));
+        }
+        for (component, links, period) in placed {
+            let id = data.add_component(component, links)?;
+            if let Some(period) = period {
+                data.clock(id);
+                data.set_clock_period(id, period)?;
+            }
+        }
+        
+        data.resimulate();
+        data.set_stepping(false);
+        data.simulation.time = 0;
+        Ok(data)
+    }
+}
@@ -1,12 +1,23 @@
-use std::collections::{HashMap, HashSet, BinaryHeap, VecDeque};
+use std::collections::{HashMap, HashSet, BinaryHeap, BTreeMap};
 use std::collections::hash_map::Entry;
 
-use crate::data::component::{Component, PortType, StateChange};
-use crate::data::subnet::{Subnet, SubnetState};
+use crate::data::component::{Component, PortType, StateChange, Strength};
+use crate::data::component::subcircuit::Subcircuit;
+use crate::data::component::parameter::{Parameter, ParameterValue};
+use crate::data::component::memory::{ImageFormat, Memory};
+use crate::data::subnet::{Subnet, SubnetState, Value, MAX_WIDTH};
+use crate::data::error::LogikError;
+use crate::data::trace::Trace;
 use std::cmp::Reverse;
 
 pub(crate) mod subnet;
+pub(crate) mod analysis;
 pub(crate) mod component;
+pub(crate) mod error;
+pub(crate) mod logisim;
+pub(crate) mod netlist;
+pub(crate) mod trace;
+pub(crate) mod verilog;
 
 #[cfg(test)]
 mod test;
@@ -14,13 +25,14 @@ mod test;
 /// Struct to represent the data that the backend should keep track of
 #[derive(Debug)]
 pub struct Data {
-    components: HashMap<i32, (Box<dyn Component>, Vec<SubnetState>)>, // should be an option
+    components: HashMap<i32, (Box<dyn Component>, Vec<Value>)>, // should be an option
     components_free: BinaryHeap<Reverse<i32>>,
     subnets: HashMap<i32, Subnet>,
     // <id, subnet>
     subnet_edges: HashMap<i32, HashSet<Edge>>,
     component_edges: HashMap<i32, HashSet<Edge>>,
-    clocks: Vec<i32>,
+    clocks: BTreeMap<i32, ClockPeriod>,
+    definitions: BTreeMap<String, String>, // <name, netlist>, the subcircuits that can be placed
     simulation: Simulator,
 }
 
@@ -32,26 +44,34 @@ impl Data {
             subnets: HashMap::new(),
             subnet_edges: HashMap::new(),
             component_edges: HashMap::new(),
-            clocks: Vec::new(),
+            clocks: BTreeMap::new(),
+            definitions: BTreeMap::new(),
             simulation: Simulator::new(),
         }
     }
     
     fn alloc_component(&mut self, component: Box<dyn Component>) -> i32 {
-        let idx = if self.components_free.len() == 0 {
+        let idx = if self.components_free.is_empty() {
             self.components.len() as i32 + 1
         } else {
             self.components_free.pop().unwrap().0
         };
     
-        let ports = component.ports();
-        assert!(self.components.insert(idx, (component, vec![SubnetState::Floating; ports])).is_none());
+        self.place_component(idx, component);
         idx
     }
     
-    pub(crate) fn add_component(&mut self, component: Box<dyn Component>, ports: Vec<Option<i32>>) -> Result<i32, ()> {
+    /// Stores a component under an id that isn't taken yet
+    fn place_component(&mut self, idx: i32, component: Box<dyn Component>) {
+        let ports = (0..component.ports())
+            .map(|port| Value::floating(component.port_width(port).unwrap()))
+            .collect();
+        assert!(self.components.insert(idx, (component, ports)).is_none());
+    }
+    
+    pub(crate) fn add_component(&mut self, component: Box<dyn Component>, ports: Vec<Option<i32>>) -> Result<i32, LogikError> {
         if ports.len() != component.ports() {
-            return Err(());
+            return Err(LogikError::WrongPortCount { expected: component.ports(), got: ports.len() });
         }
         
         let ports = ports.into_iter()
@@ -62,22 +82,139 @@ impl Data {
         let idx = self.alloc_component(component);
     
         for port in ports {
-            assert!(self.link(idx, port.1, port.2));
+            if let Err(e) = self.link(idx, port.1, port.2) {
+                self.remove_component(idx)?;
+                return Err(e);
+            }
         }
         
         Ok(idx)
     }
     
+    /// Lets the scheduler drive a clock, it starts out with the default period
     pub(crate) fn clock(&mut self, clock_id: i32) {
-        self.clocks.push(clock_id);
+        self.clocks.insert(clock_id, ClockPeriod::default());
+    }
+    
+    /// Sets for how long a clock is high and low, and by how much its first rising edge is delayed
+    /// beyond the low time. The clock immediately takes the level it has at this point of its schedule
+    pub(crate) fn set_clock_period(&mut self, clock: i32, period: ClockPeriod) -> Result<(), LogikError> {
+        self.component(clock)?;
+        if period.high == 0 || period.low == 0 {
+            return Err(LogikError::InvalidPeriod { high: period.high, low: period.low });
+        }
+        
+        *self.clocks.get_mut(&clock).ok_or(LogikError::NotAClock(clock))? = period;
+        
+        self.components.get(&clock).unwrap().0.drive(period.level_at(self.simulation.clock_time).into());
+        self.simulation.update_component(clock, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        
+        Ok(())
+    }
+    
+    pub(crate) fn clock_period(&self, clock: i32) -> Result<ClockPeriod, LogikError> {
+        self.component(clock)?;
+        self.clocks.get(&clock).copied().ok_or(LogikError::NotAClock(clock))
+    }
+    
+    /// The time of the next clock edge, if there are any clocks
+    pub(crate) fn next_clock_edge(&self) -> Option<u64> {
+        self.simulation.next_clock_edge(&self.clocks, &self.components)
+    }
+    
+    /// The parameters of a component, clocks also have their schedule as the parameters `high`,
+    /// `low` and `phase`
+    pub(crate) fn parameters(&self, component: i32) -> Result<Vec<Parameter>, LogikError> {
+        let mut parameters = self.component(component)?.parameters();
+        if let Some(period) = self.clocks.get(&component) {
+            let max = u32::MAX as i64;
+            parameters.push(Parameter::int("high", 1, max, period.high as i64));
+            parameters.push(Parameter::int("low", 1, max, period.low as i64));
+            parameters.push(Parameter::int("phase", 0, max, period.phase as i64));
+        }
+        Ok(parameters)
+    }
+    
+    pub(crate) fn parameter(&self, component: i32, name: &str) -> Result<Parameter, LogikError> {
+        self.parameters(component)?
+            .into_iter()
+            .find(|parameter| parameter.name == name)
+            .ok_or_else(|| LogikError::UnknownParameter(String::from(name)))
+    }
+    
+    /// Changes a parameter of a component and simulates the component again. The ports that are
+    /// linked have to keep existing with the same width, otherwise the parameter keeps its old value
+    pub(crate) fn set_parameter(&mut self, component: i32, name: &str, value: ParameterValue) -> Result<(), LogikError> {
+        self.parameter(component, name)?.check(&value)?;
+        
+        if let (Some(mut period), ParameterValue::Int(time)) = (self.clocks.get(&component).copied(), &value) {
+            let field = match name {
+                "high" => Some(&mut period.high),
+                "low" => Some(&mut period.low),
+                "phase" => Some(&mut period.phase),
+                _ => None,
+            };
+            if let Some(field) = field {
+                *field = *time as u32;
+                return self.set_clock_period(component, period);
+            }
+        }
+        
+        // the parameter is tried on a copy first, so that the component stays untouched when its
+        // links don't fit. Changing one parameter can reset others, so all of them are copied in order
+        let current = self.component(component)?;
+        let mut copy = current.id().build()?;
+        for parameter in current.parameters() {
+            copy.set_parameter(parameter.name, parameter.value)?;
+        }
+        copy.set_parameter(name, value.clone())?;
+        let edges = self.component_edges.get(&component).cloned().unwrap_or_default();
+        self.check_links(copy.as_ref(), &edges)?;
+        self.components.get_mut(&component).unwrap().0.set_parameter(name, value)?;
+        
+        // ports can turn from inputs into outputs when their number changes
+        for edge in &edges {
+            let direction = self.port_direction_component(component, edge.port)?;
+            if direction != edge.direction {
+                self.remove_edge(edge);
+                self.add_edge(edge.subnet, component, edge.port, direction)?;
+            }
+            self.simulation.dirty_subnet(edge.subnet);
+        }
+        
+        let (comp, ports) = self.components.get_mut(&component).unwrap();
+        *ports = (0..comp.ports())
+            .map(|port| Value::floating(comp.port_width(port).unwrap()))
+            .collect();
+        self.simulation.update_component(component, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        
+        Ok(())
+    }
+    
+    /// Checks that every linked port of a component still exists and fits its subnet
+    fn check_links(&self, component: &dyn Component, edges: &HashSet<Edge>) -> Result<(), LogikError> {
+        for edge in edges {
+            let port_width = component.port_width(edge.port).ok_or_else(|| {
+                LogikError::InvalidParameter(format!("port {} is linked to subnet {}", edge.port, edge.subnet))
+            })?;
+            let subnet_width = self.subnets[&edge.subnet].width();
+            if port_width != subnet_width {
+                return Err(LogikError::WidthMismatch { port_width, subnet_width });
+            }
+        }
+        Ok(())
     }
     
-    pub(crate) fn remove_component(&mut self, id: i32) -> bool {
+    pub(crate) fn remove_component(&mut self, id: i32) -> Result<(), LogikError> {
         if self.components.remove(&id).is_none() {
-            return false;
+            return Err(LogikError::UnknownComponent(id));
         };
         
         self.components_free.push(Reverse(id));
+        self.simulation.delays.remove(&id);
+        self.clocks.remove(&id);
     
         let mut to_remove = Vec::new();
     
@@ -92,19 +229,36 @@ impl Data {
             self.simulation.dirty_subnet(r.subnet);
         }
         
-        self.simulation.process_until_clean(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
         
-        true
+        Ok(())
+    }
+    
+    pub(crate) fn add_subnet(&mut self, id: i32) -> Result<(), LogikError> {
+        self.add_subnet_with_width(id, 1)
     }
     
-    pub(crate) fn add_subnet(&mut self, id: i32) -> bool {
-        self.subnets.insert(id, Subnet::new()).is_none()
+    /// Adds a subnet carrying `width` bits, fails if the width is out of range or the id is taken
+    pub(crate) fn add_subnet_with_width(&mut self, id: i32, width: u8) -> Result<(), LogikError> {
+        if width == 0 || width > MAX_WIDTH {
+            return Err(LogikError::InvalidWidth(width));
+        }
+        if self.subnets.contains_key(&id) {
+            return Err(LogikError::SubnetAlreadyExists(id));
+        }
+        
+        self.subnets.insert(id, Subnet::new(width));
+        if let Some(trace) = &mut self.simulation.trace {
+            trace.record(self.simulation.time, self.simulation.ticks, id, Value::floating(width));
+        }
+        Ok(())
     }
     
-    pub(crate) fn remove_subnet(&mut self, subnet: i32) -> bool {
+    pub(crate) fn remove_subnet(&mut self, subnet: i32) -> Result<(), LogikError> {
         if self.subnets.remove(&subnet).is_none() {
-            return false;
+            return Err(LogikError::UnknownSubnet(subnet));
         }
+        self.simulation.oscillating.remove(&subnet);
     
         if let Some(edges) = self.subnet_edges.get(&subnet) {
             let mut to_remove = Vec::new();
@@ -118,14 +272,19 @@ impl Data {
             }
         }
         
-        true
+        Ok(())
     }
     
-    pub(crate) fn link(&mut self, component: i32, port: usize, subnet: i32) -> bool {
-        let direction = match self.port_direction_component(component, port) {
-            Some(t) => t,
-            None => return false,
-        };
+    pub(crate) fn link(&mut self, component: i32, port: usize, subnet: i32) -> Result<(), LogikError> {
+        let direction = self.port_direction_component(component, port)?;
+        let port_width = self.port_width_component(component, port)?;
+        let subnet_width = self.subnets.get(&subnet)
+            .ok_or(LogikError::UnknownSubnet(subnet))?
+            .width();
+        
+        if port_width != subnet_width {
+            return Err(LogikError::WidthMismatch { port_width, subnet_width });
+        }
     
         let mut linked_to = None;
         for edge in self.component_edges.get(&component).unwrap_or(&HashSet::new()) {
@@ -135,54 +294,48 @@ impl Data {
         }
     
         if let Some(old_subnet) = linked_to {
-            self.unlink(component, port, old_subnet);
+            self.unlink(component, port, old_subnet)?;
         }
         
-        if !self.add_edge(subnet, component, port, direction) {
-            return false;
-        }
+        self.add_edge(subnet, component, port, direction)?;
         self.simulation.update_component(component, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
-        self.simulation.process_until_clean(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
         
-        true
+        Ok(())
     }
     
-    pub(crate) fn unlink(&mut self, component: i32, port: usize, subnet: i32) -> bool {
-        let direction = match self.port_direction_component(component, port) {
-            Some(t) => t,
-            None => return false,
-        };
+    pub(crate) fn unlink(&mut self, component: i32, port: usize, subnet: i32) -> Result<(), LogikError> {
+        let direction = self.port_direction_component(component, port)?;
         
         if !self.remove_edge(&Edge::new(subnet, component, port, direction)) {
-            return false;
+            return Err(LogikError::NotLinked { component, port, subnet });
         }
         self.simulation.dirty_subnet(subnet);
         self.simulation.update_component(component, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
-        self.simulation.process_until_clean(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
         
-        true
+        Ok(())
     }
 
-    pub(crate) fn press_component(&mut self, id: i32) -> SubnetState {
-        let state = self.components.get(&id).unwrap().0.pressed();
+    pub(crate) fn press_component(&mut self, id: i32) -> Result<SubnetState, LogikError> {
+        let state = self.component(id)?.pressed();
 
         self.simulation.update_component(id, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
-        self.simulation.process_until_clean(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
 
-        return state
+        Ok(state)
     }
 
-    pub(crate) fn release_component(&mut self, id: i32) -> SubnetState {
-        let state = self.components.get(&id).unwrap().0.released();
+    pub(crate) fn release_component(&mut self, id: i32) -> Result<SubnetState, LogikError> {
+        let state = self.component(id)?.released();
 
         self.simulation.update_component(id, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
-        self.simulation.process_until_clean(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
-
-        return state
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
 
+        Ok(state)
     }
     
-    fn add_edge(&mut self, subnet: i32, component: i32, port: usize, direction: EdgeDirection) -> bool {
+    fn add_edge(&mut self, subnet: i32, component: i32, port: usize, direction: EdgeDirection) -> Result<(), LogikError> {
         let edge = Edge::new(subnet, component, port, direction);
     
         let mut removing = Vec::new();
@@ -193,7 +346,7 @@ impl Data {
                     same = Some(e.clone());
                 }
                 if e.component == edge.component && e.port == edge.port && e.subnet != edge.subnet {
-                    return false;
+                    return Err(LogikError::PortAlreadyLinked { component, port, subnet: e.subnet });
                 }
             }
             if let Some(same) = same { //This edge already exists
@@ -210,91 +363,377 @@ impl Data {
         self.component_edges.entry(component).or_default().insert(edge.clone());
         self.subnet_edges.entry(subnet).or_default().insert(edge);
         
-        true
+        Ok(())
     }
     
     fn remove_edge(&mut self, edge: &Edge) -> bool {
         match self.component_edges.get_mut(&(edge.component)) {
-            Some(t) => t.remove(edge),
+            Some(t) => if !t.remove(edge) {
+                return false;
+            },
             None => return false,
         };
         self.subnet_edges.get_mut(&(edge.subnet)).unwrap().remove(edge);
         // If an edge exists in one direction, there should also exist one in the other direction
-        if self.component_edges.get(&(edge.component)).unwrap().len() == 0 {
+        if self.component_edges.get(&(edge.component)).unwrap().is_empty() {
             self.component_edges.remove(&(edge.component));
         }
-        if self.subnet_edges.get(&(edge.subnet)).unwrap().len() == 0 {
+        if self.subnet_edges.get(&(edge.subnet)).unwrap().is_empty() {
             self.subnet_edges.remove(&(edge.subnet));
         }
         true
     }
     
-    fn port_direction_component(&self, component: i32, port: usize) -> Option<EdgeDirection> {
-        Some(self.components.get(&component)?.0.port_type(port)?.to_edge_direction())
+    fn component(&self, component: i32) -> Result<&dyn Component, LogikError> {
+        Ok(self.components.get(&component).ok_or(LogikError::UnknownComponent(component))?.0.as_ref())
+    }
+    
+    fn port_direction_component(&self, component: i32, port: usize) -> Result<EdgeDirection, LogikError> {
+        Ok(self.component(component)?
+            .port_type(port)
+            .ok_or(LogikError::PortOutOfRange { component, port })?
+            .to_edge_direction())
+    }
+    
+    fn port_width_component(&self, component: i32, port: usize) -> Result<u8, LogikError> {
+        self.component(component)?
+            .port_width(port)
+            .ok_or(LogikError::PortOutOfRange { component, port })
+    }
+    
+    /// Gets the state of a subnet, for subnets wider than one bit this is the least significant bit
+    pub(crate) fn subnet_state(&self, subnet: i32) -> Result<SubnetState, LogikError> {
+        Ok(self.subnet_value(subnet)?.get(0))
     }
     
-    /// Gets the state of a subnet
-    pub(crate) fn subnet_state(&self, subnet: i32) -> Option<SubnetState> {
-        Some(self.subnets.get(&subnet)?.val())
+    /// Gets every bit of a subnet
+    pub(crate) fn subnet_value(&self, subnet: i32) -> Result<Value, LogikError> {
+        Ok(self.subnets.get(&subnet).ok_or(LogikError::UnknownSubnet(subnet))?.val())
     }
     
     /// Gets the state of a subnet which a port is connected to
-    pub(crate) fn port_state(&self, component: i32, port: usize) -> Option<SubnetState> {
-        self.components.get(&component)?.1.get(port).map(|e| *e)
+    pub(crate) fn port_state(&self, component: i32, port: usize) -> Result<SubnetState, LogikError> {
+        self.components.get(&component)
+            .ok_or(LogikError::UnknownComponent(component))?
+            .1
+            .get(port)
+            .map(|e| e.get(0))
+            .ok_or(LogikError::PortOutOfRange { component, port })
     }
     
     pub(crate) fn time_step(&mut self) {
         self.simulation.time_step(&self.clocks, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
     }
+    
+    /// Sets how many units of time it takes for a change on the inputs of a component to show up
+    /// on the subnets it drives
+    pub(crate) fn set_delay(&mut self, component: i32, delay: u32) -> Result<(), LogikError> {
+        self.component(component)?;
+        if delay == 0 {
+            return Err(LogikError::InvalidDelay(delay));
+        }
+        
+        self.simulation.delays.insert(component, delay);
+        Ok(())
+    }
+    
+    pub(crate) fn delay(&self, component: i32) -> Result<u32, LogikError> {
+        self.component(component)?;
+        
+        Ok(self.simulation.delay(component))
+    }
+    
+    /// When stepping, changes are only scheduled and time has to be advanced manually with
+    /// `advance_time`, which makes it possible to observe the circuit before it has settled
+    pub(crate) fn set_stepping(&mut self, stepping: bool) {
+        self.simulation.stepping = stepping;
+        
+        if !stepping {
+            self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        }
+    }
+    
+    pub(crate) fn advance_time(&mut self, units: u32) {
+        self.simulation.advance_time_by(units as u64, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+    }
+    
+    pub(crate) fn time(&self) -> u64 {
+        self.simulation.time
+    }
+    
+    /// Starts recording every change of every subnet, throwing away what was recorded before. The
+    /// subcircuits record the subnets inside of them along with this
+    pub(crate) fn start_recording(&mut self) {
+        let initial = self.subnets.iter()
+            .map(|(id, subnet)| (*id, subnet.val()))
+            .collect();
+        self.simulation.trace = Some(Trace::new(self.simulation.time, self.simulation.ticks, initial));
+        for (component, _) in self.components.values() {
+            if let Some(subcircuit) = component.subcircuit() {
+                subcircuit.circuit_mut().start_recording();
+            }
+        }
+    }
+    
+    /// Stops recording, what was recorded is kept until recording starts again
+    pub(crate) fn stop_recording(&mut self) {
+        if let Some(trace) = &mut self.simulation.trace {
+            trace.stop();
+        }
+        for (component, _) in self.components.values() {
+            if let Some(subcircuit) = component.subcircuit() {
+                subcircuit.circuit_mut().stop_recording();
+            }
+        }
+    }
+    
+    pub(crate) fn trace(&self) -> Result<&Trace, LogikError> {
+        self.simulation.trace.as_ref().ok_or(LogikError::NoTrace)
+    }
+    
+    /// Writes what has been recorded as a Value Change Dump, with what the subcircuits recorded in
+    /// scopes of their own
+    pub(crate) fn to_vcd(&self) -> Result<String, LogikError> {
+        Ok(self.trace_with_subcircuits()?.to_vcd())
+    }
+    
+    fn trace_with_subcircuits(&self) -> Result<Trace, LogikError> {
+        let mut trace = self.trace()?.clone();
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        for id in ids {
+            if let Some(subcircuit) = self.components.get(&id).unwrap().0.subcircuit() {
+                if let Ok(inner) = subcircuit.circuit().trace_with_subcircuits() {
+                    trace.add_subcircuit(id, inner);
+                }
+            }
+        }
+        Ok(trace)
+    }
+    
+    /// Stores a copy of a circuit so that it can be placed as a subcircuit under the given name
+    pub(crate) fn define_subcircuit(&mut self, name: &str, circuit: &Data) -> Result<(), LogikError> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(LogikError::InvalidName(String::from(name)));
+        }
+        if self.definitions.contains_key(name) {
+            return Err(LogikError::SubcircuitExists(String::from(name)));
+        }
+        
+        self.definitions.insert(String::from(name), circuit.save());
+        Ok(())
+    }
+    
+    /// Places a new instance of a subcircuit, its ports are left unlinked
+    pub(crate) fn add_subcircuit(&mut self, name: &str) -> Result<i32, LogikError> {
+        let netlist = self.definitions.get(name)
+            .ok_or_else(|| LogikError::UnknownSubcircuit(String::from(name)))?;
+        let component = Subcircuit::new(name, Data::load(netlist)?);
+        let ports = component.ports();
+        
+        self.add_component(Box::new(component), vec![None; ports])
+    }
+    
+    /// Gets every bit of a subnet inside a subcircuit, `path` are the ids of the subcircuit
+    /// components leading to it, starting in this circuit
+    pub(crate) fn subnet_value_at(&self, path: &[i32], subnet: i32) -> Result<Value, LogikError> {
+        match path.split_first() {
+            Some((component, rest)) => self.component(*component)?
+                .subcircuit()
+                .ok_or(LogikError::NotASubcircuit(*component))?
+                .circuit()
+                .subnet_value_at(rest, subnet),
+            None => self.subnet_value(subnet),
+        }
+    }
+    
+    /// The value a register, counter, shift register or flip-flop currently holds
+    pub(crate) fn stored_value(&self, component: i32) -> Result<Value, LogikError> {
+        self.component(component)?.stored_value().ok_or(LogikError::NoStoredValue(component))
+    }
+    
+    pub(crate) fn read_memory(&self, component: i32, address: u64) -> Result<u64, LogikError> {
+        self.memory(component)?.read(address)
+    }
+    
+    /// Changes a word of a RAM or ROM, whatever reads the word sees the new one right away
+    pub(crate) fn write_memory(&mut self, component: i32, address: u64, word: u64) -> Result<(), LogikError> {
+        self.memory(component)?.write(address, word)?;
+        self.simulation.update_component(component, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        Ok(())
+    }
+    
+    /// Replaces everything stored in a RAM or ROM with the words of an image
+    pub(crate) fn load_memory(&mut self, component: i32, image: &[u8], format: ImageFormat) -> Result<(), LogikError> {
+        self.memory(component)?.load(image, format)?;
+        self.simulation.update_component(component, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        Ok(())
+    }
+    
+    fn memory(&self, component: i32) -> Result<&Memory, LogikError> {
+        self.component(component)?.memory().ok_or(LogikError::NotAMemory(component))
+    }
+    
+    /// Changes the value an `Input` component drives and lets it propagate
+    fn drive_input(&mut self, component: i32, value: Value) {
+        self.components.get(&component).unwrap().0.drive(value);
+        self.simulation.update_component(component, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+    }
+    
+    /// Processes everything scheduled up to `time` and moves on to it, a subcircuit keeps up with
+    /// the circuit it is placed in this way
+    fn advance_to(&mut self, time: u64) {
+        if time > self.simulation.time {
+            let units = time - self.simulation.time;
+            self.simulation.advance_time_by(units, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        }
+    }
+    
+    /// Drives the clocks with the level they have at a clock edge of the circuit this one is
+    /// placed in, without processing what they drive
+    fn clock_edge(&mut self, clock_time: u64) {
+        self.simulation.clock_time = clock_time;
+        self.simulation.tick(&self.clocks, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+    }
+    
+    /// Gets the value of the subnet an input port reads, floating if the port isn't linked
+    fn input_value(&self, component: i32, port: usize) -> Value {
+        self.component_edges.get(&component)
+            .and_then(|edges| edges.iter().find(|edge| edge.port == port))
+            .map(|edge| self.subnets.get(&edge.subnet).unwrap().val())
+            .unwrap_or_else(|| Value::floating(self.port_width_component(component, port).unwrap()))
+    }
+    
+    /// Simulates every component again, used once components got their state restored
+    fn resimulate(&mut self) {
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        for id in ids {
+            self.simulation.update_component(id, &mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+        }
+        self.simulation.settle(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
+    }
+    
+    /// Gets the subnets that kept changing without the circuit settling, these are held in the
+    /// error state until something drives them to a new value
+    pub(crate) fn oscillating_subnets(&self) -> Vec<i32> {
+        let mut subnets = self.simulation.oscillating.iter().copied().collect::<Vec<_>>();
+        subnets.sort_unstable();
+        subnets
+    }
 }
 
 #[cfg(test)]
+#[allow(dead_code)]
 impl Data {
-    fn update_subnet(&mut self, subnet: i32, state: SubnetState) {
+    fn update_subnet(&mut self, subnet: i32, state: Value) {
         self.simulation.update_subnet(subnet, state, &mut self.subnets);
     }
     
-    fn advance_time(&mut self) {
+    fn advance_time_once(&mut self) {
         self.simulation.advance_time(&mut self.components, &mut self.subnets, &self.subnet_edges, &self.component_edges);
     }
     
-    fn update_silent(&mut self, subnet: i32, state: SubnetState) {
+    fn update_silent(&mut self, subnet: i32, state: Value) {
         self.subnets.get_mut(&subnet).unwrap().update(state);
     }
 }
 
-#[derive(Debug)]
+/// The first edge after `time` of any of the clocks, the clocks inside of subcircuits included
+fn next_clock_edge(time: u64, clocks: &BTreeMap<i32, ClockPeriod>, components: &HashMap<i32, (Box<dyn Component>, Vec<Value>)>) -> Option<u64> {
+    clocks.values()
+        .map(|clock| clock.next_edge(time))
+        .chain(components.values().filter_map(|(component, _)| component.subcircuit()?.next_clock_edge(time)))
+        .min()
+}
+
+/// The bit standing for the wire a bit belongs to, given the parent of every bit
+fn find_wire(wires: &[usize], mut bit: usize) -> usize {
+    while wires[bit] != bit {
+        bit = wires[bit];
+    }
+    bit
+}
+
+#[derive(Debug, Clone)]
 struct Simulator {
-    dirty_subnets: VecDeque<HashSet<i32>>,
-    changed_subnets: HashMap<i32, SubnetState>, //<subnet, old state>
+    dirty_subnets: BTreeMap<u64, HashSet<i32>>, // <time, subnets to resolve then>, only times with something to do
+    changed_subnets: HashMap<i32, Value>, //<subnet, old state>
+    delays: HashMap<i32, u32>, //<component, delay>, components not in here use DEFAULT_DELAY
+    time: u64,
+    stepping: bool,
+    oscillating: HashSet<i32>, // subnets that were marked as errors after refusing to settle
+    toggling: Option<HashMap<i32, u32>>, // <subnet, how often it changed> while looking for oscillations
+    ticks: u64,
+    clock_time: u64, // the time of the last clock edge
+    trace: Option<Trace>,
 }
 
 impl Simulator {
+    const DEFAULT_DELAY: u32 = 1;
+    
     fn new() -> Self {
         Self {
-            dirty_subnets: VecDeque::new(),
+            dirty_subnets: BTreeMap::new(),
             changed_subnets: HashMap::new(),
+            delays: HashMap::new(),
+            time: 0,
+            stepping: false,
+            oscillating: HashSet::new(),
+            toggling: None,
+            ticks: 0,
+            clock_time: 0,
+            trace: None,
         }
     }
     
+    /// The next time at which anything is scheduled, here or inside of a subcircuit
+    fn next_event(&self, components: &HashMap<i32, (Box<dyn Component>, Vec<Value>)>) -> Option<u64> {
+        components.values()
+            .filter_map(|(component, _)| component.subcircuit()?.next_event())
+            .chain(self.dirty_subnets.keys().next().copied())
+            .min()
+    }
+    
+    /// Processes a single unit of time. Every subnet scheduled for this time is resolved from what
+    /// its drivers are currently driving. The circuits of subcircuits that have something to do at
+    /// this time catch up next, like components reading a subnet that changed, and every component
+    /// that is simulated this way schedules its outputs to be resolved once its delay has passed
     fn advance_time(
         &mut self,
-        components: &mut HashMap<i32, (Box<dyn Component>, Vec<SubnetState>)>,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
         subnets: &mut HashMap<i32, Subnet>,
         subnet_edges: &HashMap<i32, HashSet<Edge>>,
         component_edges: &HashMap<i32, HashSet<Edge>>
     ) -> bool {
-        let to_simulate = match self.dirty_subnets.pop_front() {
-            Some(t) => t,
-            None => return false,
+        if self.next_event(components).is_none() {
+            return false;
+        }
+        self.time += 1;
+        let to_resolve = match self.dirty_subnets.keys().next() {
+            Some(next) if *next <= self.time => self.dirty_subnets.pop_first().unwrap().1,
+            _ => HashSet::new(),
         };
+    
+        let mut resolved = HashSet::new();
+        for subnet in to_resolve {
+            if subnets.contains_key(&subnet) && !resolved.contains(&subnet) {
+                self.resolve_net(subnet, &mut resolved, components, subnets, subnet_edges, component_edges);
+            }
+        }
         
-        let mut simulating = HashSet::new();
-        for subnet in &to_simulate {
-            for edge in subnet_edges.get(subnet).unwrap_or(&HashSet::new()) {
-                if edge.direction != EdgeDirection::ToSubnet {
-                    simulating.insert(edge.component);
-                }
+        let busy = components.iter()
+            .filter(|(_, (component, _))| component.subcircuit().and_then(Subcircuit::next_event).is_some_and(|next| next <= self.time))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in busy {
+            let driving = components.get(&id).unwrap().1.clone();
+            self.simulate(id, &HashMap::new(), components, subnets, subnet_edges, component_edges);
+            if components.get(&id).unwrap().1 != driving {
+                self.schedule_outputs(id, component_edges);
             }
         }
         
@@ -302,200 +741,468 @@ impl Simulator {
         
         std::mem::swap(&mut self.changed_subnets, &mut old_state);
         
-        let mut to_eval = HashSet::new();
-        
-        for s in simulating {
-            self.simulate(s, &old_state, components, subnets, component_edges);
-            for edge in component_edges.get(&s).unwrap() {
-                if edge.direction != EdgeDirection::ToComponent {
-                    to_eval.insert(edge.subnet);
+        let mut simulating = HashSet::new();
+        for subnet in old_state.keys() {
+            for edge in subnet_edges.get(subnet).unwrap_or(&HashSet::new()) {
+                if edge.direction != EdgeDirection::ToSubnet {
+                    simulating.insert(edge.component);
                 }
             }
         }
         
-        to_eval.extend(to_simulate);
-    
-        let mut diff: HashMap<i32, HashSet<SubnetState>> = HashMap::new();
-    
-        for evaluating_subnets in to_eval {
-            diff.entry(evaluating_subnets).or_default()
-                .extend(subnet_edges.get(&evaluating_subnets).unwrap_or(&HashSet::new())
-                    .into_iter()
-                    .filter(|edge| edge.direction != EdgeDirection::ToComponent)
-                    .map(|edge| *components
-                        .get(&edge.component)
-                        .unwrap()
-                        .1
-                        .get(edge.port)
-                        .unwrap()));
+        for s in simulating {
+            self.simulate(s, &old_state, components, subnets, subnet_edges, component_edges);
+            self.schedule_outputs(s, component_edges);
         }
         
-        self.apply_state_diff(diff, subnets);
-        
         true
     }
     
+    /// Skips the time in which nothing is scheduled and processes the next unit of time that has
+    /// something to do, returns false if nothing is scheduled at all
+    fn advance_to_next_event(
+        &mut self,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
+        subnets: &mut HashMap<i32, Subnet>,
+        subnet_edges: &HashMap<i32, HashSet<Edge>>,
+        component_edges: &HashMap<i32, HashSet<Edge>>
+    ) -> bool {
+        match self.next_event(components) {
+            Some(next) => {
+                self.time = self.time.max(next - 1);
+                self.advance_time(components, subnets, subnet_edges, component_edges)
+            }
+            None => false,
+        }
+    }
+    
     /// Takes in a component id and the difference between the old state and the current. Updates
     /// what each port is driving. It is the responsibility of the caller to use the updated edge
     /// state
     fn simulate(
         &mut self,
         component: i32,
-        old_state: &HashMap<i32, SubnetState>,
-        components: &mut HashMap<i32, (Box<dyn Component>, Vec<SubnetState>)>,
+        old_state: &HashMap<i32, Value>,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
         subnets: &HashMap<i32, Subnet>,
+        subnet_edges: &HashMap<i32, HashSet<Edge>>,
         component_edges: &HashMap<i32, HashSet<Edge>>
     ) {
-        let comp = components.get_mut(&component).unwrap();
         let edges = component_edges.get(&component);
-        let mut searching = HashSet::new();
-        let mut dirty_ports = HashSet::new();
-        for (port_idx, port_type) in comp.0.ports_type()
-            .into_iter()
-            .enumerate()
-        {
-            if port_type != PortType::Output {
-                searching.insert(port_idx);
-            } else if port_type != PortType::Input {
-                dirty_ports.insert(port_idx);
+        
+        // joined bits are resolved together, so only a bidirectional port that isn't joining its
+        // subnet to another one right now drives a value of its own. It reads what everything else
+        // drives onto its subnet, reading the value it drives itself back would keep it driving
+        // that value forever. Weak drivers are left out since the port drives a strong value
+        let mut driven_by_others = HashMap::new();
+        for edge in edges.unwrap_or(&HashSet::new()) {
+            if edge.direction == EdgeDirection::Bidirectional {
+                let drivers = subnet_edges.get(&edge.subnet).unwrap_or(&HashSet::new())
+                    .iter()
+                    .filter(|other| other.direction != EdgeDirection::ToComponent && !other.same_nodes(edge))
+                    .filter(|other| components.get(&other.component).unwrap().0.strength() == Strength::Strong)
+                    .map(|other| components.get(&other.component).unwrap().1.get(other.port).unwrap().clone())
+                    .collect();
+                let width = subnets.get(&edge.subnet).unwrap().width();
+                driven_by_others.insert(edge.port, Value::work_out_diff(&drivers, width));
             }
         }
         
+        let comp = components.get_mut(&component).unwrap();
+        if let Some(subcircuit) = comp.0.subcircuit() {
+            subcircuit.advance_to(self.time);
+        }
+        let port_types = comp.0.ports_type();
         let mut states = HashMap::new();
-        let mut dirtying = HashMap::new();
         
         for edge in edges.unwrap_or(&HashSet::new()) {
-            if searching.contains(&edge.port) {
-                let val = subnets.get(&edge.subnet).unwrap().val();
-                let old = match old_state.get(&edge.subnet) {
-                    Some(t) => *t,
-                    None => val,
-                };
-                let diff = StateChange::new(old, val);
-                states.insert(edge.port, diff);
-            } else if dirty_ports.contains(&edge.port) {
-                dirtying.insert(edge.port, edge.subnet);
-            }
+            let diff = match port_types.get(edge.port) {
+                Some(PortType::Input) => {
+                    let val = subnets.get(&edge.subnet).unwrap().val();
+                    let old = match old_state.get(&edge.subnet) {
+                        Some(t) => t.clone(),
+                        None => val.clone(),
+                    };
+                    StateChange::new(old, val)
+                }
+                // what the others drove before isn't known, so there are no edges on these ports
+                Some(PortType::Bidirectional) => {
+                    let val = driven_by_others.remove(&edge.port).unwrap();
+                    StateChange::new(val.clone(), val)
+                }
+                _ => continue,
+            };
+            states.insert(edge.port, diff);
         }
         
         let res = comp.0.evaluate(states);
         
         for (port, state) in res {
+            debug_assert_eq!(Some(state.width()), comp.0.port_width(port));
             *comp.1.get_mut(port).unwrap() = state;
         }
     }
     
-    /// Forces a component to update and advances time. Is probably called when the user places a
-    /// components and wants the changes to propagate. Also empties propagates changes until no
-    /// more updates are happening.
+    /// Forces a component to update and schedules its outputs. Is probably called when the user
+    /// places a components and wants the changes to propagate.
     fn update_component(
         &mut self,
         component: i32,
-        components: &mut HashMap<i32, (Box<dyn Component>, Vec<SubnetState>)>,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
         subnets: &mut HashMap<i32, Subnet>,
         subnet_edges: &HashMap<i32, HashSet<Edge>>,
         component_edges: &HashMap<i32, HashSet<Edge>>
     ) {
-        let mut old_state = HashMap::new();
-        
-        std::mem::swap(&mut old_state, &mut self.changed_subnets);
+        self.simulate(component, &HashMap::new(), components, subnets, subnet_edges, component_edges);
+        self.schedule_outputs(component, component_edges);
+    }
         
-        self.simulate(component, &old_state, components, subnets, component_edges);
-    
-        let mut to_eval = HashSet::new();
+    /// Schedules the subnets a component drives to be resolved once the delay of the component
+    /// has passed
+    fn schedule_outputs(&mut self, component: i32, component_edges: &HashMap<i32, HashSet<Edge>>) {
+        let delay = self.delay(component);
         
         for edge in component_edges.get(&component).unwrap_or(&HashSet::new()) {
             if edge.direction != EdgeDirection::ToComponent {
-                to_eval.insert(edge.subnet);
+                self.schedule_subnet(edge.subnet, delay);
+            }
+        }
+    }
+    
+    /// Resolves a subnet along with every subnet joined to it by bidirectional ports that are
+    /// connected right now. Bits that are joined are one wire, each wire takes its value from all of
+    /// the components driving any of its bits, so no port passes on a value it drove itself. Weak
+    /// drivers only decide the wires no strong driver drives
+    fn resolve_net(
+        &mut self,
+        subnet: i32,
+        resolved: &mut HashSet<i32>,
+        components: &HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
+        subnets: &mut HashMap<i32, Subnet>,
+        subnet_edges: &HashMap<i32, HashSet<Edge>>,
+        component_edges: &HashMap<i32, HashSet<Edge>>
+    ) {
+        let no_edges = HashSet::new();
+        
+        // the subnets reachable over joining components, with every pair of bits they join
+        let mut net = vec![subnet];
+        let mut visited = HashSet::new();
+        let mut joining = HashSet::new();
+        let mut joined = Vec::new();
+        let mut next = 0;
+        while next < net.len() {
+            for edge in subnet_edges.get(&net[next]).unwrap_or(&no_edges) {
+                let component = &components.get(&edge.component).unwrap().0;
+                if edge.direction != EdgeDirection::Bidirectional || component.joining() != SubnetState::On || !visited.insert(edge.component) {
+                    continue;
+                }
+                let linked = component_edges.get(&edge.component).unwrap_or(&no_edges)
+                    .iter()
+                    .map(|edge| (edge.port, edge.subnet))
+                    .collect::<HashMap<_, _>>();
+                for ((port_a, bit_a), (port_b, bit_b)) in component.joined_bits() {
+                    joining.insert((edge.component, port_a));
+                    joining.insert((edge.component, port_b));
+                    if let (Some(a), Some(b)) = (linked.get(&port_a), linked.get(&port_b)) {
+                        joined.push(((*a, bit_a), (*b, bit_b)));
+                        for subnet in [*a, *b] {
+                            if !net.contains(&subnet) {
+                                net.push(subnet);
+                            }
+                        }
+                    }
+                }
+            }
+            next += 1;
+        }
+        
+        let mut first_bit = HashMap::new();
+        let mut bits = 0;
+        for subnet in &net {
+            first_bit.insert(*subnet, bits);
+            bits += subnets.get(subnet).unwrap().width() as usize;
+        }
+        let mut wires = (0..bits).collect::<Vec<_>>(); // the parent of every bit, the root stands for the wire
+        for ((a, bit_a), (b, bit_b)) in joined {
+            if bit_a < subnets.get(&a).unwrap().width() as usize && bit_b < subnets.get(&b).unwrap().width() as usize {
+                let a = find_wire(&wires, first_bit[&a] + bit_a);
+                let b = find_wire(&wires, first_bit[&b] + bit_b);
+                wires[a] = b;
             }
         }
         
-        let mut diff: HashMap<i32, HashSet<SubnetState>> = HashMap::new();
-    
-        for evaluating_subnets in to_eval {
-            for edge in subnet_edges.get(&evaluating_subnets).unwrap() {
-                if edge.direction != EdgeDirection::ToComponent {
-                    diff
-                        .entry(evaluating_subnets)
-                        .or_default()
-                        .insert(
-                            *components
-                                .get(&edge.component)
-                                .unwrap()
-                                .1
-                                .get(edge.port)
-                                .unwrap()
-                        );
+        let mut drivers: HashMap<usize, (HashSet<SubnetState>, HashSet<SubnetState>)> = HashMap::new(); //<wire, (strong, weak)>
+        for subnet in &net {
+            for edge in subnet_edges.get(subnet).unwrap_or(&no_edges) {
+                if edge.direction == EdgeDirection::ToComponent || joining.contains(&(edge.component, edge.port)) {
+                    continue;
+                }
+                let (component, ports) = components.get(&edge.component).unwrap();
+                let value = ports.get(edge.port).unwrap();
+                for bit in 0..subnets.get(subnet).unwrap().width() as usize {
+                    let (strong, weak) = drivers.entry(find_wire(&wires, first_bit[subnet] + bit)).or_default();
+                    match component.strength() {
+                        Strength::Strong => strong.insert(value.get(bit)),
+                        Strength::Weak => weak.insert(value.get(bit)),
+                    };
                 }
             }
         }
         
-        self.apply_state_diff(diff, subnets);
-    }
-    
-    /// Takes a change in subnet_state and updates the relevant subnets
-    fn apply_state_diff(&mut self, diff: HashMap<i32, HashSet<SubnetState>>, subnets: &mut HashMap<i32, Subnet>) {
-        for (subnet, proposals) in diff {
-            self.update_subnet(subnet, SubnetState::work_out_diff(&proposals), subnets);
+        for subnet in net {
+            let (strong, weak): (Vec<_>, Vec<_>) = (0..subnets.get(&subnet).unwrap().width() as usize)
+                .map(|bit| match drivers.get(&find_wire(&wires, first_bit[&subnet] + bit)) {
+                    Some((strong, weak)) => (SubnetState::work_out_diff(strong), SubnetState::work_out_diff(weak)),
+                    None => (SubnetState::Floating, SubnetState::Floating),
+                })
+                .unzip();
+            let state = Value::from(strong).or_weak(&weak.into());
+            self.update_subnet(subnet, state, subnets);
+            resolved.insert(subnet);
         }
     }
     
-    /// Changes a subnets value and enques it in dirty_subnets if the state changed
-    fn update_subnet(&mut self, subnet: i32, state: SubnetState, subnets: &mut HashMap<i32, Subnet>) {
+    /// Changes a subnets value and remembers the old value if the state changed, so that the
+    /// components reading it get simulated
+    fn update_subnet(&mut self, subnet: i32, state: Value, subnets: &mut HashMap<i32, Subnet>) {
         let old_state = subnets.get(&subnet).unwrap().val();
         if subnets.get_mut(&subnet).unwrap().update(state) { //we actually changed a subnet
-            if self.dirty_subnets.len() == 0 { //maybe change to account for propagation time
-                self.dirty_subnets.push_back(HashSet::new());
+            self.changed_subnets.entry(subnet).or_insert(old_state);
+            self.oscillating.remove(&subnet);
+            if let Some(toggling) = &mut self.toggling {
+                *toggling.entry(subnet).or_default() += 1;
+            }
+            if let Some(trace) = &mut self.trace {
+                trace.record(self.time, self.ticks, subnet, subnets.get(&subnet).unwrap().val());
             }
-            self.dirty_subnets.get_mut(0).unwrap().insert(subnet);
-            self.changed_subnets.insert(subnet, old_state);
         }
     }
     
     /// Dirties a subnet
     fn dirty_subnet(&mut self, subnet: i32) {
-        if self.dirty_subnets.len() == 0 {
-            self.dirty_subnets.push_back(HashSet::new());
+        self.schedule_subnet(subnet, 1);
+    }
+    
+    /// Enqueues a subnet to be resolved `delay` units of time from now
+    fn schedule_subnet(&mut self, subnet: i32, delay: u32) {
+        self.dirty_subnets.entry(self.time + delay.max(1) as u64).or_default().insert(subnet);
+    }
+    
+    fn delay(&self, component: i32) -> u32 {
+        self.delays.get(&component).copied().unwrap_or(Self::DEFAULT_DELAY)
+    }
+    
+    /// Processes `units` units of time, regardless of whether anything is scheduled
+    fn advance_time_by(
+        &mut self,
+        units: u64,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
+        subnets: &mut HashMap<i32, Subnet>,
+        subnet_edges: &HashMap<i32, HashSet<Edge>>,
+        component_edges: &HashMap<i32, HashSet<Edge>>
+    ) {
+        let end = self.time + units;
+        while self.next_event(components).is_some_and(|next| next <= end) {
+            self.advance_to_next_event(components, subnets, subnet_edges, component_edges);
         }
-        self.dirty_subnets.get_mut(0).unwrap().insert(subnet);
+        self.time = end;
+    }
+    
+    /// Propagates every scheduled change, unless the simulation is being stepped manually
+    fn settle(
+        &mut self,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
+        subnets: &mut HashMap<i32, Subnet>,
+        subnet_edges: &HashMap<i32, HashSet<Edge>>,
+        component_edges: &HashMap<i32, HashSet<Edge>>
+    ) -> bool {
+        if self.stepping {
+            return true;
+        }
+        
+        self.process_until_clean(components, subnets, subnet_edges, component_edges)
     }
     
+    /// Processes the scheduled changes until nothing is left to do. The limit counts the units of
+    /// time that had something to do, so a slow component doesn't count as busy while it waits.
+    /// A circuit that is still busy after that is watched for subnets that keep changing, which are
+    /// held in the error state while everything else that is scheduled carries on. If nothing
+    /// changes more than once it is just slow and is watched for longer the next time. Returns
+    /// whether the circuit settled without marking any subnets, what is still scheduled after the
+    /// last attempt is kept for the next time the circuit settles
     fn process_until_clean(
         &mut self,
-        components: &mut HashMap<i32, (Box<dyn Component>, Vec<SubnetState>)>,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
         subnets: &mut HashMap<i32, Subnet>,
         subnet_edges: &HashMap<i32, HashSet<Edge>>,
         component_edges: &HashMap<i32, HashSet<Edge>>
     ) -> bool {
         const MAX_ITERS: i32 = 1000;
-        for _ in 0..MAX_ITERS {
-            if !self.advance_time(components, subnets, subnet_edges, component_edges) {
-                return true;
+        const MAX_ATTEMPTS: i32 = 8;
+        let mut observe_iters = 100;
+        let mut marked = false;
+        for _ in 0..MAX_ATTEMPTS {
+            for _ in 0..MAX_ITERS {
+                if !self.advance_to_next_event(components, subnets, subnet_edges, component_edges) {
+                    return !marked;
+                }
+            }
+            
+            self.watch_toggling(components);
+            for _ in 0..observe_iters {
+                if !self.advance_to_next_event(components, subnets, subnet_edges, component_edges) {
+                    break;
+                }
+            }
+            if self.mark_toggling(components, subnets) {
+                marked = true;
+            } else {
+                observe_iters *= 2;
             }
         }
         
         false
     }
     
-    /// Toggles all clocks
+    /// Starts counting how often every subnet changes, in here and inside of every subcircuit
+    fn watch_toggling(&mut self, components: &HashMap<i32, (Box<dyn Component>, Vec<Value>)>) {
+        self.toggling = Some(HashMap::new());
+        for (component, _) in components.values() {
+            if let Some(subcircuit) = component.subcircuit() {
+                subcircuit.watch_toggling();
+            }
+        }
+    }
+    
+    /// Stops counting changes and marks the subnets that changed more than once as errors, in here
+    /// and inside of every subcircuit. Returns whether anything was marked
+    fn mark_toggling(&mut self, components: &HashMap<i32, (Box<dyn Component>, Vec<Value>)>, subnets: &mut HashMap<i32, Subnet>) -> bool {
+        let mut marked = false;
+        for (component, _) in components.values() {
+            if let Some(subcircuit) = component.subcircuit() {
+                marked |= subcircuit.mark_toggling();
+            }
+        }
+        
+        let toggling = self.toggling.take()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, changes)| *changes > 1)
+            .map(|(subnet, _)| subnet)
+            .collect::<HashSet<_>>();
+        if toggling.is_empty() {
+            return marked;
+        }
+        
+        // the marked subnets aren't resolved from their drivers anymore until one of them
+        // changes what it drives
+        for slot in self.dirty_subnets.values_mut() {
+            slot.retain(|subnet| !toggling.contains(subnet));
+        }
+        self.dirty_subnets.retain(|_, slot| !slot.is_empty());
+        for subnet in &toggling {
+            let width = subnets.get(subnet).unwrap().width();
+            self.update_subnet(*subnet, Value::error(width), subnets);
+        }
+        self.oscillating.extend(toggling);
+        
+        // an empty slot makes the next advance simulate whatever reads the marked subnets
+        self.dirty_subnets.entry(self.time + 1).or_default();
+        true
+    }
+    
+    fn next_clock_edge(&self, clocks: &BTreeMap<i32, ClockPeriod>, components: &HashMap<i32, (Box<dyn Component>, Vec<Value>)>) -> Option<u64> {
+        next_clock_edge(self.clock_time, clocks, components)
+    }
+    
+    /// Advances to the next edge of any of the clocks and drives every clock with the level it has
+    /// at that point. If the circuit is still busy past that time the edge happens late, and when
+    /// stepping it happens right away
     fn time_step(
         &mut self,
-        clocks: &[i32],
-        components: &mut HashMap<i32, (Box<dyn Component>, Vec<SubnetState>)>,
+        clocks: &BTreeMap<i32, ClockPeriod>,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
         subnets: &mut HashMap<i32, Subnet>,
         subnet_edges: &HashMap<i32, HashSet<Edge>>,
         component_edges: &HashMap<i32, HashSet<Edge>>
     ) {
-        let mut updating = Vec::new();
-        for clock in clocks {
-            updating.push(*clock);
+        if let Some(edge) = self.next_clock_edge(clocks, components) {
+            if !self.stepping && edge > self.time {
+                self.advance_time_by(edge - self.time, components, subnets, subnet_edges, component_edges);
+            }
+            self.clock_time = edge;
         }
         
-        for u in updating {
-            self.update_component(u, components, subnets, subnet_edges, component_edges);
+        self.tick(clocks, components, subnets, subnet_edges, component_edges);
+        self.settle(components, subnets, subnet_edges, component_edges);
+    }
+    
+    /// Drives every clock with the level it has at the current clock time, the clocks inside of
+    /// subcircuits included, and schedules what they drive
+    fn tick(
+        &mut self,
+        clocks: &BTreeMap<i32, ClockPeriod>,
+        components: &mut HashMap<i32, (Box<dyn Component>, Vec<Value>)>,
+        subnets: &mut HashMap<i32, Subnet>,
+        subnet_edges: &HashMap<i32, HashSet<Edge>>,
+        component_edges: &HashMap<i32, HashSet<Edge>>
+    ) {
+        self.ticks += 1;
+        if let Some(trace) = &mut self.trace {
+            trace.record_tick(self.time, self.ticks);
         }
         
-        self.process_until_clean(components, subnets, subnet_edges, component_edges);
+        for (clock, period) in clocks {
+            components.get(clock).unwrap().0.drive(period.level_at(self.clock_time).into());
+            self.update_component(*clock, components, subnets, subnet_edges, component_edges);
+        }
+        for (component, _) in components.values() {
+            if let Some(subcircuit) = component.subcircuit() {
+                subcircuit.advance_to(self.time);
+                subcircuit.clock_edge(self.clock_time);
+            }
+        }
+    }
+}
+
+/// The schedule of a clock. Every period starts with the clock being low for `low` units of time,
+/// followed by `high` units of time of it being high. The first period starts at `phase`
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub(crate) struct ClockPeriod {
+    pub(crate) high: u32,
+    pub(crate) low: u32,
+    pub(crate) phase: u32,
+}
+
+impl Default for ClockPeriod {
+    fn default() -> Self {
+        Self { high: 1, low: 1, phase: 0 }
+    }
+}
+
+impl ClockPeriod {
+    /// How far into a period the clock is at `time`
+    fn position(&self, time: u64) -> u64 {
+        let period = self.high as i128 + self.low as i128;
+        (time as i128 - self.phase as i128).rem_euclid(period) as u64
+    }
+    
+    pub(crate) fn level_at(&self, time: u64) -> SubnetState {
+        if self.position(time) < self.low as u64 {
+            SubnetState::Off
+        } else {
+            SubnetState::On
+        }
+    }
+    
+    /// The first time after `time` at which the clock changes its level
+    pub(crate) fn next_edge(&self, time: u64) -> u64 {
+        let position = self.position(time);
+        if position < self.low as u64 {
+            time + (self.low as u64 - position)
+        } else {
+            time + (self.high as u64 + self.low as u64 - position)
+        }
     }
 }
 
@@ -518,15 +1225,6 @@ impl Edge {
             self.component == other.component &&
             self.port == other.port
     }
-    
-    fn add_direction(mut self, direction: EdgeDirection) -> Self {
-        if self.direction == direction {
-            self
-        } else {
-            self.direction = EdgeDirection::Bidirectional;
-            self
-        }
-    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
@@ -0,0 +1,308 @@
+use crate::data::{Data, ClockPeriod};
+use crate::data::component::{Component, ComponentId};
+use crate::data::component::subcircuit::Subcircuit;
+use crate::data::component::parameter::{find_parameter, set_parameter, ParameterValue};
+use crate::data::error::LogikError;
+use std::cmp::Reverse;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The version written at the top of every netlist, it has to be bumped whenever the format changes
+/// in a way older versions can't read
+pub(crate) const NETLIST_VERSION: u32 = 1;
+
+impl Data {
+    /// Writes the circuit as a text netlist, the format is described in NETLIST.md. Everything is
+    /// written sorted by id so that saving the same circuit twice gives the same text
+    pub(crate) fn save(&self) -> String {
+        let mut out = format!("logik {}\n", NETLIST_VERSION);
+        
+        for (name, netlist) in &self.definitions {
+            writeln!(out, "define {}", name).unwrap();
+            for line in netlist.lines().skip(1) {
+                writeln!(out, "    {}", line).unwrap();
+            }
+            out.push_str("end\n");
+        }
+        
+        let mut subnets = self.subnets.iter().collect::<Vec<_>>();
+        subnets.sort_by_key(|(id, _)| **id);
+        for (id, subnet) in subnets {
+            writeln!(out, "subnet {} {}", id, subnet.width()).unwrap();
+        }
+        
+        let mut components = self.components.keys().copied().collect::<Vec<_>>();
+        components.sort_unstable();
+        for id in &components {
+            let component = &self.components[id].0;
+            write!(out, "component {} {}", id, component.id().name()).unwrap();
+            if let Some(subcircuit) = component.subcircuit() {
+                write!(out, " name={}", subcircuit.name()).unwrap();
+            }
+            if let Some(delay) = self.simulation.delays.get(id) {
+                write!(out, " delay={}", delay).unwrap();
+            }
+            if let Some(period) = self.clocks.get(id).filter(|period| **period != ClockPeriod::default()) {
+                write!(out, " high={} low={} phase={}", period.high, period.low, period.phase).unwrap();
+            }
+            // only the parameters that differ from a component built without any are written
+            let defaults = component.id().build().map(|default| default.parameters()).unwrap_or_default();
+            for parameter in component.parameters().into_iter().filter(|parameter| !defaults.contains(parameter)) {
+                write!(out, " {}={}", parameter.name, parameter.value.to_text()).unwrap();
+            }
+            if let Some(state) = component.save_state() {
+                write!(out, " state={}", state).unwrap();
+            }
+            out.push('\n');
+        }
+        
+        for id in &components {
+            let mut edges = self.component_edges.get(id).into_iter().flatten().collect::<Vec<_>>();
+            edges.sort_by_key(|edge| edge.port);
+            for edge in edges {
+                writeln!(out, "link {} {} {}", id, edge.port, edge.subnet).unwrap();
+            }
+        }
+        
+        out
+    }
+    
+    /// Builds a circuit from a netlist, the values of the subnets are worked out by simulating
+    /// every component once everything is in place
+    pub(crate) fn load(netlist: &str) -> Result<Data, LogikError> {
+        let mut lines = netlist.lines()
+            .enumerate()
+            .map(|(i, text)| (i + 1, text.trim()))
+            .filter(|(_, text)| !text.is_empty() && !text.starts_with('#'))
+            .collect::<Vec<_>>()
+            .into_iter();
+        
+        let version = match lines.next() {
+            Some((line, text)) => {
+                let mut words = text.split_whitespace();
+                if words.next() != Some("logik") {
+                    return Err(invalid(line, String::from("expected the netlist header")));
+                }
+                let version: u32 = parse(line, words.next(), "version")?;
+                if version > NETLIST_VERSION {
+                    return Err(invalid(line, format!("version {} is newer than supported", version)));
+                }
+                end_of_line(line, words)?;
+                version
+            }
+            None => return Err(invalid(1, String::from("the netlist is empty"))),
+        };
+        
+        let mut data = Data::new();
+        // linking simulates components, that is done once at the end instead
+        data.simulation.stepping = true;
+        let mut states = Vec::new();
+        
+        while let Some((line, text)) = lines.next() {
+            let mut words = text.split_whitespace();
+            match words.next() {
+                Some("define") => {
+                    let name = words.next()
+                        .ok_or_else(|| invalid(line, String::from("expected a subcircuit name")))?;
+                    end_of_line(line, words)?;
+                    
+                    // the body is a netlist of its own, which can contain definitions as well
+                    let mut body = vec![(line, format!("logik {}", version))];
+                    let mut depth = 1;
+                    for (line, text) in lines.by_ref() {
+                        match text.split_whitespace().next() {
+                            Some("define") => depth += 1,
+                            Some("end") => depth -= 1,
+                            _ => {},
+                        }
+                        if depth == 0 {
+                            break;
+                        }
+                        body.push((line, String::from(text)));
+                    }
+                    if depth != 0 {
+                        return Err(invalid(line, format!("subcircuit {} has no end", name)));
+                    }
+                    
+                    let netlist = body.iter().map(|(_, text)| text.as_str()).collect::<Vec<_>>().join("\n");
+                    // errors in the body are reported with the line numbers of the whole netlist
+                    let circuit = Data::load(&netlist).map_err(|e| match e {
+                        LogikError::InvalidNetlist { line, reason } => invalid(body[line - 1].0, reason),
+                        e => e,
+                    })?;
+                    data.define_subcircuit(name, &circuit).map_err(|e| invalid(line, e.to_string()))?;
+                }
+                Some("subnet") => {
+                    let id = parse(line, words.next(), "subnet id")?;
+                    let width = parse(line, words.next(), "subnet width")?;
+                    end_of_line(line, words)?;
+                    
+                    data.add_subnet_with_width(id, width).map_err(|e| invalid(line, e.to_string()))?;
+                }
+                Some("component") => {
+                    let id: i32 = parse(line, words.next(), "component id")?;
+                    let kind = words.next()
+                        .and_then(ComponentId::from_name)
+                        .ok_or_else(|| invalid(line, String::from("expected a component type")))?;
+                    if id < 1 || data.components.contains_key(&id) {
+                        return Err(invalid(line, format!("{} is not a free component id", id)));
+                    }
+                    
+                    let mut name = None;
+                    let mut delay = None;
+                    let mut period = ClockPeriod::default();
+                    let mut params = Vec::new();
+                    for word in words {
+                        match word.split_once('=') {
+                            Some(("name", value)) => name = Some(value),
+                            Some(("delay", value)) => delay = Some(parse(line, Some(value), "delay")?),
+                            Some(("high", value)) if kind == ComponentId::Clock => period.high = parse(line, Some(value), "time")?,
+                            Some(("low", value)) if kind == ComponentId::Clock => period.low = parse(line, Some(value), "time")?,
+                            Some(("phase", value)) if kind == ComponentId::Clock => period.phase = parse(line, Some(value), "time")?,
+                            Some(("state", value)) => states.push((line, id, value)),
+                            Some((name, value)) => params.push((word, name, value)),
+                            None => return Err(invalid(line, format!("unexpected '{}'", word))),
+                        }
+                    }
+                    
+                    let component: Box<dyn Component> = match (kind, name) {
+                        (ComponentId::Custom, Some(name)) => {
+                            let netlist = data.definitions.get(name)
+                                .ok_or_else(|| invalid(line, format!("there is no subcircuit called {}", name)))?;
+                            Box::new(Subcircuit::new(name, Data::load(netlist)?))
+                        }
+                        (ComponentId::Custom, None) => return Err(invalid(line, String::from("expected a subcircuit name"))),
+                        (kind, _) => {
+                            let mut component = kind.build().map_err(|e| invalid(line, e.to_string()))?;
+                            for (word, name, text) in params.drain(..) {
+                                let parameter = find_parameter(component.as_ref(), name)
+                                    .map_err(|_| invalid(line, format!("unexpected '{}'", word)))?;
+                                let value = ParameterValue::from_text(parameter.kind, text)
+                                    .ok_or_else(|| invalid(line, format!("'{}' is not a valid value for {}", text, name)))?;
+                                set_parameter(component.as_mut(), name, value).map_err(|e| invalid(line, e.to_string()))?;
+                            }
+                            component
+                        }
+                    };
+                    if let Some((word, _, _)) = params.first() {
+                        return Err(invalid(line, format!("unexpected '{}'", word)));
+                    }
+                    
+                    data.place_component(id, component);
+                    if kind == ComponentId::Clock {
+                        if period.high == 0 || period.low == 0 {
+                            let e = LogikError::InvalidPeriod { high: period.high, low: period.low };
+                            return Err(invalid(line, e.to_string()));
+                        }
+                        data.clocks.insert(id, period);
+                    }
+                    if let Some(delay) = delay {
+                        data.set_delay(id, delay).map_err(|e| invalid(line, e.to_string()))?;
+                    }
+                }
+                Some("link") => {
+                    let component = parse(line, words.next(), "component id")?;
+                    let port = parse(line, words.next(), "port")?;
+                    let subnet = parse(line, words.next(), "subnet id")?;
+                    end_of_line(line, words)?;
+                    
+                    data.link(component, port, subnet).map_err(|e| invalid(line, e.to_string()))?;
+                }
+                Some(word) => return Err(invalid(line, format!("unexpected '{}'", word))),
+                None => unreachable!(),
+            }
+        }
+        
+        // states are restored last because linking a component simulates it, which can change it
+        for (line, id, state) in states {
+            if !data.components[&id].0.load_state(state) {
+                return Err(invalid(line, format!("'{}' is not a valid state for component {}", state, id)));
+            }
+        }
+        
+        if let Some(max) = data.components.keys().max().copied() {
+            data.components_free = (1..max)
+                .filter(|id| !data.components.contains_key(id))
+                .map(Reverse)
+                .collect();
+        }
+        
+        data.resimulate();
+        data.set_stepping(false);
+        data.simulation.time = 0;
+        
+        Ok(data)
+    }
+    
+    /// Writes the state of every stateful component as `(id:state,...)`
+    pub(crate) fn save_states(&self) -> String {
+        let mut ids = self.components.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        
+        let states = ids.into_iter()
+            .filter_map(|id| self.components[&id].0.save_state().map(|state| format!("{}:{}", id, state)))
+            .collect::<Vec<_>>();
+        format!("({})", states.join(","))
+    }
+    
+    /// Restores the states written by `save_states`, returns whether they were understood
+    pub(crate) fn load_states(&mut self, states: &str) -> bool {
+        let inner = match states.strip_prefix('(').and_then(|states| states.strip_suffix(')')) {
+            Some(t) => t,
+            None => return false,
+        };
+        
+        for entry in split_top_level(inner) {
+            let restored = entry.split_once(':')
+                .and_then(|(id, state)| Some((self.components.get(&id.parse().ok()?)?, state)))
+                .map(|(component, state)| component.0.load_state(state))
+                .unwrap_or(false);
+            if !restored {
+                return false;
+            }
+        }
+        
+        self.resimulate();
+        true
+    }
+}
+
+/// Splits at every comma that isn't inside of parentheses
+fn split_top_level(s: &str) -> Vec<&str> {
+    if s.is_empty() {
+        return Vec::new();
+    }
+    
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {},
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn invalid(line: usize, reason: String) -> LogikError {
+    LogikError::InvalidNetlist { line, reason }
+}
+
+fn parse<T: FromStr>(line: usize, word: Option<&str>, what: &str) -> Result<T, LogikError> {
+    word.and_then(|word| word.parse().ok())
+        .ok_or_else(|| invalid(line, format!("expected a {}", what)))
+}
+
+fn end_of_line<'a>(line: usize, mut words: impl Iterator<Item = &'a str>) -> Result<(), LogikError> {
+    match words.next() {
+        Some(word) => Err(invalid(line, format!("unexpected '{}'", word))),
+        None => Ok(()),
+    }
+}
@@ -1,23 +1,35 @@
 use std::collections::HashSet;
 
+/// The widest value a single subnet can carry
+pub(crate) const MAX_WIDTH: u8 = 64;
+
+/// The number with the lowest `width` bits set
+pub(crate) fn mask(width: u8) -> u64 {
+    u64::MAX.checked_shr(64 - width as u32).unwrap_or(0)
+}
+
 /// Representing a subnet with a certain state
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub(crate) struct Subnet {
-    state: SubnetState,
+    state: Value,
 }
 
 impl Subnet {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(width: u8) -> Self {
         Self {
-            state: SubnetState::Floating,
+            state: Value::floating(width),
         }
     }
     
-    pub(crate) fn val(&self) -> SubnetState {
-        self.state
+    pub(crate) fn val(&self) -> Value {
+        self.state.clone()
     }
     
-    pub(crate) fn update(&mut self, state: SubnetState) -> bool {
+    pub(crate) fn width(&self) -> u8 {
+        self.state.width()
+    }
+    
+    pub(crate) fn update(&mut self, state: Value) -> bool {
         if self.state == state {
             false
         } else {
@@ -39,7 +51,7 @@ pub enum SubnetState {
 
 impl SubnetState {
     pub(crate) fn work_out_diff(diff: &HashSet<SubnetState>) -> SubnetState {
-        let filtered = diff.into_iter()
+        let filtered = diff.iter()
             .filter(|e| e != &&SubnetState::Floating)
             .collect::<Vec<_>>();
         
@@ -51,24 +63,105 @@ impl SubnetState {
     }
     
     pub(crate) fn truthy(&self) -> bool {
-        match self {
-            SubnetState::On => true,
-            _ => false,
-        }
+        matches!(self, SubnetState::On)
     }
     
     pub(crate) fn falsy(&self) -> bool {
-        match self {
-            SubnetState::Off | SubnetState::Floating => true,
-            _ => false,
+        matches!(self, SubnetState::Off | SubnetState::Floating)
+    }
+}
+
+/// A vector of bit states carried by a subnet or a port, stored least significant bit first
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
+pub(crate) struct Value {
+    bits: Vec<SubnetState>,
+}
+
+impl Value {
+    /// Creates a value of the given width where every bit is floating
+    pub(crate) fn floating(width: u8) -> Self {
+        Self {
+            bits: vec![SubnetState::Floating; width as usize],
         }
     }
+    
+    /// Creates a value of the given width where every bit is in the error state
+    pub(crate) fn error(width: u8) -> Self {
+        Self {
+            bits: vec![SubnetState::Error; width as usize],
+        }
+    }
+    
+    pub(crate) fn width(&self) -> u8 {
+        self.bits.len() as u8
+    }
+    
+    /// Gets the state of a single bit, bits outside of the value are floating
+    pub(crate) fn get(&self, bit: usize) -> SubnetState {
+        self.bits.get(bit).copied().unwrap_or(SubnetState::Floating)
+    }
+    
+    pub(crate) fn bits(&self) -> &[SubnetState] {
+        &self.bits
+    }
+    
+    /// Creates a value out of the lowest `width` bits of a number
+    pub(crate) fn from_u64(number: u64, width: u8) -> Self {
+        let bits = (0..width as u32)
+            .map(|bit| if number.checked_shr(bit).unwrap_or(0) & 1 == 1 { SubnetState::On } else { SubnetState::Off })
+            .collect();
+        
+        Self { bits }
+    }
+    
+    /// The number the bits stand for, if every bit is either on or off
+    pub(crate) fn to_u64(&self) -> Option<u64> {
+        self.bits.iter().enumerate().try_fold(0, |number, (bit, state)| match state {
+            SubnetState::On => Some(number | 1 << bit),
+            SubnetState::Off => Some(number),
+            _ => None,
+        })
+    }
+    
+    /// Takes the bits of a weaker value wherever this value is floating
+    pub(crate) fn or_weak(self, weak: &Value) -> Self {
+        let bits = self.bits.into_iter()
+            .enumerate()
+            .map(|(bit, state)| if state == SubnetState::Floating { weak.get(bit) } else { state })
+            .collect();
+        
+        Self { bits }
+    }
+    
+    /// Resolves the values driven onto a subnet bit by bit
+    pub(crate) fn work_out_diff(diff: &HashSet<Value>, width: u8) -> Value {
+        let bits = (0..width as usize)
+            .map(|bit| SubnetState::work_out_diff(&diff.iter()
+                .map(|value| value.get(bit))
+                .collect()))
+            .collect();
+        
+        Self { bits }
+    }
+}
+
+impl From<SubnetState> for Value {
+    fn from(state: SubnetState) -> Self {
+        Self { bits: vec![state] }
+    }
+}
+
+impl From<Vec<SubnetState>> for Value {
+    fn from(bits: Vec<SubnetState>) -> Self {
+        Self { bits }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::data::subnet::SubnetState;
+    use crate::data::subnet::{SubnetState, Value, mask};
     use crate::set;
+    use std::collections::HashSet;
     
     #[test]
     fn test_working_out_subnet_diff() {
@@ -90,4 +183,38 @@ mod test {
         let d6 = set!(SubnetState::Off, SubnetState::On);
         assert_eq!(SubnetState::work_out_diff(&d6), SubnetState::Error);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_working_out_value_diff() {
+        use SubnetState::*;
+        
+        let d1 = set!(Value::from(vec![On, Floating, Off]), Value::from(vec![Floating, Floating, Off]));
+        assert_eq!(Value::work_out_diff(&d1, 3), Value::from(vec![On, Floating, Off]));
+        
+        let d2 = set!(Value::from(vec![On, Off, Floating]), Value::from(vec![Off, Off, On]));
+        assert_eq!(Value::work_out_diff(&d2, 3), Value::from(vec![Error, Off, On]));
+        
+        let d3 = set!(Value::floating(2));
+        assert_eq!(Value::work_out_diff(&d3, 2), Value::floating(2));
+        
+        assert_eq!(Value::work_out_diff(&HashSet::new(), 4), Value::floating(4));
+        
+        let weak = Value::from(vec![On, On, Off]);
+        assert_eq!(Value::from(vec![Floating, Off, Error]).or_weak(&weak), Value::from(vec![On, Off, Error]));
+    }
+    
+    #[test]
+    fn test_numbers() {
+        use SubnetState::*;
+        
+        assert_eq!(Value::from_u64(6, 4), Value::from(vec![Off, On, On, Off]));
+        assert_eq!(Value::from_u64(0xff, 2), Value::from(vec![On, On]));
+        assert_eq!(Value::from_u64(u64::MAX, 64).to_u64(), Some(u64::MAX));
+        assert_eq!(Value::from(vec![On, Off, On]).to_u64(), Some(5));
+        assert_eq!(Value::from(vec![On, Floating]).to_u64(), None);
+        
+        assert_eq!(mask(0), 0);
+        assert_eq!(mask(4), 0xf);
+        assert_eq!(mask(64), u64::MAX);
+    }
+}
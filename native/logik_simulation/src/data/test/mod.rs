@@ -1,7 +1,12 @@
 use crate::data::component::components::*;
 use crate::{map, set};
 use super::*;
-use crate::data::component::statefuls::{SRFlipFlop, Constant};
+use crate::data::error::LogikError;
+use crate::data::component::statefuls::{SRFlipFlop, Constant, DFlipFlop, Clock, Switch, Register};
+use crate::data::component::ComponentId;
+use crate::data::component::parameter::ParameterValue;
+use crate::data::component::memory::ImageFormat;
+use crate::data::analysis::synthesis::Synthesis;
 use std::cell::Cell;
 
 macro_rules! edge {
@@ -34,8 +39,8 @@ macro_rules! edge {
 macro_rules! subnet {
         ($state:expr) => {
             {
-                let mut s = Subnet::new();
-                s.update($state);
+                let mut s = Subnet::new(1);
+                s.update($state.into());
                 s
             }
         };
@@ -45,16 +50,16 @@ macro_rules! subnet {
 fn test_adding_components() {
     let mut data = Data::new();
     
-    data.add_subnet(0);
+    data.add_subnet(0).unwrap();
     
-    assert!(data.add_component(Box::new(OutputGate {}), vec![Some(0)]).is_ok());
+    assert!(data.add_component(Box::new(OutputGate::new()), vec![Some(0)]).is_ok());
     
-    data.add_subnet(1);
-    data.add_subnet(5);
+    data.add_subnet(1).unwrap();
+    data.add_subnet(5).unwrap();
     
-    assert!(data.add_component(Box::new(AND {}), vec![Some(1), Some(5), Some(0)]).is_ok());
+    assert!(data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(5), Some(0)]).is_ok());
     
-    assert!(data.add_component(Box::new(OutputGate {}), vec![Some(0)]).is_ok());
+    assert!(data.add_component(Box::new(OutputGate::new()), vec![Some(0)]).is_ok());
     
     assert_eq!(data.component_edges, map!(
         1 => set!(edge!(0, 1, 0, 0)),
@@ -67,20 +72,20 @@ fn test_adding_components() {
         5 => set!(edge!(5, 2, 1, 0))
     ));
     
-    assert!(data.add_component(Box::new(AND {}), vec![]).is_err());
+    assert!(data.add_component(Box::new(AND::new(2)), vec![]).is_err());
 }
 
 #[test]
 fn test_removing_subnets() {
     let mut data = Data::new();
     
-    data.add_subnet(0);
-    data.add_subnet(1);
+    data.add_subnet(0).unwrap();
+    data.add_subnet(1).unwrap();
     
     assert_eq!(data.component_edges, map!());
     assert_eq!(data.subnet_edges, map!());
     
-    assert!(data.add_component(Box::new(OutputGate {}), vec![Some(0)]).is_ok());
+    assert!(data.add_component(Box::new(OutputGate::new()), vec![Some(0)]).is_ok());
     
     assert_eq!(data.component_edges, map!(
         1 => set!(edge!(0, 1, 0, 0))
@@ -89,31 +94,31 @@ fn test_removing_subnets() {
         0 => set!(edge!(0, 1, 0, 0))
     ));
     
-    assert!(data.remove_subnet(0));
+    assert!(data.remove_subnet(0).is_ok());
     
     assert_eq!(data.component_edges, map!());
     assert_eq!(data.subnet_edges, map!());
     
-    assert!(data.remove_subnet(1));
+    assert!(data.remove_subnet(1).is_ok());
     
     assert_eq!(data.component_edges, map!());
     assert_eq!(data.subnet_edges, map!());
     
-    assert!(!data.remove_subnet(0));
-    assert!(!data.remove_subnet(3));
+    assert!(data.remove_subnet(0).is_err());
+    assert!(data.remove_subnet(3).is_err());
 }
 
 #[test]
 fn test_simulation() {
     let mut data = Data::new();
     
-    data.add_subnet(0);
-    data.add_subnet(1);
+    data.add_subnet(0).unwrap();
+    data.add_subnet(1).unwrap();
     
     assert!(data.add_component(Box::new(NOT {}), vec![Some(0), Some(1)]).is_ok());
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(false) }), vec![Some(0)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(false)), vec![Some(0)]).is_ok());
     
-    assert_eq!(data.simulation.dirty_subnets, VecDeque::from(vec![]));
+    assert_eq!(data.simulation.dirty_subnets, BTreeMap::new());
     assert_eq!(data.subnets, map!(
         0 => subnet!(SubnetState::Off),
         1 => subnet!(SubnetState::On)
@@ -124,17 +129,17 @@ fn test_simulation() {
 fn test_simulation_2() {
     let mut data = Data::new();
     
-    data.add_subnet(1);
-    data.add_subnet(2);
-    data.add_subnet(5);
-    data.add_subnet(7);
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(5).unwrap();
+    data.add_subnet(7).unwrap();
     
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(false) }), vec![Some(7)]).is_ok());
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(true) }), vec![Some(2)]).is_ok());
-    assert!(data.add_component(Box::new(AND {}), vec![Some(1), Some(2), Some(5)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(false)), vec![Some(7)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(true)), vec![Some(2)]).is_ok());
+    assert!(data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(5)]).is_ok());
     assert!(data.add_component(Box::new(NOT {}), vec![Some(7), Some(1)]).is_ok());
     
-    assert_eq!(data.simulation.dirty_subnets, VecDeque::from(vec![]));
+    assert_eq!(data.simulation.dirty_subnets, BTreeMap::new());
     assert_eq!(data.subnets, map!(
         1 => subnet!(SubnetState::On),
         2 => subnet!(SubnetState::On),
@@ -147,21 +152,21 @@ fn test_simulation_2() {
 fn test_simulation_3() {
     let mut data = Data::new();
     
-    data.add_subnet(1);
-    data.add_subnet(2);
-    data.add_subnet(3);
-    data.add_subnet(4);
-    data.add_subnet(5);
-    data.add_subnet(6);
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_subnet(4).unwrap();
+    data.add_subnet(5).unwrap();
+    data.add_subnet(6).unwrap();
     
-    assert!(data.add_component(Box::new(AND {}), vec![Some(3), Some(3), Some(4)]).is_ok());
-    assert!(data.add_component(Box::new(AND {}), vec![Some(1), Some(2), Some(3)]).is_ok());
+    assert!(data.add_component(Box::new(AND::new(2)), vec![Some(3), Some(3), Some(4)]).is_ok());
+    assert!(data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(3)]).is_ok());
     assert!(data.add_component(Box::new(NOT {}), vec![Some(5), Some(1)]).is_ok());
     assert!(data.add_component(Box::new(NOT {}), vec![Some(6), Some(2)]).is_ok());
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(false) }), vec![Some(5)]).is_ok());
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(false) }), vec![Some(6)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(false)), vec![Some(5)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(false)), vec![Some(6)]).is_ok());
     
-    assert_eq!(data.simulation.dirty_subnets, VecDeque::from(vec![]));
+    assert_eq!(data.simulation.dirty_subnets, BTreeMap::new());
     assert_eq!(data.subnets, map!(
         1 => subnet!(SubnetState::On),
         2 => subnet!(SubnetState::On),
@@ -174,25 +179,26 @@ fn test_simulation_3() {
 
 // probably easiest to implement with buttons and such
 //#[test]
+#[allow(dead_code)]
 fn test_sr_latch() {
     let mut data = Data::new();
     
-    data.add_subnet(0);
-    data.add_subnet(1);
-    data.add_subnet(2);
-    data.add_subnet(3);
-    data.add_subnet(4);
-    data.add_subnet(5);
+    data.add_subnet(0).unwrap();
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_subnet(4).unwrap();
+    data.add_subnet(5).unwrap();
     
     assert!(data.add_component(Box::new( SRFlipFlop { state: Cell::new(false) }),
                        vec![Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)]).is_ok());
-    assert!(data.add_component(Box::new( Constant { state: Cell::new(true) }), vec![Some(0)]).is_ok());
-    assert!(data.add_component(Box::new( Constant { state: Cell::new(false) }), vec![Some(2)]).is_ok());
+    assert!(data.add_component(Box::new( Constant::with_state(true)), vec![Some(0)]).is_ok());
+    assert!(data.add_component(Box::new( Constant::with_state(false)), vec![Some(2)]).is_ok());
     
-    data.update_subnet(0, SubnetState::On);
-    data.update_subnet(2, SubnetState::Off);
+    data.update_subnet(0, SubnetState::On.into());
+    data.update_subnet(2, SubnetState::Off.into());
     
-    data.advance_time();
+    data.advance_time_once();
     
     assert_eq!(data.subnets, map!(
         0 => subnet!(SubnetState::On),
@@ -203,9 +209,9 @@ fn test_sr_latch() {
         5 => subnet!(SubnetState::On)
     ));
     
-    data.update_subnet(2, SubnetState::On);
+    data.update_subnet(2, SubnetState::On.into());
     
-    data.advance_time();
+    data.advance_time_once();
     
     assert_eq!(data.subnets, map!(
         0 => subnet!(SubnetState::On),
@@ -216,11 +222,11 @@ fn test_sr_latch() {
         5 => subnet!(SubnetState::Off)
     ));
     
-    data.update_subnet(0, SubnetState::Off);
-    data.update_subnet(1, SubnetState::On);
-    data.update_subnet(2, SubnetState::Off);
+    data.update_subnet(0, SubnetState::Off.into());
+    data.update_subnet(1, SubnetState::On.into());
+    data.update_subnet(2, SubnetState::Off.into());
     
-    data.advance_time();
+    data.advance_time_once();
     
     assert_eq!(data.subnets, map!(
         0 => subnet!(SubnetState::Off),
@@ -231,9 +237,9 @@ fn test_sr_latch() {
         5 => subnet!(SubnetState::Off)
     ));
     
-    data.update_subnet(2, SubnetState::On);
+    data.update_subnet(2, SubnetState::On.into());
     
-    data.advance_time();
+    data.advance_time_once();
     
     assert_eq!(data.subnets, map!(
         0 => subnet!(SubnetState::Off),
@@ -249,12 +255,12 @@ fn test_sr_latch() {
 fn test_error_driving() {
     let mut data = Data::new();
     
-    data.add_subnet(0);
-    data.add_subnet(1);
-    data.add_subnet(2);
+    data.add_subnet(0).unwrap();
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
     
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(false) }), vec![Some(0)]).is_ok());
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(true) }), vec![Some(1)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(false)), vec![Some(0)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(true)), vec![Some(1)]).is_ok());
     assert!(data.add_component(Box::new(NOT {}), vec![Some(0), Some(2)]).is_ok());
     assert!(data.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).is_ok());
     
@@ -269,29 +275,29 @@ fn test_error_driving() {
 fn test_linking() {
     let mut data = Data::new();
     
-    data.add_subnet(0);
-    data.add_subnet(1);
-    data.add_subnet(2);
+    data.add_subnet(0).unwrap();
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
     
-    assert!(data.add_component(Box::new(AND {}), vec![Some(0), Some(1), Some(2)]).is_ok());
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(true) }), vec![Some(0)]).is_ok());
-    assert!(data.add_component(Box::new(Constant { state: Cell::new(true) }), vec![Some(1)]).is_ok());
+    assert!(data.add_component(Box::new(AND::new(2)), vec![Some(0), Some(1), Some(2)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(true)), vec![Some(0)]).is_ok());
+    assert!(data.add_component(Box::new(Constant::with_state(true)), vec![Some(1)]).is_ok());
     
-    assert_eq!(data.simulation.dirty_subnets, VecDeque::from(vec![]));
+    assert_eq!(data.simulation.dirty_subnets, BTreeMap::new());
     
-    assert_eq!(data.subnets.get(&2).unwrap().val(), SubnetState::On);
+    assert_eq!(data.subnets.get(&2).unwrap().val(), SubnetState::On.into());
 }
 
 #[test]
 fn test_simulating_loop() {
     let mut data = Data::new();
     
-    data.add_subnet(1);
+    data.add_subnet(1).unwrap();
     
     assert!(data.add_component(Box::new(Constant::new()), vec![Some(1)]).is_ok());
     assert!(data.add_component(Box::new(NOT {}), vec![Some(1), Some(1)]).is_ok());
     
-    assert_eq!(data.simulation.dirty_subnets, VecDeque::from(vec![]));
+    assert_eq!(data.simulation.dirty_subnets, BTreeMap::new());
     assert_eq!(data.subnets, map!(
         1 => subnet!(SubnetState::Error)
     ));
@@ -301,12 +307,12 @@ fn test_simulating_loop() {
 fn test_relinking() {
     let mut data = Data::new();
     
-    data.add_subnet(1);
-    data.add_subnet(2);
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
     
     assert!(data.add_component(Box::new(Constant::new()), vec![None]).is_ok());
     
-    data.link(1, 0, 1);
+    data.link(1, 0, 1).unwrap();
     
     assert_eq!(data.component_edges, map!(
         1 => set!(edge!(1, 1, 0, 2))
@@ -315,7 +321,7 @@ fn test_relinking() {
         1 => set!(edge!(1, 1, 0, 2))
     ));
     
-    data.link(1, 0, 2);
+    data.link(1, 0, 2).unwrap();
     
     assert_eq!(data.component_edges, map!(
         1 => set!(edge!(2, 1, 0, 2))
@@ -329,22 +335,1856 @@ fn test_relinking() {
 fn test_unlinking_last() {
     let mut data = Data::new();
     
-    data.add_subnet(1);
+    data.add_subnet(1).unwrap();
     data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
     
     assert_eq!(data.subnets, map!(
         1 => subnet!(SubnetState::Floating)
     ));
     
-    assert!(data.link(1, 0, 1));
+    assert!(data.link(1, 0, 1).is_ok());
     
     assert_eq!(data.subnets, map!(
         1 => subnet!(SubnetState::Off)
     ));
     
-    assert!(data.unlink(1, 0, 1));
+    assert!(data.unlink(1, 0, 1).is_ok());
     
     assert_eq!(data.subnets, map!(
         1 => subnet!(SubnetState::Floating)
     ));
-}
\ No newline at end of file
+}
+/// Drives a fixed multi-bit value, used until there are real bus components
+#[derive(Debug)]
+struct BusConstant {
+    value: Value,
+}
+
+impl Component for BusConstant {
+    // only used in tests that never save their circuit
+    fn id(&self) -> ComponentId {
+        ComponentId::Constant
+    }
+    
+    fn ports(&self) -> usize {
+        1
+    }
+    
+    fn port_type(&self, port: usize) -> Option<PortType> {
+        match port {
+            0 => Some(PortType::Output),
+            _ => None,
+        }
+    }
+    
+    fn port_width(&self, port: usize) -> Option<u8> {
+        self.port_type(port).map(|_| self.value.width())
+    }
+    
+    fn evaluate(&self, _: HashMap<usize, StateChange>) -> HashMap<usize, Value> {
+        map!(0 => self.value.clone())
+    }
+}
+
+#[test]
+fn test_wide_subnets() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    assert!(data.add_subnet_with_width(1, 4).is_ok());
+    assert_eq!(data.add_subnet_with_width(1, 4), Err(LogikError::SubnetAlreadyExists(1)));
+    assert_eq!(data.add_subnet_with_width(2, 0), Err(LogikError::InvalidWidth(0)));
+    assert_eq!(data.add_subnet_with_width(2, MAX_WIDTH + 1), Err(LogikError::InvalidWidth(MAX_WIDTH + 1)));
+    
+    assert_eq!(data.subnet_value(1), Ok(Value::floating(4)));
+    
+    assert!(data.add_component(Box::new(BusConstant { value: vec![On, Off, Off, On].into() }), vec![Some(1)]).is_ok());
+    assert!(data.add_component(Box::new(BusConstant { value: vec![Floating, Off, On, On].into() }), vec![Some(1)]).is_ok());
+    
+    assert_eq!(data.subnet_value(1), Ok(vec![On, Off, Error, On].into()));
+    assert_eq!(data.subnet_state(1), Ok(On));
+}
+
+#[test]
+fn test_linking_mismatched_width() {
+    let mut data = Data::new();
+    
+    data.add_subnet_with_width(1, 8).unwrap();
+    data.add_subnet(2).unwrap();
+    
+    let not = data.add_component(Box::new(NOT {}), vec![None, None]).unwrap();
+    let bus = data.add_component(Box::new(BusConstant { value: Value::floating(8) }), vec![None]).unwrap();
+    
+    assert_eq!(data.link(not, 0, 1), Err(LogikError::WidthMismatch { port_width: 1, subnet_width: 8 }));
+    assert_eq!(data.link(bus, 0, 2), Err(LogikError::WidthMismatch { port_width: 8, subnet_width: 1 }));
+    assert_eq!(data.link(bus, 0, 3), Err(LogikError::UnknownSubnet(3)));
+    assert!(data.link(bus, 0, 1).is_ok());
+    assert!(data.link(not, 0, 2).is_ok());
+    
+    assert_eq!(data.component_edges, map!(
+        not => set!(edge!(2, not, 0, 0)),
+        bus => set!(edge!(1, bus, 0, 2))
+    ));
+}
+
+#[test]
+fn test_component_delay() {
+    let mut data = Data::new();
+    
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    
+    let constant = data.add_component(Box::new(Constant::new()), vec![Some(1)]).unwrap();
+    let not = data.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).unwrap();
+    
+    assert_eq!(data.set_delay(not, 0), Err(LogikError::InvalidDelay(0)));
+    assert_eq!(data.set_delay(not + 1, 3), Err(LogikError::UnknownComponent(not + 1)));
+    assert!(data.set_delay(not, 3).is_ok());
+    assert_eq!(data.delay(not), Ok(3));
+    assert_eq!(data.delay(constant), Ok(Simulator::DEFAULT_DELAY));
+    
+    data.set_stepping(true);
+    data.press_component(constant).unwrap();
+    
+    let start = data.time();
+    data.advance_time(1);
+    assert_eq!(data.subnet_state(1), Ok(SubnetState::On));
+    assert_eq!(data.subnet_state(2), Ok(SubnetState::On));
+    
+    data.advance_time(2);
+    assert_eq!(data.subnet_state(2), Ok(SubnetState::On));
+    
+    data.advance_time(1);
+    assert_eq!(data.subnet_state(2), Ok(SubnetState::Off));
+    assert_eq!(data.time(), start + 4);
+    assert_eq!(data.simulation.dirty_subnets, BTreeMap::new());
+    
+    // long delays are waited out without counting as a circuit that doesn't settle, and nothing
+    // is kept for the time in between
+    data.set_stepping(false);
+    data.set_delay(not, 2000).unwrap();
+    data.press_component(constant).unwrap();
+    assert_eq!(data.subnet_state(2), Ok(SubnetState::On));
+    assert_eq!(data.oscillating_subnets(), vec![]);
+    
+    data.set_delay(not, u32::MAX).unwrap();
+    data.set_stepping(true);
+    data.press_component(constant).unwrap();
+    assert_eq!(data.simulation.dirty_subnets.len(), 1);
+    data.advance_time(u32::MAX - 1);
+    assert_eq!(data.subnet_state(2), Ok(SubnetState::On));
+    data.advance_time(2);
+    assert_eq!(data.subnet_state(2), Ok(SubnetState::Off));
+}
+
+#[test]
+fn test_static_hazard() {
+    let mut data = Data::new();
+    
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    
+    let constant = data.add_component(Box::new(Constant::new()), vec![Some(1)]).unwrap();
+    data.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).unwrap();
+    data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(3)]).unwrap();
+    
+    assert_eq!(data.subnet_state(3), Ok(SubnetState::Off));
+    
+    data.set_stepping(true);
+    data.press_component(constant).unwrap();
+    
+    let mut output = Vec::new();
+    for _ in 0..4 {
+        data.advance_time(1);
+        output.push(data.subnet_state(3).unwrap());
+    }
+    
+    assert_eq!(output, vec![SubnetState::Off, SubnetState::On, SubnetState::Off, SubnetState::Off]);
+}
+
+#[test]
+fn test_ring_oscillator() {
+    let mut data = Data::new();
+    
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_subnet(4).unwrap();
+    
+    let enable = data.add_component(Box::new(Constant::new()), vec![Some(4)]).unwrap();
+    data.add_component(Box::new(NAND::new(2)), vec![Some(4), Some(3), Some(1)]).unwrap();
+    data.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).unwrap();
+    data.add_component(Box::new(NOT {}), vec![Some(2), Some(3)]).unwrap();
+    
+    assert_eq!(data.oscillating_subnets(), vec![]);
+    assert_eq!(data.subnet_state(3), Ok(SubnetState::On));
+    
+    data.press_component(enable).unwrap();
+    
+    assert_eq!(data.oscillating_subnets(), vec![1, 2, 3]);
+    assert_eq!(data.simulation.dirty_subnets, BTreeMap::new());
+    assert_eq!(data.subnets, map!(
+        1 => subnet!(SubnetState::Error),
+        2 => subnet!(SubnetState::Error),
+        3 => subnet!(SubnetState::Error),
+        4 => subnet!(SubnetState::On)
+    ));
+    
+    data.press_component(enable).unwrap();
+    
+    assert_eq!(data.oscillating_subnets(), vec![]);
+    assert_eq!(data.subnets, map!(
+        1 => subnet!(SubnetState::On),
+        2 => subnet!(SubnetState::Off),
+        3 => subnet!(SubnetState::On),
+        4 => subnet!(SubnetState::Off)
+    ));
+}
+
+
+#[test]
+fn test_slow_settling() {
+    let mut data = Data::new();
+    
+    // a ring oscillator next to a long chain of inverters ending in a slow one, all started by
+    // the same constant
+    for subnet in 1..=4 {
+        data.add_subnet(subnet).unwrap();
+    }
+    let enable = data.add_component(Box::new(Constant::new()), vec![Some(4)]).unwrap();
+    data.add_component(Box::new(NAND::new(2)), vec![Some(4), Some(3), Some(1)]).unwrap();
+    data.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).unwrap();
+    data.add_component(Box::new(NOT {}), vec![Some(2), Some(3)]).unwrap();
+    let mut last = 4;
+    for subnet in 10..1510 {
+        data.add_subnet(subnet).unwrap();
+        data.add_component(Box::new(NOT {}), vec![Some(last), Some(subnet)]).unwrap();
+        last = subnet;
+    }
+    data.add_subnet(2000).unwrap();
+    let slow = data.add_component(Box::new(Buffer {}), vec![Some(last), Some(2000)]).unwrap();
+    data.set_delay(slow, 5000).unwrap();
+    assert_eq!(data.oscillating_subnets(), vec![]);
+    assert_eq!(data.subnet_state(2000), Ok(SubnetState::Off));
+    
+    // only the ring is marked, the change still makes it all the way through the chain
+    data.press_component(enable).unwrap();
+    assert_eq!(data.oscillating_subnets(), vec![1, 2, 3]);
+    assert_eq!(data.subnet_state(last), Ok(SubnetState::On));
+    assert_eq!(data.subnet_state(2000), Ok(SubnetState::On));
+    assert_eq!(data.simulation.dirty_subnets, BTreeMap::new());
+}
+
+#[test]
+fn test_errors() {
+    let mut data = Data::new();
+    
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    
+    let and = data.add_component(Box::new(AND::new(2)), vec![Some(1), None, None]).unwrap();
+    
+    assert_eq!(data.add_component(Box::new(AND::new(2)), vec![None]), Err(LogikError::WrongPortCount { expected: 3, got: 1 }));
+    assert_eq!(data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(7), None]), Err(LogikError::UnknownSubnet(7)));
+    assert_eq!(data.components.len(), 1);
+    
+    assert_eq!(data.link(and + 1, 0, 1), Err(LogikError::UnknownComponent(and + 1)));
+    assert_eq!(data.link(and, 3, 1), Err(LogikError::PortOutOfRange { component: and, port: 3 }));
+    assert_eq!(data.unlink(and, 0, 2), Err(LogikError::NotLinked { component: and, port: 0, subnet: 2 }));
+    assert_eq!(data.port_state(and, 5), Err(LogikError::PortOutOfRange { component: and, port: 5 }));
+    assert_eq!(data.subnet_state(3), Err(LogikError::UnknownSubnet(3)));
+    assert_eq!(data.press_component(and + 1), Err(LogikError::UnknownComponent(and + 1)));
+    assert_eq!(data.remove_component(and), Ok(()));
+    assert_eq!(data.remove_component(and), Err(LogikError::UnknownComponent(and)));
+}
+
+#[test]
+fn test_netlist_round_trip() {
+    let mut data = Data::new();
+    
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_subnet_with_width(4, 8).unwrap();
+    
+    let a = data.add_component(Box::new(Constant::new()), vec![Some(1)]).unwrap();
+    let removed = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    let b = data.add_component(Box::new(Constant::new()), vec![Some(2)]).unwrap();
+    let and = data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(3)]).unwrap();
+    data.remove_component(removed).unwrap();
+    data.set_delay(and, 3).unwrap();
+    data.press_component(a).unwrap();
+    data.press_component(b).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(SubnetState::On));
+    
+    let netlist = data.save();
+    assert_eq!(netlist, "logik 1\n\
+        subnet 1 1\n\
+        subnet 2 1\n\
+        subnet 3 1\n\
+        subnet 4 8\n\
+        component 1 Constant value=1\n\
+        component 3 Constant value=1\n\
+        component 4 And delay=3\n\
+        link 1 0 1\n\
+        link 3 0 2\n\
+        link 4 0 1\n\
+        link 4 1 2\n\
+        link 4 2 3\n");
+    
+    let mut loaded = Data::load(&netlist).unwrap();
+    assert_eq!(loaded.save(), netlist);
+    assert_eq!(loaded.subnets, data.subnets);
+    assert_eq!(loaded.delay(and), Ok(3));
+    
+    assert_eq!(loaded.add_component(Box::new(NOT {}), vec![None, None]), Ok(removed));
+    assert_eq!(loaded.add_component(Box::new(NOT {}), vec![None, None]), Ok(and + 1));
+}
+
+#[test]
+fn test_invalid_netlists() {
+    let invalid = |line, reason: &str| Err(LogikError::InvalidNetlist { line, reason: String::from(reason) });
+    
+    assert_eq!(Data::load("").map(|_| ()), invalid(1, "the netlist is empty"));
+    assert_eq!(Data::load("logik 2\n").map(|_| ()), invalid(1, "version 2 is newer than supported"));
+    assert_eq!(Data::load("circuit 1\n").map(|_| ()), invalid(1, "expected the netlist header"));
+    assert_eq!(
+        Data::load("logik 1\n# comment\n\ncomponent 1 Flux\n").map(|_| ()),
+        invalid(4, "expected a component type")
+    );
+    assert_eq!(
+        Data::load("logik 1\ncomponent 1 Not\ncomponent 1 Not\n").map(|_| ()),
+        invalid(3, "1 is not a free component id")
+    );
+    assert_eq!(
+        Data::load("logik 1\ncomponent 1 Not state=1\n").map(|_| ()),
+        invalid(2, "'1' is not a valid state for component 1")
+    );
+    assert_eq!(
+        Data::load("logik 1\nsubnet 1 1\nlink 1 0 1\n").map(|_| ()),
+        invalid(3, "there is no component with id 1")
+    );
+    assert_eq!(Data::load("logik 1\nsubnet 1 1 1\n").map(|_| ()), invalid(2, "unexpected '1'"));
+    assert_eq!(
+        Data::load("logik 1\ncomponent 1 And inputs=40\n").map(|_| ()),
+        invalid(2, "invalid parameter, inputs has to be between 2 and 32, got 40")
+    );
+    assert_eq!(Data::load("logik 1\ncomponent 1 Not inputs=3\n").map(|_| ()), invalid(2, "unexpected 'inputs=3'"));
+}
+
+/// A half adder with the inputs a and b on ports 0 and 1, the sum on port 2 and the carry on port 3
+fn half_adder() -> Data {
+    let mut data = Data::new();
+    
+    for subnet in 1..=4 {
+        data.add_subnet(subnet).unwrap();
+    }
+    
+    data.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    data.add_component(Box::new(InputGate::new()), vec![Some(2)]).unwrap();
+    data.add_component(Box::new(OutputGate::new()), vec![Some(3)]).unwrap();
+    data.add_component(Box::new(OutputGate::new()), vec![Some(4)]).unwrap();
+    data.add_component(Box::new(XOR::new(2, false)), vec![Some(1), Some(2), Some(3)]).unwrap();
+    data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(4)]).unwrap();
+    
+    data
+}
+
+#[test]
+fn test_subcircuits() {
+    let mut data = Data::new();
+    
+    data.define_subcircuit("half_adder", &half_adder()).unwrap();
+    assert_eq!(data.define_subcircuit("half_adder", &half_adder()), Err(LogikError::SubcircuitExists(String::from("half_adder"))));
+    assert_eq!(data.define_subcircuit("half adder", &half_adder()), Err(LogikError::InvalidName(String::from("half adder"))));
+    assert_eq!(data.add_subcircuit("full_adder"), Err(LogikError::UnknownSubcircuit(String::from("full_adder"))));
+    
+    for subnet in 1..=6 {
+        data.add_subnet(subnet).unwrap();
+    }
+    
+    let a = data.add_component(Box::new(Constant::new()), vec![Some(1)]).unwrap();
+    let b = data.add_component(Box::new(Constant::new()), vec![Some(2)]).unwrap();
+    let first = data.add_subcircuit("half_adder").unwrap();
+    let second = data.add_subcircuit("half_adder").unwrap();
+    for (port, subnet) in [1, 2, 3, 4].iter().enumerate() {
+        data.link(first, port, *subnet).unwrap();
+    }
+    for (port, subnet) in [3, 4, 5, 6].iter().enumerate() {
+        data.link(second, port, *subnet).unwrap();
+    }
+    
+    assert_eq!(data.port_state(first, 2), Ok(SubnetState::Off));
+    
+    data.press_component(a).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(SubnetState::On));
+    assert_eq!(data.subnet_state(4), Ok(SubnetState::Off));
+    assert_eq!(data.subnet_state(5), Ok(SubnetState::On));
+    assert_eq!(data.subnet_state(6), Ok(SubnetState::Off));
+    
+    data.press_component(b).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(SubnetState::Off));
+    assert_eq!(data.subnet_state(4), Ok(SubnetState::On));
+    assert_eq!(data.subnet_state(5), Ok(SubnetState::On));
+    assert_eq!(data.subnet_state(6), Ok(SubnetState::Off));
+    
+    assert_eq!(data.subnet_value_at(&[first], 4), Ok(SubnetState::On.into()));
+    assert_eq!(data.subnet_value_at(&[second], 2), Ok(SubnetState::On.into()));
+    assert_eq!(data.subnet_value_at(&[a], 1), Err(LogikError::NotASubcircuit(a)));
+    assert_eq!(data.subnet_value_at(&[first, 5], 1), Err(LogikError::NotASubcircuit(5)));
+}
+
+#[test]
+fn test_subcircuit_timing() {
+    use SubnetState::*;
+    
+    // a pulse on the rising edge of the input, as long as the delay of the NOT, and a clock
+    let mut pulse = Data::new();
+    for subnet in 1..=4 {
+        pulse.add_subnet(subnet).unwrap();
+    }
+    pulse.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    pulse.add_component(Box::new(OutputGate::new()), vec![Some(3)]).unwrap();
+    pulse.add_component(Box::new(OutputGate::new()), vec![Some(4)]).unwrap();
+    let not = pulse.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).unwrap();
+    pulse.set_delay(not, 3).unwrap();
+    pulse.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(3)]).unwrap();
+    let clock = pulse.add_component(Box::new(Clock::new()), vec![Some(4)]).unwrap();
+    pulse.clock(clock);
+    
+    let mut data = Data::new();
+    data.define_subcircuit("pulse", &pulse).unwrap();
+    for subnet in 1..=3 {
+        data.add_subnet(subnet).unwrap();
+    }
+    let input = data.add_component(Box::new(Switch::new()), vec![Some(1)]).unwrap();
+    let instance = data.add_subcircuit("pulse").unwrap();
+    for (port, subnet) in (0..3).zip(1..=3) {
+        data.link(instance, port, subnet).unwrap();
+    }
+    assert_eq!(data.subnet_state(2), Ok(Off));
+    
+    // the glitch of the circuit inside makes it out, after the delays inside and of the instance
+    data.set_stepping(true);
+    data.start_recording();
+    let start = data.time();
+    data.press_component(input).unwrap();
+    data.advance_time(20);
+    let trace = data.trace().unwrap();
+    assert_eq!(trace.entries().iter().filter(|e| e.subnet == 2).map(|e| (e.time - start, e.value.get(0))).collect::<Vec<_>>(), vec![
+        (4, On),
+        (7, Off),
+    ]);
+    assert_eq!(data.subnet_value_at(&[instance], 2), Ok(Off.into()));
+    
+    // the subnets inside are recorded as well
+    let vcd = data.to_vcd().unwrap();
+    assert!(vcd.contains(&format!("$scope module c{} $end\n$var wire 1 % subnet_1 $end\n", instance)));
+    assert!(vcd.contains(&format!("#{}\n0&\n", start + 5)));
+    data.set_stepping(false);
+    
+    // the clock inside ticks with the circuit outside, which has no clocks of its own
+    assert_eq!(data.next_clock_edge(), Some(1));
+    data.time_step();
+    assert_eq!(data.subnet_state(3), Ok(On));
+    data.time_step();
+    assert_eq!(data.subnet_state(3), Ok(Off));
+}
+
+#[test]
+fn test_subcircuit_oscillation() {
+    // a ring that starts oscillating once the input is on
+    let mut ring = Data::new();
+    for subnet in 1..=4 {
+        ring.add_subnet(subnet).unwrap();
+    }
+    ring.add_component(Box::new(InputGate::new()), vec![Some(4)]).unwrap();
+    ring.add_component(Box::new(NAND::new(2)), vec![Some(4), Some(3), Some(1)]).unwrap();
+    ring.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).unwrap();
+    ring.add_component(Box::new(NOT {}), vec![Some(2), Some(3)]).unwrap();
+    
+    let mut data = Data::new();
+    data.define_subcircuit("ring", &ring).unwrap();
+    data.add_subnet(1).unwrap();
+    let enable = data.add_component(Box::new(Switch::new()), vec![Some(1)]).unwrap();
+    let instance = data.add_subcircuit("ring").unwrap();
+    data.link(instance, 0, 1).unwrap();
+    
+    // the subnets inside are marked, so the circuit outside settles
+    data.press_component(enable).unwrap();
+    for subnet in 1..=3 {
+        assert_eq!(data.subnet_value_at(&[instance], subnet), Ok(SubnetState::Error.into()));
+    }
+    assert_eq!(data.simulation.next_event(&data.components), None);
+}
+
+#[test]
+fn test_nested_subcircuit_netlist() {
+    let mut inner = half_adder();
+    inner.add_subnet(5).unwrap();
+    let flip_flop = inner.add_component(Box::new(DFlipFlop::new()), vec![None, None, None, Some(5), None]).unwrap();
+    inner.press_component(flip_flop).unwrap();
+    
+    let mut outer = Data::new();
+    outer.define_subcircuit("half_adder", &inner).unwrap();
+    outer.add_subnet(1).unwrap();
+    let instance = outer.add_subcircuit("half_adder").unwrap();
+    outer.link(instance, 0, 1).unwrap();
+    outer.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    
+    let mut data = Data::new();
+    data.define_subcircuit("wrapper", &outer).unwrap();
+    data.add_subnet(1).unwrap();
+    let wrapper = data.add_subcircuit("wrapper").unwrap();
+    data.link(wrapper, 0, 1).unwrap();
+    
+    assert_eq!(data.subnet_value_at(&[wrapper, instance], 5), Ok(SubnetState::On.into()));
+    
+    let netlist = data.save();
+    assert!(netlist.starts_with("logik 1\ndefine wrapper\n    define half_adder\n        subnet 1 1\n"));
+    assert!(netlist.contains("component 1 Custom name=wrapper state=(1:(7:1))\n"));
+    
+    let loaded = Data::load(&netlist).unwrap();
+    assert_eq!(loaded.save(), netlist);
+    assert_eq!(loaded.subnet_value_at(&[wrapper, instance], 5), Ok(SubnetState::On.into()));
+    
+    let broken = netlist.replacen("subnet 1 1\n", "subnet 1 0\n", 1);
+    assert_eq!(Data::load(&broken).map(|_| ()), Err(LogikError::InvalidNetlist {
+        line: 4,
+        reason: String::from("0 is not a valid subnet width"),
+    }));
+}
+
+#[test]
+fn test_recording() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    data.add_subnet(1).unwrap();
+    data.add_subnet_with_width(2, 2).unwrap();
+    data.add_subnet(3).unwrap();
+    
+    let constant = data.add_component(Box::new(Constant::new()), vec![Some(1)]).unwrap();
+    let not = data.add_component(Box::new(NOT {}), vec![Some(1), Some(3)]).unwrap();
+    data.set_delay(not, 2).unwrap();
+    let start = data.time();
+    
+    assert_eq!(data.trace().map(|_| ()), Err(LogikError::NoTrace));
+    data.start_recording();
+    data.press_component(constant).unwrap();
+    data.time_step();
+    data.add_component(Box::new(BusConstant { value: vec![On, Floating].into() }), vec![Some(2)]).unwrap();
+    data.stop_recording();
+    data.press_component(constant).unwrap();
+    
+    let trace = data.trace().unwrap();
+    assert_eq!(trace.entries().iter().map(|e| (e.time - start, e.tick, e.subnet)).collect::<Vec<_>>(), vec![
+        (1, 0, 1),
+        (3, 0, 3),
+        (4, 1, 2),
+    ]);
+    
+    assert_eq!(trace.to_vcd(), format!("$version logik $end\n\
+        $timescale 1ns $end\n\
+        $scope module circuit $end\n\
+        $var integer 64 ! tick $end\n\
+        $var wire 1 \" subnet_1 $end\n\
+        $var wire 2 # subnet_2 $end\n\
+        $var wire 1 $ subnet_3 $end\n\
+        $upscope $end\n\
+        $enddefinitions $end\n\
+        #{}\n\
+        $dumpvars\n\
+        b0 !\n\
+        0\"\n\
+        bzz #\n\
+        1$\n\
+        $end\n\
+        #{}\n\
+        1\"\n\
+        #{}\n\
+        b1 !\n\
+        0$\n\
+        #{}\n\
+        bz1 #\n", start, start + 1, start + 3, start + 4));
+}
+
+#[test]
+fn test_recording_width_change() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    // subnet 1 is replaced by a wider one while recording, and then by one as narrow as before
+    data.add_subnet(1).unwrap();
+    data.start_recording();
+    data.remove_subnet(1).unwrap();
+    data.add_subnet_with_width(1, 2).unwrap();
+    let bus = data.add_component(Box::new(BusConstant { value: vec![On, Off].into() }), vec![Some(1)]).unwrap();
+    data.remove_component(bus).unwrap();
+    data.remove_subnet(1).unwrap();
+    data.add_subnet(1).unwrap();
+    data.add_component(Box::new(Constant::with_state(true)), vec![Some(1)]).unwrap();
+    
+    let vcd = data.to_vcd().unwrap();
+    assert!(vcd.contains("$var wire 1 \" subnet_1 $end\n$var wire 2 # subnet_1_2 $end\n$upscope $end\n"));
+    assert!(vcd.contains("\nb01 #\n"));
+    assert!(vcd.ends_with("#2\nbzz #\nz\"\n#3\n1\"\n"));
+}
+
+#[test]
+fn test_clock_periods() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    
+    let fast = data.add_component(Box::new(Clock::new()), vec![Some(1)]).unwrap();
+    let slow = data.add_component(Box::new(Clock::new()), vec![Some(2)]).unwrap();
+    let and = data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(3)]).unwrap();
+    data.clock(fast);
+    data.clock(slow);
+    
+    assert_eq!(data.clock_period(fast), Ok(ClockPeriod { high: 1, low: 1, phase: 0 }));
+    assert_eq!(data.clock_period(and), Err(LogikError::NotAClock(and)));
+    assert_eq!(data.set_clock_period(slow, ClockPeriod { high: 0, low: 1, phase: 0 }), Err(LogikError::InvalidPeriod { high: 0, low: 1 }));
+    
+    data.set_clock_period(fast, ClockPeriod { high: 5, low: 5, phase: 0 }).unwrap();
+    data.set_clock_period(slow, ClockPeriod { high: 10, low: 10, phase: 5 }).unwrap();
+    assert_eq!(data.subnet_state(1), Ok(Off));
+    assert_eq!(data.subnet_state(2), Ok(On));
+    
+    let mut seen = Vec::new();
+    for _ in 0..6 {
+        let edge = data.next_clock_edge().unwrap();
+        // the edge happens late if the simulation already got past it, like it did while building
+        let happens = edge.max(data.time());
+        data.time_step();
+        // the clocks and then the gate drive their subnets after the edge
+        assert_eq!(data.time(), happens + 2);
+        seen.push((edge, data.subnet_state(1).unwrap(), data.subnet_state(2).unwrap(), data.subnet_state(3).unwrap()));
+    }
+    
+    assert_eq!(seen, vec![
+        (5, On, Off, Off),
+        (10, Off, Off, Off),
+        (15, On, On, On),
+        (20, Off, On, Off),
+        (25, On, Off, Off),
+        (30, Off, Off, Off),
+    ]);
+    
+    let netlist = data.save();
+    assert!(netlist.contains("component 2 Clock high=10 low=10 phase=5 state=0\n"));
+    assert_eq!(Data::load(&netlist).unwrap().clock_period(slow), Ok(ClockPeriod { high: 10, low: 10, phase: 5 }));
+}
+
+#[test]
+fn test_gate_inputs() {
+    let mut data = Data::new();
+    
+    for subnet in 1..=9 {
+        data.add_subnet(subnet).unwrap();
+    }
+    let inputs = (1..=5).map(|subnet| data.add_component(Box::new(Switch::new()), vec![Some(subnet)]).unwrap())
+        .collect::<Vec<_>>();
+    let linked = |output| vec![Some(1), Some(2), Some(3), Some(4), Some(5), Some(output)];
+    data.add_component(Box::new(AND::new(5)), linked(6)).unwrap();
+    data.add_component(Box::new(NOR::new(5)), linked(7)).unwrap();
+    data.add_component(Box::new(XOR::new(5, false)), linked(8)).unwrap();
+    data.add_component(ComponentId::Xor.build_with_params(&[5, 1]).unwrap(), linked(9)).unwrap();
+    
+    let outputs = |data: &Data| (6..=9).map(|subnet| data.subnet_state(subnet).unwrap()).collect::<Vec<_>>();
+    assert_eq!(outputs(&data), vec![SubnetState::Off, SubnetState::On, SubnetState::Off, SubnetState::Off]);
+    
+    data.press_component(inputs[0]).unwrap();
+    assert_eq!(outputs(&data), vec![SubnetState::Off, SubnetState::Off, SubnetState::On, SubnetState::On]);
+    
+    for input in &inputs[1..3] {
+        data.press_component(*input).unwrap();
+    }
+    assert_eq!(outputs(&data), vec![SubnetState::Off, SubnetState::Off, SubnetState::On, SubnetState::Off]);
+    
+    for input in &inputs[3..] {
+        data.press_component(*input).unwrap();
+    }
+    assert_eq!(outputs(&data), vec![SubnetState::On, SubnetState::Off, SubnetState::On, SubnetState::Off]);
+    
+    let netlist = data.save();
+    assert!(netlist.contains("component 6 And inputs=5\n"));
+    assert!(netlist.contains("component 9 Xor inputs=5 one_hot=1\n"));
+    assert_eq!(Data::load(&netlist).unwrap().save(), netlist);
+    
+    assert!(matches!(ComponentId::And.build_with_params(&[1]), Err(LogikError::InvalidParameter(_))));
+    assert!(matches!(ComponentId::And.build_with_params(&[4, 1]), Err(LogikError::InvalidParameter(_))));
+    assert!(matches!(ComponentId::Xor.build_with_params(&[4, 2]), Err(LogikError::InvalidParameter(_))));
+    assert!(matches!(ComponentId::Not.build_with_params(&[2]), Err(LogikError::InvalidParameter(_))));
+}
+
+#[test]
+fn test_parameters() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    for subnet in 1..=4 {
+        data.add_subnet(subnet).unwrap();
+    }
+    data.add_subnet_with_width(5, 4).unwrap();
+    data.add_component(Box::new(Constant::with_state(true)), vec![Some(1)]).unwrap();
+    let constant = data.add_component(Box::new(Constant::with_state(true)), vec![Some(2)]).unwrap();
+    let and = data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(3)]).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(On));
+    
+    let names = |data: &Data, id| data.parameters(id).unwrap().iter().map(|p| p.name).collect::<Vec<_>>();
+    assert_eq!(names(&data, and), vec!["inputs"]);
+    assert_eq!(data.parameter(and, "inputs").map(|p| p.value), Ok(ParameterValue::Int(2)));
+    assert_eq!(data.parameter(and, "width"), Err(LogikError::UnknownParameter(String::from("width"))));
+    assert!(matches!(data.set_parameter(and, "inputs", ParameterValue::Int(33)), Err(LogikError::InvalidParameter(_))));
+    assert!(matches!(data.set_parameter(and, "inputs", ParameterValue::Bool(true)), Err(LogikError::InvalidParameter(_))));
+    
+    // the old output turns into the third input, which nothing drives anymore
+    data.set_parameter(and, "inputs", ParameterValue::Int(3)).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(Floating));
+    data.link(and, 3, 4).unwrap();
+    assert_eq!(data.subnet_state(4), Ok(Error));
+    assert!(matches!(data.set_parameter(and, "inputs", ParameterValue::Int(2)), Err(LogikError::InvalidParameter(_))));
+    assert_eq!(data.parameter(and, "inputs").map(|p| p.value), Ok(ParameterValue::Int(3)));
+    
+    // a constant is as wide as its value
+    let wide = ParameterValue::Bits(vec![On, Off, On, On].into());
+    assert_eq!(data.set_parameter(constant, "value", wide.clone()), Err(LogikError::WidthMismatch { port_width: 4, subnet_width: 1 }));
+    assert_eq!(data.subnet_state(2), Ok(On));
+    data.unlink(constant, 0, 2).unwrap();
+    data.set_parameter(constant, "value", wide).unwrap();
+    data.link(constant, 0, 5).unwrap();
+    assert_eq!(data.subnet_value(5), Ok(vec![On, Off, On, On].into()));
+    
+    let clock = data.add_component(Box::new(Clock::new()), vec![None]).unwrap();
+    data.clock(clock);
+    assert_eq!(names(&data, clock), vec!["high", "low", "phase"]);
+    data.set_parameter(clock, "high", ParameterValue::Int(4)).unwrap();
+    assert_eq!(data.clock_period(clock), Ok(ClockPeriod { high: 4, low: 1, phase: 0 }));
+    assert!(matches!(data.set_parameter(clock, "low", ParameterValue::Int(0)), Err(LogikError::InvalidParameter(_))));
+    
+    let input = data.add_component(Box::new(InputGate::new()), vec![None]).unwrap();
+    data.set_parameter(input, "label", ParameterValue::String(String::from("carry_in"))).unwrap();
+    assert!(matches!(data.set_parameter(input, "label", ParameterValue::String(String::from("a b"))), Err(LogikError::InvalidParameter(_))));
+    
+    let netlist = data.save();
+    assert!(netlist.contains("component 2 Constant value=1101\n"));
+    assert!(netlist.contains("component 3 And inputs=3\n"));
+    assert!(netlist.contains("component 5 Input label=carry_in\n"));
+    assert_eq!(Data::load(&netlist).unwrap().save(), netlist);
+}
+
+#[test]
+fn test_memory() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    data.add_subnet_with_width(1, 4).unwrap();
+    data.add_subnet_with_width(2, 8).unwrap();
+    let address = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(address, "value", ParameterValue::Bits(Value::from_u64(3, 4))).unwrap();
+    data.link(address, 0, 1).unwrap();
+    let rom = data.add_component(ComponentId::ROM.build_with_params(&[4, 8]).unwrap(), vec![Some(1), Some(2)]).unwrap();
+    assert_eq!(data.subnet_value(2), Ok(Value::from_u64(0, 8)));
+    
+    data.load_memory(rom, b"v2.0 raw\n12 34 3*ff # comment\n7f\n", ImageFormat::HexText).unwrap();
+    assert_eq!(data.subnet_value(2), Ok(Value::from_u64(0xff, 8)));
+    assert_eq!(data.read_memory(rom, 5), Ok(0x7f));
+    assert_eq!(data.read_memory(rom, 6), Ok(0));
+    assert_eq!(data.read_memory(rom, 16), Err(LogikError::AddressOutOfRange(16)));
+    
+    data.write_memory(rom, 3, 0x42).unwrap();
+    assert_eq!(data.subnet_value(2), Ok(Value::from_u64(0x42, 8)));
+    assert_eq!(data.write_memory(rom, 3, 0x100), Err(LogikError::WordTooWide { word: 0x100, width: 8 }));
+    assert_eq!(data.write_memory(address, 0, 1), Err(LogikError::NotAMemory(address)));
+    assert!(matches!(data.load_memory(rom, b"17*0", ImageFormat::HexText), Err(LogikError::InvalidImage(_))));
+    assert!(matches!(data.load_memory(rom, b"16*1 1", ImageFormat::HexText), Err(LogikError::InvalidImage(_))));
+    assert!(matches!(data.load_memory(rom, b"1 18446744073709551615*1", ImageFormat::HexText), Err(LogikError::InvalidImage(_))));
+    assert_eq!(data.read_memory(rom, 3), Ok(0x42));
+    
+    data.load_memory(rom, b":020000040000FA\n:030002001234565F\n:00000001FF\n", ImageFormat::IntelHex).unwrap();
+    assert_eq!((0..6).map(|address| data.read_memory(rom, address).unwrap()).collect::<Vec<_>>(), vec![0, 0, 0x12, 0x34, 0x56, 0]);
+    assert!(matches!(data.load_memory(rom, b":030002001234565E\n", ImageFormat::IntelHex), Err(LogikError::InvalidImage(_))));
+    
+    // words that take more than one byte are stored least significant byte first
+    data.unlink(rom, 1, 2).unwrap();
+    data.set_parameter(rom, "data_width", ParameterValue::Int(12)).unwrap();
+    data.load_memory(rom, &[0x34, 0x12, 0x78, 0xf6], ImageFormat::Binary).unwrap();
+    assert_eq!(data.read_memory(rom, 0), Ok(0x234));
+    assert_eq!(data.read_memory(rom, 1), Ok(0x678));
+    
+    let netlist = data.save();
+    assert!(netlist.contains("component 2 ROM address_width=4 data_width=12 state=0:234;1:678\n"));
+    assert_eq!(Data::load(&netlist).unwrap().read_memory(rom, 1), Ok(0x678));
+    
+    // an image that runs past the end of a full memory is rejected, even the smallest one
+    let small = data.add_component(ComponentId::ROM.build_with_params(&[1]).unwrap(), vec![None, None]).unwrap();
+    assert!(matches!(data.load_memory(small, b"1 1 1", ImageFormat::HexText), Err(LogikError::InvalidImage(_))));
+    data.load_memory(small, b"1 1", ImageFormat::HexText).unwrap();
+    assert_eq!(data.read_memory(small, 1), Ok(1));
+    data.remove_component(small).unwrap();
+    
+    // a run can't go past the addresses of a wide memory either, and can only expand to so many words
+    let wide = data.add_component(ComponentId::ROM.build_with_params(&[32]).unwrap(), vec![None, None]).unwrap();
+    assert!(matches!(data.load_memory(wide, b"1 4294967296*0", ImageFormat::HexText), Err(LogikError::InvalidImage(_))));
+    assert!(matches!(data.load_memory(wide, b"4294967296*ff", ImageFormat::HexText), Err(LogikError::InvalidImage(_))));
+    data.load_memory(wide, b"4294967295*0 ff", ImageFormat::HexText).unwrap();
+    assert_eq!(data.read_memory(wide, 0xffff_ffff), Ok(0xff));
+    data.remove_component(wide).unwrap();
+    
+    // a RAM writes on the rising edge of its clock while writing is enabled
+    data.add_subnet_with_width(3, 8).unwrap();
+    data.add_subnet(4).unwrap();
+    data.add_subnet(5).unwrap();
+    data.add_subnet_with_width(6, 8).unwrap();
+    let word = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(word, "value", ParameterValue::Bits(Value::from_u64(0x5a, 8))).unwrap();
+    data.link(word, 0, 3).unwrap();
+    let enable = data.add_component(Box::new(Switch::new()), vec![Some(4)]).unwrap();
+    let clock = data.add_component(Box::new(Switch::new()), vec![Some(5)]).unwrap();
+    let ram = data.add_component(ComponentId::RAM.build_with_params(&[4]).unwrap(), vec![Some(1), Some(3), Some(4), Some(5), Some(6)]).unwrap();
+    
+    data.press_component(clock).unwrap();
+    assert_eq!(data.read_memory(ram, 3), Ok(0));
+    data.press_component(clock).unwrap();
+    data.press_component(enable).unwrap();
+    assert_eq!(data.subnet_value(6), Ok(Value::from_u64(0, 8)));
+    data.press_component(clock).unwrap();
+    assert_eq!(data.read_memory(ram, 3), Ok(0x5a));
+    assert_eq!(data.subnet_value(6), Ok(Value::from_u64(0x5a, 8)));
+    
+    // the word can't be read while the address isn't known
+    data.unlink(address, 0, 1).unwrap();
+    assert_eq!(data.subnet_value(6), Ok(vec![Error; 8].into()));
+}
+
+#[test]
+fn test_registers() {
+    let mut data = Data::new();
+    
+    data.add_subnet_with_width(1, 8).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_subnet_with_width(4, 8).unwrap();
+    let word = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(word, "value", ParameterValue::Bits(Value::from_u64(0x5a, 8))).unwrap();
+    data.link(word, 0, 1).unwrap();
+    let clock = data.add_component(Box::new(Switch::new()), vec![Some(2)]).unwrap();
+    let clear = data.add_component(Box::new(Switch::new()), vec![Some(3)]).unwrap();
+    let register = data.add_component(Box::new(Register::new()), vec![Some(1), Some(2), None, Some(3), Some(4)]).unwrap();
+    assert_eq!(data.stored_value(register), Ok(Value::from_u64(0, 8)));
+    
+    data.press_component(clock).unwrap();
+    assert_eq!(data.stored_value(register), Ok(Value::from_u64(0x5a, 8)));
+    assert_eq!(data.subnet_value(4), Ok(Value::from_u64(0x5a, 8)));
+    
+    // clearing happens right away
+    data.press_component(clear).unwrap();
+    assert_eq!(data.subnet_value(4), Ok(Value::from_u64(0, 8)));
+    assert_eq!(data.stored_value(word), Err(LogikError::NoStoredValue(word)));
+    
+    // counting down from 0 goes around to the maximum, which is where an up counter has its carry
+    data.add_subnet(5).unwrap();
+    data.add_subnet(6).unwrap();
+    data.add_subnet_with_width(7, 4).unwrap();
+    data.add_subnet(8).unwrap();
+    let count = data.add_component(Box::new(Switch::new()), vec![Some(5)]).unwrap();
+    let up = data.add_component(Box::new(Switch::new()), vec![Some(6)]).unwrap();
+    let counter = ComponentId::Counter.build_with_params(&[4, 9]).unwrap();
+    let counter = data.add_component(counter, vec![None, Some(5), None, None, Some(6), None, Some(7), Some(8)]).unwrap();
+    
+    data.press_component(count).unwrap();
+    assert_eq!(data.subnet_value(7), Ok(Value::from_u64(9, 4)));
+    assert_eq!(data.subnet_state(8), Ok(SubnetState::Off));
+    data.press_component(up).unwrap();
+    assert_eq!(data.subnet_state(8), Ok(SubnetState::On));
+    data.press_component(count).unwrap();
+    data.press_component(count).unwrap();
+    assert_eq!(data.stored_value(counter), Ok(Value::from_u64(0, 4)));
+    assert_eq!(data.subnet_state(8), Ok(SubnetState::Off));
+    
+    // a width that doesn't fit the linked output leaves the counter as it was
+    assert_eq!(data.set_parameter(counter, "width", ParameterValue::Int(6)), Err(LogikError::WidthMismatch { port_width: 6, subnet_width: 4 }));
+    assert_eq!(data.parameter(counter, "max").unwrap().value, ParameterValue::Int(9));
+    assert_eq!(data.stored_value(counter), Ok(Value::from_u64(0, 4)));
+    
+    // a maximum that is too big for the width is refused, a new width resets it
+    assert!(matches!(data.set_parameter(counter, "max", ParameterValue::Int(16)), Err(LogikError::InvalidParameter(_))));
+    data.unlink(counter, 6, 7).unwrap();
+    data.set_parameter(counter, "width", ParameterValue::Int(6)).unwrap();
+    assert_eq!(data.parameter(counter, "max").unwrap().value, ParameterValue::Int(63));
+    
+    // the bit shifted in from the right reaches the serial output after as many clocks as the
+    // register is long
+    data.add_subnet_with_width(9, 4).unwrap();
+    data.add_subnet(10).unwrap();
+    let shift = ComponentId::ShiftRegister.build_with_params(&[4]).unwrap();
+    let shift = data.add_component(shift, vec![None, Some(5), None, None, None, None, None, Some(9), Some(10)]).unwrap();
+    data.add_subnet(11).unwrap();
+    let one = data.add_component(Box::new(Constant::with_state(true)), vec![Some(11)]).unwrap();
+    data.link(shift, 0, 11).unwrap();
+    
+    for _ in 0..3 {
+        data.press_component(count).unwrap();
+        data.press_component(count).unwrap();
+    }
+    assert_eq!(data.subnet_value(9), Ok(Value::from_u64(0b0111, 4)));
+    assert_eq!(data.subnet_state(10), Ok(SubnetState::Off));
+    data.press_component(count).unwrap();
+    data.press_component(count).unwrap();
+    assert_eq!(data.subnet_value(9), Ok(Value::from_u64(0b1111, 4)));
+    assert_eq!(data.subnet_state(10), Ok(SubnetState::On));
+    data.remove_component(one).unwrap();
+    
+    let netlist = data.save();
+    assert!(netlist.contains(&format!("component {} Register state=0\n", register)));
+    assert!(netlist.contains(&format!("component {} Counter width=6 max=63 state=4\n", counter)));
+    assert!(netlist.contains(&format!("component {} ShiftRegister length=4 state=f\n", shift)));
+    let loaded = Data::load(&netlist).unwrap();
+    assert_eq!(loaded.stored_value(shift), Ok(Value::from_u64(0b1111, 4)));
+    assert_eq!(loaded.save(), netlist);
+    
+    // a counter can't be 64 bits wide since its max wouldn't fit into the parameter, the widest
+    // one counts through every value and can be loaded again
+    let mut data = Data::new();
+    let wide = data.add_component(ComponentId::Counter.build().unwrap(), vec![None; 8]).unwrap();
+    assert!(matches!(data.set_parameter(wide, "width", ParameterValue::Int(64)), Err(LogikError::InvalidParameter(_))));
+    assert!(matches!(ComponentId::Counter.build_with_params(&[64]), Err(LogikError::InvalidParameter(_))));
+    data.set_parameter(wide, "width", ParameterValue::Int(63)).unwrap();
+    assert_eq!(data.parameter(wide, "max").map(|p| p.value), Ok(ParameterValue::Int(i64::MAX)));
+    let netlist = data.save();
+    assert!(netlist.contains(&format!("component {} Counter width=63 max=9223372036854775807 state=0\n", wide)));
+    assert_eq!(Data::load(&netlist).unwrap().save(), netlist);
+}
+
+#[test]
+fn test_plexers() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    // a multiplexer with four data ports of two bits and an enable port
+    for subnet in 1..=4 {
+        data.add_subnet_with_width(subnet, 2).unwrap();
+        let constant = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+        data.set_parameter(constant, "value", ParameterValue::Bits(Value::from_u64(subnet as u64 - 1, 2))).unwrap();
+        data.link(constant, 0, subnet).unwrap();
+    }
+    data.add_subnet_with_width(5, 2).unwrap();
+    data.add_subnet_with_width(6, 2).unwrap();
+    data.add_subnet(7).unwrap();
+    let select = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(select, "value", ParameterValue::Bits(Value::from_u64(2, 2))).unwrap();
+    data.link(select, 0, 5).unwrap();
+    let enable = data.add_component(Box::new(Switch::new()), vec![Some(7)]).unwrap();
+    let mux = ComponentId::Mux.build_with_params(&[2, 2, 1]).unwrap();
+    data.add_component(mux, vec![Some(1), Some(2), Some(3), Some(4), Some(5), Some(6), Some(7)]).unwrap();
+    assert_eq!(data.subnet_value(6), Ok(vec![Floating; 2].into()));
+    data.press_component(enable).unwrap();
+    assert_eq!(data.subnet_value(6), Ok(Value::from_u64(2, 2)));
+    
+    // a select port that isn't driven picks nothing, an error on it is passed on
+    data.unlink(select, 0, 5).unwrap();
+    assert_eq!(data.subnet_value(6), Ok(vec![Floating; 2].into()));
+    let conflict = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(conflict, "value", ParameterValue::Bits(Value::from_u64(1, 2))).unwrap();
+    data.link(select, 0, 5).unwrap();
+    data.link(conflict, 0, 5).unwrap();
+    assert_eq!(data.subnet_value(6), Ok(vec![Error; 2].into()));
+    data.unlink(conflict, 0, 5).unwrap();
+    
+    // the decoder turns on the output the select port picks
+    data.add_subnet(8).unwrap();
+    data.add_subnet(9).unwrap();
+    let decoder = ComponentId::Decoder.build_with_params(&[2]).unwrap();
+    data.add_component(decoder, vec![Some(5), None, None, Some(8), Some(9)]).unwrap();
+    assert_eq!(data.subnet_value(8), Ok(On.into()));
+    assert_eq!(data.subnet_value(9), Ok(Off.into()));
+    
+    // the demultiplexer passes its data on to the output the select port picks
+    data.add_subnet_with_width(10, 2).unwrap();
+    data.add_subnet_with_width(11, 2).unwrap();
+    let demux = ComponentId::Demux.build_with_params(&[2, 2]).unwrap();
+    data.add_component(demux, vec![Some(4), Some(5), None, Some(10), Some(11), None]).unwrap();
+    assert_eq!(data.subnet_value(10), Ok(Value::from_u64(0, 2)));
+    assert_eq!(data.subnet_value(11), Ok(Value::from_u64(3, 2)));
+    
+    // the priority encoder picks the highest input that is on and ignores floating ones
+    data.add_subnet_with_width(12, 2).unwrap();
+    data.add_subnet(13).unwrap();
+    let encoder = ComponentId::PriorityEncoder.build_with_params(&[2]).unwrap();
+    let encoder = data.add_component(encoder, vec![Some(9), Some(8), None, Some(9), Some(12), Some(13)]).unwrap();
+    assert_eq!(data.subnet_value(12), Ok(Value::from_u64(1, 2)));
+    assert_eq!(data.subnet_value(13), Ok(On.into()));
+    data.unlink(encoder, 1, 8).unwrap();
+    assert_eq!(data.subnet_value(12), Ok(vec![Floating; 2].into()));
+    assert_eq!(data.subnet_value(13), Ok(Off.into()));
+    
+    // turned off it has no valid number, and an enable port that isn't driven makes both floating
+    data.link(encoder, 1, 8).unwrap();
+    data.set_parameter(encoder, "enable", ParameterValue::Bool(true)).unwrap();
+    data.add_subnet(14).unwrap();
+    let enable = data.add_component(Box::new(Switch::new()), vec![Some(14)]).unwrap();
+    data.link(encoder, 6, 14).unwrap();
+    assert_eq!(data.subnet_value(12), Ok(vec![Floating; 2].into()));
+    assert_eq!(data.subnet_value(13), Ok(Off.into()));
+    data.press_component(enable).unwrap();
+    assert_eq!(data.subnet_value(12), Ok(Value::from_u64(1, 2)));
+    assert_eq!(data.subnet_value(13), Ok(On.into()));
+    data.unlink(enable, 0, 14).unwrap();
+    assert_eq!(data.subnet_value(12), Ok(vec![Floating; 2].into()));
+    assert_eq!(data.subnet_value(13), Ok(Floating.into()));
+    
+    let netlist = data.save();
+    assert!(netlist.contains(" Mux select=2 width=2 enable=1\n"));
+    assert_eq!(Data::load(&netlist).unwrap().save(), netlist);
+}
+
+#[test]
+fn test_arithmetic() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    // subnets 1 and 2 carry the numbers 200 and 100, 3 is left floating
+    for (subnet, number) in [(1, 200), (2, 100)].iter() {
+        data.add_subnet_with_width(*subnet, 8).unwrap();
+        let constant = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+        data.set_parameter(constant, "value", ParameterValue::Bits(Value::from_u64(*number, 8))).unwrap();
+        data.link(constant, 0, *subnet).unwrap();
+    }
+    data.add_subnet_with_width(3, 8).unwrap();
+    for subnet in 4..=13 {
+        data.add_subnet_with_width(subnet, if subnet % 2 == 0 { 8 } else { 1 }).unwrap();
+    }
+    
+    data.add_component(ComponentId::Adder.build().unwrap(), vec![Some(1), Some(2), None, Some(4), Some(5)]).unwrap();
+    assert_eq!(data.subnet_value(4), Ok(Value::from_u64(44, 8)));
+    assert_eq!(data.subnet_value(5), Ok(On.into()));
+    
+    data.add_component(ComponentId::Subtractor.build().unwrap(), vec![Some(2), Some(1), None, Some(6), Some(7)]).unwrap();
+    assert_eq!(data.subnet_value(6), Ok(Value::from_u64(156, 8)));
+    assert_eq!(data.subnet_value(7), Ok(On.into()));
+    
+    data.add_subnet_with_width(14, 8).unwrap();
+    data.add_component(ComponentId::Multiplier.build().unwrap(), vec![Some(1), Some(2), None, Some(8), Some(14)]).unwrap();
+    assert_eq!(data.subnet_value(8), Ok(Value::from_u64(20000 & 0xff, 8)));
+    assert_eq!(data.subnet_value(14), Ok(Value::from_u64(20000 >> 8, 8)));
+    
+    data.add_subnet_with_width(15, 8).unwrap();
+    let divider = data.add_component(ComponentId::Divider.build().unwrap(), vec![Some(1), Some(2), Some(10), Some(15)]).unwrap();
+    assert_eq!(data.subnet_value(10), Ok(Value::from_u64(2, 8)));
+    assert_eq!(data.subnet_value(15), Ok(Value::from_u64(0, 8)));
+    
+    // an operand that isn't known makes every output an error, and so does dividing by 0
+    data.unlink(divider, 1, 2).unwrap();
+    data.link(divider, 1, 3).unwrap();
+    assert_eq!(data.subnet_value(10), Ok(Value::error(8)));
+    
+    // 200 is -56 as a signed byte
+    let comparator = data.add_component(ComponentId::Comparator.build().unwrap(), vec![Some(1), Some(2), Some(9), Some(11), Some(13)]).unwrap();
+    assert_eq!([9, 11, 13].iter().map(|subnet| data.subnet_state(*subnet).unwrap()).collect::<Vec<_>>(), vec![Off, Off, On]);
+    data.set_parameter(comparator, "signed", ParameterValue::Bool(true)).unwrap();
+    assert_eq!([9, 11, 13].iter().map(|subnet| data.subnet_state(*subnet).unwrap()).collect::<Vec<_>>(), vec![On, Off, Off]);
+    data.unlink(comparator, 1, 2).unwrap();
+    data.link(comparator, 1, 3).unwrap();
+    assert_eq!(data.subnet_state(11), Ok(Error));
+}
+
+#[test]
+fn test_splitter() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    data.add_subnet_with_width(1, 4).unwrap();
+    data.add_subnet_with_width(2, 2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_subnet(4).unwrap();
+    let splitter = data.add_component(ComponentId::Splitter.build_with_params(&[4, 3]).unwrap(), vec![None; 4]).unwrap();
+    data.set_parameter(splitter, "bits", ParameterValue::String(String::from("0,0,1,2"))).unwrap();
+    for (port, subnet) in (0..4).zip(1..=4) {
+        data.link(splitter, port, subnet).unwrap();
+    }
+    
+    // a bus is split into its branches
+    let bus = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(bus, "value", ParameterValue::Bits(Value::from_u64(0b1011, 4))).unwrap();
+    data.link(bus, 0, 1).unwrap();
+    assert_eq!(data.subnet_value(2), Ok(Value::from_u64(0b11, 2)));
+    assert_eq!(data.subnet_value(3), Ok(Off.into()));
+    assert_eq!(data.subnet_value(4), Ok(On.into()));
+    
+    // and the branches are joined into a bus, without the splitter holding on to the old value
+    data.remove_component(bus).unwrap();
+    assert_eq!(data.subnet_value(1), Ok(Value::floating(4)));
+    assert_eq!(data.subnet_value(2), Ok(Value::floating(2)));
+    let low = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(low, "value", ParameterValue::Bits(Value::from_u64(0b01, 2))).unwrap();
+    data.link(low, 0, 2).unwrap();
+    data.add_component(Box::new(Constant::with_state(true)), vec![Some(3)]).unwrap();
+    assert_eq!(data.subnet_value(1), Ok(vec![On, Off, On, Floating].into()));
+    
+    // driving a bit from both sides is a conflict on both of them
+    let bus = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(bus, "value", ParameterValue::Bits(Value::from_u64(0b1100, 4))).unwrap();
+    data.link(bus, 0, 1).unwrap();
+    assert_eq!(data.subnet_value(1), Ok(vec![Error, Off, On, On].into()));
+    assert_eq!(data.subnet_value(2), Ok(vec![Error, Off].into()));
+    assert_eq!(data.subnet_value(4), Ok(On.into()));
+    
+    assert!(matches!(data.set_parameter(splitter, "bits", ParameterValue::String(String::from("0,0,1"))), Err(LogikError::InvalidParameter(_))));
+    assert!(matches!(data.set_parameter(splitter, "bits", ParameterValue::String(String::from("0,0,1,1"))), Err(LogikError::InvalidParameter(_))));
+    assert!(data.set_parameter(splitter, "fan_out", ParameterValue::Int(2)).is_err());
+    assert_eq!(data.parameter(splitter, "bits").unwrap().value, ParameterValue::String(String::from("0,0,1,2")));
+    
+    let netlist = data.save();
+    assert!(netlist.contains("component 1 Splitter width=4 fan_out=3 bits=0,0,1,2\n"));
+    assert_eq!(Data::load(&netlist).unwrap().subnet_value(1), Ok(vec![Error, Off, On, On].into()));
+}
+
+#[test]
+fn test_pull_resistors() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    // a bus shared by two tri-state buffers that idles high
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_subnet(4).unwrap();
+    data.add_component(Box::new(Constant::with_state(false)), vec![Some(1)]).unwrap();
+    let enable = data.add_component(Box::new(Switch::new()), vec![Some(2)]).unwrap();
+    data.add_component(Box::new(TriBuffer {}), vec![Some(1), Some(2), Some(3)]).unwrap();
+    data.add_component(Box::new(TriInverter {}), vec![Some(1), None, Some(3)]).unwrap();
+    let pull_up = data.add_component(ComponentId::PullUp.build().unwrap(), vec![Some(3)]).unwrap();
+    data.add_component(Box::new(NOT {}), vec![Some(3), Some(4)]).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(On));
+    assert_eq!(data.subnet_state(4), Ok(Off));
+    
+    // any strong driver wins over the pull
+    data.press_component(enable).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(Off));
+    assert_eq!(data.subnet_state(4), Ok(On));
+    data.press_component(enable).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(On));
+    
+    // two pulls that disagree collide like any other drivers
+    let pull_down = data.add_component(ComponentId::PullDown.build().unwrap(), vec![Some(3)]).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(Error));
+    data.remove_component(pull_up).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(Off));
+    
+    data.add_subnet_with_width(5, 4).unwrap();
+    data.set_parameter(pull_down, "width", ParameterValue::Int(4)).unwrap_err();
+    data.unlink(pull_down, 0, 3).unwrap();
+    data.set_parameter(pull_down, "width", ParameterValue::Int(4)).unwrap();
+    data.link(pull_down, 0, 5).unwrap();
+    assert_eq!(data.subnet_value(5), Ok(Value::from_u64(0, 4)));
+}
+
+#[test]
+fn test_pull_resistors_across() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    // a pull up on a branch of a splitter reaches the bus, next to a branch that is driven
+    data.add_subnet_with_width(1, 2).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_component(ComponentId::Splitter.build().unwrap(), vec![Some(1), Some(2), Some(3)]).unwrap();
+    data.add_component(ComponentId::PullUp.build().unwrap(), vec![Some(2)]).unwrap();
+    let driver = data.add_component(Box::new(Constant::with_state(false)), vec![Some(3)]).unwrap();
+    assert_eq!(data.subnet_value(1), Ok(vec![On, Off].into()));
+    
+    // a strong driver on the bus wins over the pull on the branch
+    let bus = data.add_component(Box::new(Constant::new()), vec![None]).unwrap();
+    data.set_parameter(bus, "value", ParameterValue::Bits(Value::from_u64(0b00, 2))).unwrap();
+    data.link(bus, 0, 1).unwrap();
+    assert_eq!(data.subnet_state(2), Ok(Off));
+    data.remove_component(bus).unwrap();
+    data.remove_component(driver).unwrap();
+    assert_eq!(data.subnet_value(1), Ok(vec![On, Floating].into()));
+    
+    // a pull down reaches the other side of a transmission gate while it is on
+    data.add_subnet(4).unwrap();
+    data.add_subnet(5).unwrap();
+    let enable = data.add_component(Box::new(Switch::new()), vec![Some(5)]).unwrap();
+    data.add_component(ComponentId::TransmissionGate.build().unwrap(), vec![Some(3), Some(4), Some(5)]).unwrap();
+    data.add_component(ComponentId::PullDown.build().unwrap(), vec![Some(4)]).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(Floating));
+    data.press_component(enable).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(Off));
+    assert_eq!(data.subnet_value(1), Ok(vec![On, Off].into()));
+}
+
+#[test]
+fn test_transmission_gates() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    // subnets 1, 2 and 3 are connected in a row by two gates, the second one is always on
+    for subnet in 1..=5 {
+        data.add_subnet(subnet).unwrap();
+    }
+    let enable = data.add_component(Box::new(Switch::new()), vec![Some(4)]).unwrap();
+    data.add_component(Box::new(Constant::with_state(true)), vec![Some(5)]).unwrap();
+    data.add_component(ComponentId::TransmissionGate.build().unwrap(), vec![Some(1), Some(2), Some(4)]).unwrap();
+    let second = data.add_component(ComponentId::TransmissionGate.build().unwrap(), vec![Some(2), Some(3), Some(5)]).unwrap();
+    let driver = data.add_component(Box::new(Constant::with_state(true)), vec![Some(1)]).unwrap();
+    assert_eq!(data.subnet_state(2), Ok(Floating));
+    
+    data.press_component(enable).unwrap();
+    assert_eq!(data.subnet_state(2), Ok(On));
+    assert_eq!(data.subnet_state(3), Ok(On));
+    
+    // values pass the other way just as well and don't stay behind once their driver is gone
+    data.remove_component(driver).unwrap();
+    assert_eq!(data.subnet_state(1), Ok(Floating));
+    assert_eq!(data.subnet_state(3), Ok(Floating));
+    data.add_component(Box::new(Constant::with_state(false)), vec![Some(3)]).unwrap();
+    assert_eq!(data.subnet_state(1), Ok(Off));
+    
+    data.press_component(enable).unwrap();
+    assert_eq!(data.subnet_state(1), Ok(Floating));
+    assert_eq!(data.subnet_state(2), Ok(Off));
+    
+    // a gate that might or might not be on passes errors
+    data.unlink(second, 2, 5).unwrap();
+    assert_eq!(data.subnet_state(2), Ok(Error));
+    assert_eq!(data.subnet_state(3), Ok(Off));
+}
+
+#[test]
+fn test_parallel_transmission_gates() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    
+    // subnets 1 and 2 are connected by two gates next to each other, which makes them one net
+    for subnet in 1..=3 {
+        data.add_subnet(subnet).unwrap();
+    }
+    data.add_component(Box::new(Constant::with_state(true)), vec![Some(3)]).unwrap();
+    data.add_component(ComponentId::TransmissionGate.build().unwrap(), vec![Some(1), Some(2), Some(3)]).unwrap();
+    data.add_component(ComponentId::TransmissionGate.build().unwrap(), vec![Some(2), Some(1), Some(3)]).unwrap();
+    let driver = data.add_component(Box::new(Switch::new()), vec![Some(1)]).unwrap();
+    assert_eq!(data.subnet_state(1), Ok(Off));
+    assert_eq!(data.subnet_state(2), Ok(Off));
+    
+    // the net follows its driver
+    data.press_component(driver).unwrap();
+    assert_eq!(data.subnet_state(1), Ok(On));
+    assert_eq!(data.subnet_state(2), Ok(On));
+    
+    // and lets go once it is gone
+    data.remove_component(driver).unwrap();
+    assert_eq!(data.subnet_state(1), Ok(Floating));
+    assert_eq!(data.subnet_state(2), Ok(Floating));
+    assert!(data.oscillating_subnets().is_empty());
+    
+    // a driver on the other side takes over
+    data.add_component(Box::new(Constant::with_state(true)), vec![Some(2)]).unwrap();
+    assert_eq!(data.subnet_state(1), Ok(On));
+}
+
+#[test]
+fn test_verilog_export() {
+    let mut data = Data::new();
+    data.define_subcircuit("half_adder", &half_adder()).unwrap();
+    
+    for subnet in 1..=7 {
+        data.add_subnet(subnet).unwrap();
+    }
+    data.add_subnet_with_width(8, 4).unwrap();
+    let a = data.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    data.set_parameter(a, "label", ParameterValue::String(String::from("a"))).unwrap();
+    data.add_component(Box::new(InputGate::new()), vec![Some(2)]).unwrap();
+    let first = data.add_subcircuit("half_adder").unwrap();
+    let second = data.add_subcircuit("half_adder").unwrap();
+    for (port, subnet) in [1, 2, 3, 4].iter().enumerate() {
+        data.link(first, port, *subnet).unwrap();
+    }
+    for (port, subnet) in [3, 4, 5, 6].iter().enumerate() {
+        data.link(second, port, *subnet).unwrap();
+    }
+    data.add_component(Box::new(Clock::new()), vec![Some(7)]).unwrap();
+    let flip_flop = data.add_component(Box::new(DFlipFlop::new()), vec![Some(5), Some(7), Some(6), None, None]).unwrap();
+    data.press_component(flip_flop).unwrap();
+    let register = data.add_component(ComponentId::Register.build().unwrap(), vec![None; 5]).unwrap();
+    data.set_parameter(register, "width", ParameterValue::Int(4)).unwrap();
+    data.link(register, 1, 7).unwrap();
+    data.link(register, 4, 8).unwrap();
+    let out = data.add_component(Box::new(OutputGate::new()), vec![None]).unwrap();
+    data.set_parameter(out, "width", ParameterValue::Int(4)).unwrap();
+    data.link(out, 0, 8).unwrap();
+    
+    let verilog = data.to_verilog("top").unwrap();
+    
+    // both instances share the module of the subcircuit, which comes first
+    assert_eq!(verilog.matches("module half_adder").count(), 1);
+    assert!(verilog.find("module half_adder").unwrap() < verilog.find("module top").unwrap());
+    assert!(verilog.contains("    xor c5 (s3, s1, s2);\n    and c6 (s4, s1, s2);\n"));
+    assert!(verilog.contains("half_adder c4 (.in_1(s3), .in_2(s4), .out_3(s5), .out_4(s6));"));
+    
+    // pins keep their labels and the clock is driven from outside
+    assert!(verilog.contains("module top (\n    input a,\n    input in_2,\n    output [3:0] out_8,\n    input clock5\n);"));
+    assert!(verilog.contains("    wire [3:0] s8;\n"));
+    assert!(verilog.contains("    assign out_8 = s8;\n"));
+    
+    // registers start with what they hold, unlinked ports are left out of the updates
+    assert!(verilog.contains("    reg q6 = 1'd1;\n"));
+    assert!(verilog.contains("    always @(posedge s7)\n        if (!s6) q6 <= s5;\n"));
+    assert!(verilog.contains("    always @(posedge s7)\n        q7 <= 4'bz;\n"));
+    
+    assert_eq!(data.to_verilog("a top"), Err(LogikError::InvalidName(String::from("a top"))));
+}
+
+#[test]
+fn test_verilog_import() {
+    use SubnetState::*;
+    
+    let full_adder = "
+        `timescale 1ns / 1ps
+        // a full adder out of two half adders
+        module half_adder(input a, input b, output sum, output carry);
+            xor (sum, a, b);
+            and g1 (carry, a, b);
+        endmodule
+        
+        module full_adder(a, b, cin, sum, cout);
+            input a, b, cin;
+            output sum, cout;
+            wire s, c1, c2;
+            half_adder first (.a(a), .b(b), .sum(s), .carry(c1));
+            half_adder second (s, cin, sum, c2);
+            assign cout = c1 | c2;
+        endmodule
+    ";
+    let mut data = Data::from_verilog(full_adder).unwrap();
+    for inputs in 0..8u64 {
+        for (pin, input) in (1..=3).enumerate() {
+            data.drive_input(input, Value::from_u64(inputs >> pin & 1, 1));
+        }
+        let sum = (inputs & 1) + (inputs >> 1 & 1) + (inputs >> 2);
+        assert_eq!(data.input_value(4, 0), Value::from_u64(sum & 1, 1));
+        assert_eq!(data.input_value(5, 0), Value::from_u64(sum >> 1, 1));
+    }
+    
+    // vectors are split into bits and registers start with their initial value
+    let rotator = "
+        module rotator(input clk, input en, input [3:0] mask, output [3:0] q, output [3:0] masked);
+            reg [3:0] q = 4'd5;
+            always @(posedge clk)
+                if (en) q <= {q[2:0], q[3]};
+            assign masked = q & ~mask;
+        endmodule
+    ";
+    for round_trip in [false, true].iter() {
+        let mut data = Data::from_verilog(rotator).unwrap();
+        if *round_trip {
+            data = Data::from_verilog(&data.to_verilog("rotator").unwrap()).unwrap();
+        }
+        data.drive_input(1, Off.into());
+        data.drive_input(2, On.into());
+        data.drive_input(3, Value::from_u64(0b0011, 4));
+        assert_eq!(data.input_value(4, 0), Value::from_u64(0b0101, 4));
+        assert_eq!(data.input_value(5, 0), Value::from_u64(0b0100, 4));
+        
+        data.drive_input(1, On.into());
+        assert_eq!(data.input_value(4, 0), Value::from_u64(0b1010, 4));
+        data.drive_input(1, Off.into());
+        data.drive_input(2, Off.into());
+        data.drive_input(1, On.into());
+        assert_eq!(data.input_value(4, 0), Value::from_u64(0b1010, 4));
+        assert_eq!(data.input_value(5, 0), Value::from_u64(0b1000, 4));
+    }
+    
+    let invalid = |line, reason: &str| Some(LogikError::InvalidVerilog { line, reason: String::from(reason) });
+    assert_eq!(Data::from_verilog("module m(input a);\nassign b = a;\nendmodule").err(), invalid(2, "b is not declared"));
+    assert_eq!(Data::from_verilog("module m(input [0:3] a);\nendmodule").err(), invalid(1, "the range [0:3] is ascending, only descending ranges are supported"));
+    assert_eq!(Data::from_verilog("module m(output a);\nassign a = 1'bx;\nendmodule").err(), invalid(2, "unknown bits can't be imported, only 0, 1 and z"));
+    assert_eq!(Data::from_verilog("module m(output a);\nmissing i (a);\nendmodule").err(), invalid(2, "there is no module called missing"));
+    assert_eq!(Data::from_verilog("module m(output [1:0] a);\nassign a = 3'b0;\nendmodule").err(), invalid(2, "expected 2 bits but the value has 3"));
+}
+
+#[test]
+fn test_verilog_import_errors() {
+    let invalid = |line, reason: &str| Some(LogikError::InvalidVerilog { line, reason: String::from(reason) });
+    let import = |verilog: &str| Data::from_verilog(verilog).err();
+    
+    // constructs outside of the structural subset
+    assert_eq!(import("module m(inout a);\nendmodule"), invalid(1, "inout ports aren't supported"));
+    assert_eq!(import("module m(input a, output b);\nand #2 (b, a, a);\nendmodule"), invalid(2, "delays aren't supported"));
+    assert_eq!(import("module m(input a);\nparameter W = 1;\nendmodule"), invalid(2, "'parameter' is not supported"));
+    assert_eq!(
+        import("module m(input c, output reg q);\nalways @(negedge c)\n    q <= 1'b0;\nendmodule"),
+        invalid(2, "only registers that change on the rising edge are supported")
+    );
+    assert_eq!(
+        import("module m(input c, input e, output reg q);\nalways @(posedge c)\n    if (e) q <= 1'b1;\n    else q <= 1'b0;\nendmodule"),
+        invalid(4, "else branches aren't supported")
+    );
+    
+    // malformed syntax is reported where it is found
+    assert_eq!(import("module m(input a)\nwire b;\nendmodule"), invalid(2, "expected ';' but found 'wire'"));
+    assert_eq!(import("module m(input a);\nassign = a;\nendmodule"), invalid(2, "expected an expression but found '='"));
+    assert_eq!(import("module m(input a, output b);\nassign b = a;\ninitial $display(a);\nendmodule"), invalid(3, "unexpected '$'"));
+    assert_eq!(import("module m(input a);\n/* never closed\nendmodule"), invalid(2, "the comment is never closed"));
+    assert_eq!(
+        import("module m(input a, output b);\nassign b = a;\n"),
+        invalid(2, "expected a declaration, assignment or instance but the file ended")
+    );
+    assert_eq!(import("module m(input a, output b);\nnot (b);\nendmodule"), invalid(2, "not needs an output and an input"));
+    
+    // widths have to match exactly, nothing is extended or truncated
+    assert_eq!(import("module m(input [1:0] a, output b);\nassign b = a;\nendmodule"), invalid(2, "expected 1 bits but the value has 2"));
+    assert_eq!(
+        import("module m(input [1:0] a, input [2:0] d, output [1:0] c);\nassign c = a & d;\nendmodule"),
+        invalid(2, "expected 2 bits but the value has 3")
+    );
+    assert_eq!(
+        import("module inner(input [1:0] x);\nendmodule\nmodule m(input a);\ninner i (.x(a));\nendmodule"),
+        invalid(4, "expected 2 bits but the value has 1")
+    );
+    assert_eq!(import("module m(input [1:0] a, output b);\nassign b = a[2];\nendmodule"), invalid(2, "a[2:2] is out of range"));
+    
+    // every net has to be declared once before it is used
+    assert_eq!(import("module m(input a, output y);\nand (y, a, z);\nendmodule"), invalid(2, "z is not declared"));
+    assert_eq!(
+        import("module inner(input x);\nendmodule\nmodule m(input a);\ninner i (.x(missing));\nendmodule"),
+        invalid(4, "missing is not declared")
+    );
+    assert_eq!(import("module m(input c, output reg q);\nalways @(posedge c)\n    r <= 1'b1;\nendmodule"), invalid(3, "r is not declared"));
+    assert_eq!(import("module m(input a, output b);\nwire w;\nwire w;\nendmodule"), invalid(3, "w is declared twice"));
+}
+
+#[test]
+fn test_logisim_import() {
+    use SubnetState::*;
+    
+    let circuit = r##"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+        <project source="2.7.1" version="1.0">
+          <!-- the libraries are numbered by the file -->
+          <lib desc="#Wiring" name="0"/>
+          <lib desc="#Gates" name="1"/>
+          <lib desc="#Memory" name="4"/>
+          <lib desc="#Base" name="6"/>
+          <main name="main"/>
+          <circuit name="main">
+            <a name="circuit" val="main"/>
+            <wire from="(100,100)" to="(150,100)"/>
+            <wire from="(100,140)" to="(150,140)"/>
+            <wire from="(200,120)" to="(260,120)"/>
+            <wire from="(420,120)" to="(460,120)"/>
+            <wire from="(500,100)" to="(540,100)"/>
+            <comp lib="0" loc="(100,100)" name="Pin"><a name="label" val="a"/></comp>
+            <comp lib="0" loc="(100,140)" name="Pin"><a name="label" val="b"/></comp>
+            <comp lib="1" loc="(200,120)" name="AND Gate"><a name="inputs" val="2"/></comp>
+            <comp lib="0" loc="(260,120)" name="Pin">
+              <a name="output" val="true"/>
+              <a name="label" val="a &amp; b"/>
+            </comp>
+            <comp lib="0" loc="(100,100)" name="Tunnel"><a name="label" val="a"/></comp>
+            <comp lib="0" loc="(100,140)" name="Tunnel"><a name="label" val="b"/></comp>
+            <comp lib="1" loc="(300,300)" name="XOR Gate">
+              <a name="facing" val="south"/>
+              <a name="inputs" val="2"/>
+              <a name="negate1" val="true"/>
+            </comp>
+            <comp lib="0" loc="(320,240)" name="Tunnel"><a name="label" val="a"/></comp>
+            <comp lib="0" loc="(280,240)" name="Tunnel"><a name="label" val="b"/></comp>
+            <comp lib="0" loc="(300,300)" name="Pin"><a name="output" val="true"/></comp>
+            <comp lib="4" loc="(500,100)" name="D Flip-Flop"/>
+            <comp lib="0" loc="(460,100)" name="Tunnel"><a name="label" val="a"/></comp>
+            <comp lib="0" loc="(420,120)" name="Clock"/>
+            <comp lib="0" loc="(540,100)" name="Pin"><a name="output" val="true"/></comp>
+            <comp lib="6" loc="(100,60)" name="Text"><a name="text" val="a lab circuit"/></comp>
+          </circuit>
+        </project>
+    "##;
+    let mut data = Data::from_logisim(circuit).unwrap();
+    assert_eq!(data.parameter(4, "label").unwrap().value, ParameterValue::String(String::from("a_&_b")));
+    assert_eq!(data.input_value(9, 0), Off.into());
+    for inputs in 0..4u64 {
+        let (a, b) = (inputs & 1, inputs >> 1);
+        data.drive_input(1, Value::from_u64(a, 1));
+        data.drive_input(2, Value::from_u64(b, 1));
+        assert_eq!(data.input_value(4, 0), Value::from_u64(a & b, 1));
+        assert_eq!(data.input_value(6, 0), Value::from_u64(a ^ b ^ 1, 1));
+    }
+    
+    // the flip-flop stores a on the first rising edge of the clock
+    data.time_step();
+    assert_eq!(data.input_value(9, 0), On.into());
+    
+    let invalid = |reason: &str| Some(LogikError::InvalidLogisim(String::from(reason)));
+    let unsupported = r##"<project>
+          <lib desc="#Wiring" name="0"/>
+          <lib desc="#Gates" name="1"/>
+          <lib desc="#Memory" name="4"/>
+          <circuit name="main">
+            <wire from="(190,130)" to="(190,150)"/>
+            <comp lib="4" loc="(10,20)" name="RAM"/>
+            <comp lib="1" loc="(50,60)" name="AND Gate"><a name="width" val="4"/></comp>
+            <comp loc="(70,80)" name="half adder"/>
+            <comp lib="4" loc="(200,100)" name="D Flip-Flop"/>
+          </circuit>
+        </project>"##;
+    assert_eq!(Data::from_logisim(unsupported).err(), invalid("unsupported components: RAM at (10,20), 4 bit AND Gate at (50,60), subcircuit half adder at (70,80), D Flip-Flop with a reset at (200,100)"));
+    let widths = r##"<project>
+          <lib desc="#Wiring" name="0"/>
+          <lib desc="#I/O" name="5"/>
+          <circuit name="main">
+            <wire from="(0,0)" to="(40,0)"/>
+            <comp lib="0" loc="(0,0)" name="Pin"><a name="width" val="4"/></comp>
+            <comp lib="5" loc="(40,0)" name="LED"/>
+          </circuit>
+        </project>"##;
+    assert_eq!(Data::from_logisim(widths).err(), invalid("the wires at (0,0) connect ports that are 1 and 4 bits wide"));
+    assert_eq!(Data::from_logisim("<project>\n<circuit name=\"main\">\n</project>").err(), invalid("</project> closes <circuit> on line 3"));
+}
+
+#[test]
+fn test_logisim_import_errors() {
+    let invalid = |reason: &str| Some(LogikError::InvalidLogisim(String::from(reason)));
+    let import = |body: &str| Data::from_logisim(&format!(
+        "<project>\n<lib desc=\"#Wiring\" name=\"0\"/>\n<lib desc=\"#Gates\" name=\"1\"/>\n<circuit name=\"main\">\n{}\n</circuit>\n</project>",
+        body
+    )).err();
+    
+    // components that are unknown or come from a library the file doesn't have
+    assert_eq!(import("<comp lib=\"1\" loc=\"(10,20)\" name=\"Flux Gate\"/>"), invalid("unsupported components: Flux Gate at (10,20)"));
+    assert_eq!(import("<comp lib=\"9\" loc=\"(10,20)\" name=\"AND Gate\"/>"), invalid("there is no library 9"));
+    assert_eq!(Data::from_logisim("<project>\n<lib desc=\"#Wiring\" name=\"0\"/>\n</project>").err(), invalid("there is no circuit to import"));
+    
+    // attributes that are missing or can't be understood
+    assert_eq!(import("<comp lib=\"1\" name=\"AND Gate\"/>"), invalid("a location is missing"));
+    assert_eq!(import("<comp lib=\"1\" loc=\"(10,20)\"/>"), invalid("a component has no name"));
+    assert_eq!(import("<wire from=\"(0,0)\"/>"), invalid("a location is missing"));
+    assert_eq!(import("<comp lib=\"1\" loc=\"(a,b)\" name=\"AND Gate\"/>"), invalid("'(a,b)' is not a location"));
+    assert_eq!(import("<comp lib=\"0\" loc=\"(10,20)\" name=\"Pin\"><a name=\"width\" val=\"wide\"/></comp>"), invalid("width of Pin isn't a number"));
+    assert_eq!(import("<comp lib=\"1\" loc=\"(10,20)\" name=\"AND Gate\"><a name=\"inputs\" val=\"many\"/></comp>"), invalid("a gate can't have 'many' inputs"));
+    assert_eq!(import("<comp lib=\"1\" loc=\"(10,20)\" name=\"AND Gate\"><a name=\"inputs\" val=\"99\"/></comp>"), invalid("a gate can't have 99 inputs"));
+    assert_eq!(import("<comp lib=\"1\" loc=\"(10,20)\" name=\"AND Gate\"><a name=\"facing\" val=\"up\"/></comp>"), invalid("AND Gate can't face up"));
+    
+    // XML that is cut short, nested too deep or otherwise broken
+    assert_eq!(Data::from_logisim("").err(), invalid("expected '<' on line 1"));
+    assert_eq!(Data::from_logisim("<circuit name=\"main\"/>").err(), invalid("expected a <project> but found a <circuit>"));
+    assert_eq!(Data::from_logisim("<project").err(), invalid("expected a name on line 1"));
+    assert_eq!(Data::from_logisim("<!-- never closed").err(), invalid("'-->' is missing on line 1"));
+    assert_eq!(Data::from_logisim("<project>\n<circuit name=\"main>\n</circuit>\n</project>").err(), invalid("an attribute value isn't closed on line 2"));
+    assert_eq!(Data::from_logisim("<project>\n<circuit name=\"a &bogus; b\">\n</circuit>\n</project>").err(), invalid("unknown character reference on line 2"));
+    assert_eq!(Data::from_logisim("<project>\n</circuit>\n</project>").err(), invalid("</circuit> closes <project> on line 2"));
+    assert_eq!(
+        Data::from_logisim("<project>\n<circuit name=\"main\" name=\"other\">\n</circuit>\n</project>").err(),
+        invalid("<circuit> has the attribute name twice on line 2")
+    );
+    assert_eq!(Data::from_logisim("<project/>\n<project/>").err(), invalid("expected the end of the file after </project> on line 2"));
+    assert_eq!(Data::from_logisim(&"<a>".repeat(100_000)).err(), invalid("elements are nested more than 64 deep on line 1"));
+    
+    let circuit = "<project>\n<lib desc=\"#Wiring\" name=\"0\"/>\n<circuit name=\"main\">\n<comp lib=\"0\" loc=\"(10,20)\" name=\"Pin\"/>\n</circuit>\n</project>";
+    assert!(Data::from_logisim(circuit).is_ok());
+    for end in 0..circuit.len() {
+        assert!(matches!(Data::from_logisim(&circuit[..end]), Err(LogikError::InvalidLogisim(_))));
+    }
+}
+
+#[test]
+fn test_truth_table() {
+    use SubnetState::*;
+    
+    let mut data = Data::new();
+    for subnet in 1..=6 {
+        data.add_subnet(subnet).unwrap();
+    }
+    let input = data.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    data.set_parameter(input, "label", ParameterValue::String(String::from("a"))).unwrap();
+    data.add_component(Box::new(Constant::new()), vec![Some(2)]).unwrap();
+    data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(3)]).unwrap();
+    data.add_component(Box::new(TriBuffer {}), vec![Some(1), Some(2), Some(4)]).unwrap();
+    let output = data.add_component(Box::new(OutputGate::new()), vec![Some(3)]).unwrap();
+    data.set_parameter(output, "label", ParameterValue::String(String::from("y"))).unwrap();
+    // clocked by a while the table is made, it has to lose what it stored afterwards
+    data.add_component(Box::new(DFlipFlop::new()), vec![Some(2), Some(1), None, Some(6), None]).unwrap();
+    data.start_recording();
+    
+    let subnets = data.subnets.clone();
+    let states = data.save_states();
+    let table = data.truth_table(&[2, 1], &[3, 4]).unwrap();
+    assert_eq!(table.rows[3], (vec![On.into(), On.into()], vec![On.into(), On.into()]));
+    assert_eq!(table.to_csv(), "subnet 2,a,y,subnet 4\n0,0,0,z\n0,1,0,z\n1,0,0,0\n1,1,1,1\n");
+    assert_eq!(table.to_markdown(), "\
+        | subnet 2 | a   | y   | subnet 4 |\n\
+        |----------|-----|-----|----------|\n\
+        | 0        | 0   | 0   | z        |\n\
+        | 0        | 1   | 0   | z        |\n\
+        | 1        | 0   | 0   | 0        |\n\
+        | 1        | 1   | 1   | 1        |\n");
+    assert_eq!(data.subnets, subnets);
+    assert_eq!(data.save_states(), states);
+    assert_eq!(data.parameter(2, "value").unwrap().value, ParameterValue::Bits(Off.into()));
+    assert!(data.trace().unwrap().entries().is_empty());
+    
+    // the inputs still drive what they drove before
+    data.set_parameter(2, "value", ParameterValue::Bits(On.into())).unwrap();
+    assert_eq!(data.subnet_state(3), Ok(On));
+    
+    assert_eq!(data.truth_table(&[3], &[4]), Err(LogikError::NotAnInput(3)));
+    assert_eq!(data.truth_table(&[1], &[7]), Err(LogikError::UnknownSubnet(7)));
+    data.add_subnet_with_width(7, 17).unwrap();
+    let wide = data.add_component(Box::new(InputGate::new()), vec![None]).unwrap();
+    data.set_parameter(wide, "width", ParameterValue::Int(17)).unwrap();
+    data.link(wide, 0, 7).unwrap();
+    assert_eq!(data.truth_table(&[7], &[]), Err(LogikError::TooManyInputs(17)));
+}
+
+#[test]
+fn test_synthesis() {
+    use crate::data::analysis::synthesis::{Form, Function, Implicant};
+    use SubnetState::*;
+    
+    let kinds = |data: &Data, synthesis: &Synthesis| synthesis.components.iter()
+        .map(|id| data.components[id].0.id())
+        .filter(|kind| *kind != ComponentId::Input && *kind != ComponentId::Output)
+        .collect::<Vec<_>>();
+    let simulate = |data: &mut Data, synthesis: &Synthesis| data.truth_table(&synthesis.inputs, &[synthesis.output])
+        .unwrap()
+        .rows
+        .into_iter()
+        .map(|(_, outputs)| Some(outputs[0] == On.into()))
+        .collect::<Vec<_>>();
+    
+    let function = Function::parse("(a & !b) | c").unwrap();
+    assert_eq!(function.variables, vec!["a", "b", "c"]);
+    assert_eq!(function.values, [false, true, false, true, true, true, false, true].iter().map(|value| Some(*value)).collect::<Vec<_>>());
+    let mut data = Data::new();
+    data.add_subnet(4).unwrap();
+    let synthesis = data.synthesize(&function, Form::SumOfProducts, false).unwrap();
+    assert_eq!(synthesis.inputs, vec![5, 6, 7]);
+    assert_eq!(kinds(&data, &synthesis), vec![ComponentId::Not, ComponentId::And, ComponentId::Or]);
+    assert_eq!(simulate(&mut data, &synthesis), function.values);
+    assert_eq!(data.parameter(synthesis.components[0], "label").unwrap().value, ParameterValue::String(String::from("a")));
+    
+    // a multiplexer out of NAND gates, the inverted select is shared and nothing is inverted twice
+    let function = Function::parse("y = s & b | !s & a").unwrap();
+    let mut data = Data::new();
+    let synthesis = data.synthesize(&function, Form::SumOfProducts, true).unwrap();
+    assert_eq!(kinds(&data, &synthesis), vec![ComponentId::Nand; 4]);
+    assert_eq!(simulate(&mut data, &synthesis), function.values);
+    for form in [Form::SumOfProducts, Form::ProductOfSums].iter() {
+        for nand_only in [false, true].iter() {
+            let function = Function::parse("(a | b) & (a | !c) ^ d").unwrap();
+            let mut data = Data::new();
+            let synthesis = data.synthesize(&function, *form, *nand_only).unwrap();
+            assert_eq!(simulate(&mut data, &synthesis), function.values);
+            
+            let table = data.truth_table(&synthesis.inputs, &[synthesis.output]).unwrap();
+            assert_eq!(Function::from_truth_table(&table, 0).unwrap().values, function.values);
+        }
+    }
+    
+    // terms that merge away leave the output on the input, constants become constants
+    let mut data = Data::new();
+    let synthesis = data.synthesize(&Function::parse("a & b | a & !b").unwrap(), Form::ProductOfSums, false).unwrap();
+    assert_eq!(synthesis.output, synthesis.inputs[0]);
+    assert!(kinds(&data, &synthesis).is_empty());
+    let synthesis = data.synthesize(&Function::parse("a & !a").unwrap(), Form::SumOfProducts, false).unwrap();
+    assert_eq!(kinds(&data, &synthesis), vec![ComponentId::Constant]);
+    assert_eq!(data.subnet_state(synthesis.output), Ok(Off));
+    
+    // don't-cares are used where they make the terms smaller
+    let function = Function { variables: vec![String::from("a"), String::from("b")], output: String::from("y"), values: vec![Some(false), Some(true), None, Some(true)] };
+    assert_eq!(function.minimize(Form::SumOfProducts), vec![Implicant { value: 1, mask: 2 }]);
+    assert_eq!(function.minimize(Form::ProductOfSums), vec![Implicant { value: 0, mask: 2 }]);
+    
+    let invalid = |column, reason: &str| Err(LogikError::InvalidExpression { column, reason: String::from(reason) });
+    assert_eq!(Function::parse("a & "), invalid(5, "the expression ends too early"));
+    assert_eq!(Function::parse("a $ b"), invalid(3, "unexpected '$'"));
+    assert_eq!(Function::parse("(a | b"), invalid(7, "expected ')'"));
+    assert_eq!(Function::parse("a b"), invalid(3, "unexpected 'b'"));
+}
+
+#[test]
+fn test_lint() {
+    use crate::data::analysis::lint::{Diagnostic, DiagnosticKind::*};
+    
+    let diagnostic = |kind, subnets: &[i32], ports: &[(i32, usize)]| Diagnostic { kind, subnets: subnets.to_vec(), ports: ports.to_vec() };
+    let ring = || {
+        let mut data = Data::new();
+        data.add_subnet(1).unwrap();
+        data.add_subnet(2).unwrap();
+        let first = data.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).unwrap();
+        let second = data.add_component(Box::new(NOT {}), vec![Some(2), Some(1)]).unwrap();
+        (data, first, second)
+    };
+    
+    let (data, first, second) = ring();
+    assert_eq!(data.lint(), vec![diagnostic(CombinationalLoop, &[1, 2], &[(first, 1), (second, 1)])]);
+    
+    // a flip-flop breaks the loop, its disable input and inverted output are left over
+    let mut data = Data::new();
+    for subnet in 1..=3 {
+        data.add_subnet(subnet).unwrap();
+    }
+    data.add_component(ComponentId::Clock.build().unwrap(), vec![Some(2)]).unwrap();
+    let flip_flop = data.add_component(Box::new(DFlipFlop::new()), vec![Some(1), Some(2), None, Some(3), None]).unwrap();
+    data.add_component(Box::new(NOT {}), vec![Some(3), Some(1)]).unwrap();
+    assert_eq!(data.lint(), vec![
+        diagnostic(UnconnectedInput, &[], &[(flip_flop, 2)]),
+        diagnostic(UnusedOutput, &[], &[(flip_flop, 4)]),
+    ]);
+    
+    // bits of a bus are followed through a splitter one by one
+    let mut data = Data::new();
+    data.add_subnet_with_width(1, 2).unwrap();
+    data.add_subnet(2).unwrap();
+    data.add_subnet(3).unwrap();
+    data.add_component(ComponentId::Splitter.build_with_params(&[2, 2]).unwrap(), vec![Some(1), Some(2), Some(3)]).unwrap();
+    let first = data.add_component(Box::new(NOT {}), vec![Some(2), Some(3)]).unwrap();
+    assert_eq!(data.lint(), Vec::new());
+    let second = data.add_component(Box::new(NOT {}), vec![Some(3), Some(2)]).unwrap();
+    assert_eq!(data.lint(), vec![diagnostic(CombinationalLoop, &[1, 2, 3], &[(first, 1), (second, 1)])]);
+    
+    // tri-state buffers and pull resistors share a subnet, inputs don't
+    let mut data = Data::new();
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    let a = data.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    let b = data.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    for _ in 0..2 {
+        data.add_component(ComponentId::TriStateBuffer.build().unwrap(), vec![Some(1), Some(1), Some(2)]).unwrap();
+    }
+    data.add_component(ComponentId::PullUp.build().unwrap(), vec![Some(2)]).unwrap();
+    data.add_component(Box::new(Probe {}), vec![Some(2)]).unwrap();
+    assert_eq!(data.lint(), vec![diagnostic(MultipleDrivers, &[1], &[(a, 0), (b, 0)])]);
+    data.remove_component(a).unwrap();
+    data.remove_component(b).unwrap();
+    let led = data.add_component(Box::new(LED {}), vec![None]).unwrap();
+    let mut readers = data.subnet_edges[&1].iter().map(|edge| (edge.component, edge.port)).collect::<Vec<_>>();
+    readers.sort_unstable();
+    assert_eq!(readers.len(), 4);
+    assert_eq!(data.lint(), vec![
+        diagnostic(Undriven, &[1], &readers),
+        diagnostic(UnconnectedInput, &[], &[(led, 0)]),
+    ]);
+    
+    // subcircuits are looked into, the output of a register doesn't follow its input right away
+    let mut inverter = Data::new();
+    inverter.add_subnet(1).unwrap();
+    inverter.add_subnet(2).unwrap();
+    inverter.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    inverter.add_component(Box::new(OutputGate::new()), vec![Some(2)]).unwrap();
+    inverter.add_component(Box::new(NOT {}), vec![Some(1), Some(2)]).unwrap();
+    let mut latch = Data::new();
+    for subnet in 1..=3 {
+        latch.add_subnet(subnet).unwrap();
+    }
+    latch.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    latch.add_component(Box::new(OutputGate::new()), vec![Some(3)]).unwrap();
+    latch.add_component(ComponentId::Clock.build().unwrap(), vec![Some(2)]).unwrap();
+    latch.add_component(Box::new(DFlipFlop::new()), vec![Some(1), Some(2), None, Some(3), None]).unwrap();
+    
+    let mut data = Data::new();
+    data.define_subcircuit("inverter", &inverter).unwrap();
+    data.define_subcircuit("latch", &latch).unwrap();
+    data.add_subnet(1).unwrap();
+    data.add_subnet(2).unwrap();
+    let first = data.add_subcircuit("inverter").unwrap();
+    data.link(first, 0, 1).unwrap();
+    data.link(first, 1, 2).unwrap();
+    let second = data.add_subcircuit("latch").unwrap();
+    data.link(second, 0, 2).unwrap();
+    data.link(second, 1, 1).unwrap();
+    assert_eq!(data.lint(), Vec::new());
+    data.remove_component(second).unwrap();
+    let second = data.add_subcircuit("inverter").unwrap();
+    data.link(second, 0, 2).unwrap();
+    data.link(second, 1, 1).unwrap();
+    assert_eq!(data.lint(), vec![diagnostic(CombinationalLoop, &[1, 2], &[(first, 1), (second, 1)])]);
+}
+
+#[test]
+fn test_timing() {
+    use crate::data::analysis::timing::CriticalPath;
+    
+    let mut data = Data::new();
+    for subnet in 1..=8 {
+        data.add_subnet(subnet).unwrap();
+    }
+    data.add_component(Box::new(InputGate::new()), vec![Some(1)]).unwrap();
+    data.add_component(Box::new(InputGate::new()), vec![Some(2)]).unwrap();
+    let and = data.add_component(Box::new(AND::new(2)), vec![Some(1), Some(2), Some(3)]).unwrap();
+    let first = data.add_component(Box::new(NOT {}), vec![Some(3), Some(4)]).unwrap();
+    data.add_component(ComponentId::Clock.build().unwrap(), vec![Some(6)]).unwrap();
+    let flip_flop = data.add_component(Box::new(DFlipFlop::new()), vec![Some(4), Some(6), None, Some(7), None]).unwrap();
+    let second = data.add_component(Box::new(NOT {}), vec![Some(7), Some(8)]).unwrap();
+    data.add_component(Box::new(OutputGate::new()), vec![Some(8)]).unwrap();
+    
+    // the flip-flop takes its own delay before its output changes
+    let delays = map!(ComponentId::And => 2, ComponentId::DFlipFlop => 3);
+    let timing = data.timing(&delays).unwrap();
+    assert_eq!(timing.arrivals, vec![(1, 0), (2, 0), (3, 2), (4, 3), (6, 0), (7, 3), (8, 4)].into_iter().collect());
+    assert_eq!(timing.critical_path, CriticalPath { components: vec![flip_flop, second], subnets: vec![7, 8], delay: 4 });
+    assert_eq!(timing.clock_period, Some(3));
+    assert_eq!(timing.max_frequency(), Some(1.0 / 3.0));
+    
+    // without a delay for their kind components keep their own
+    data.set_delay(and, 10).unwrap();
+    let timing = data.timing(&HashMap::new()).unwrap();
+    assert_eq!(timing.critical_path, CriticalPath { components: vec![1, and, first], subnets: vec![1, 3, 4], delay: 11 });
+    assert_eq!(timing.clock_period, Some(11));
+    
+    data.remove_component(flip_flop).unwrap();
+    let timing = data.timing(&HashMap::new()).unwrap();
+    assert_eq!(timing.clock_period, None);
+    assert_eq!(timing.max_frequency(), None);
+    assert_eq!(timing.critical_path, CriticalPath::default());
+    
+    data.add_component(Box::new(NOT {}), vec![Some(4), Some(3)]).unwrap();
+    assert_eq!(data.timing(&HashMap::new()), Err(LogikError::CombinationalLoop(3)));
+    
+    // a subcircuit takes as long as the input pin and the gates inside, on top of its own delay,
+    // which is how long the simulation takes as well
+    let mut data = Data::new();
+    data.define_subcircuit("half_adder", &half_adder()).unwrap();
+    for subnet in 1..=4 {
+        data.add_subnet(subnet).unwrap();
+    }
+    let input = data.add_component(Box::new(Switch::new()), vec![Some(1)]).unwrap();
+    data.add_component(Box::new(Constant::with_state(true)), vec![Some(2)]).unwrap();
+    let instance = data.add_subcircuit("half_adder").unwrap();
+    for (port, subnet) in (0..4).zip(1..=4) {
+        data.link(instance, port, subnet).unwrap();
+    }
+    data.add_component(Box::new(OutputGate::new()), vec![Some(3)]).unwrap();
+    data.set_delay(instance, 2).unwrap();
+    let timing = data.timing(&HashMap::new()).unwrap();
+    assert_eq!(timing.arrivals[&3], 4);
+    assert_eq!(timing.critical_path.delay, 4);
+    
+    data.set_stepping(true);
+    data.press_component(input).unwrap();
+    data.advance_time(1);
+    assert_eq!(data.subnet_state(1), Ok(SubnetState::On));
+    data.advance_time(3);
+    assert_eq!(data.subnet_state(3), Ok(SubnetState::On));
+    assert_eq!(data.subnet_state(4), Ok(SubnetState::Off));
+    data.advance_time(1);
+    assert_eq!(data.subnet_state(3), Ok(SubnetState::Off));
+    assert_eq!(data.subnet_state(4), Ok(SubnetState::On));
+}
@@ -0,0 +1,194 @@
+use crate::data::subnet::{SubnetState, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// A single change of a subnet while recording
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct TraceEntry {
+    pub(crate) time: u64,
+    pub(crate) tick: u64,
+    pub(crate) subnet: i32,
+    pub(crate) value: Value,
+}
+
+/// The history of every subnet since recording started
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct Trace {
+    recording: bool,
+    start: u64,
+    initial: BTreeMap<i32, Value>, // <subnet, value when recording started>
+    entries: Vec<TraceEntry>,
+    ticks: Vec<(u64, u64)>, // <time, tick>, when every tick happened
+    subcircuits: BTreeMap<i32, Trace>, // <subcircuit component, what its circuit recorded>
+}
+
+impl Trace {
+    pub(crate) fn new(time: u64, tick: u64, initial: BTreeMap<i32, Value>) -> Self {
+        Self {
+            recording: true,
+            start: time,
+            initial,
+            entries: Vec::new(),
+            ticks: vec![(time, tick)],
+            subcircuits: BTreeMap::new(),
+        }
+    }
+    
+    pub(crate) fn stop(&mut self) {
+        self.recording = false;
+    }
+    
+    pub(crate) fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+    
+    pub(crate) fn record(&mut self, time: u64, tick: u64, subnet: i32, value: Value) {
+        if self.recording {
+            self.entries.push(TraceEntry { time, tick, subnet, value });
+        }
+    }
+    
+    pub(crate) fn record_tick(&mut self, time: u64, tick: u64) {
+        if self.recording {
+            self.ticks.push((time, tick));
+        }
+    }
+    
+    /// Adds what the circuit of a subcircuit recorded, to be written along with this trace
+    pub(crate) fn add_subcircuit(&mut self, component: i32, trace: Trace) {
+        self.subcircuits.insert(component, trace);
+    }
+    
+    /// Writes the trace as a Value Change Dump, every subnet is a wire called `subnet_<id>` and
+    /// the tick counter is an integer called `tick`. A subnet that shows up with another width
+    /// while recording is another wire for that width, called `subnet_<id>_<width>`. The subnets
+    /// of a subcircuit are in a scope called `c<id>` after the subcircuit component. One unit of
+    /// simulation time is written as 1ns
+    pub(crate) fn to_vcd(&self) -> String {
+        let tick_code = identifier(0);
+        let mut variables = Variables { count: 1, header: String::new(), initial: String::new(), changes: Vec::new() };
+        self.declare(&mut variables);
+        // every trace is in order by itself, the stable sort keeps that order within a unit of time
+        variables.changes.sort_by_key(|(time, _)| *time);
+        
+        let mut out = String::new();
+        out.push_str("$version logik $end\n");
+        out.push_str("$timescale 1ns $end\n");
+        out.push_str("$scope module circuit $end\n");
+        writeln!(out, "$var integer 64 {} tick $end", tick_code).unwrap();
+        out.push_str(&variables.header);
+        out.push_str("$upscope $end\n");
+        out.push_str("$enddefinitions $end\n");
+        
+        writeln!(out, "#{}", self.start).unwrap();
+        out.push_str("$dumpvars\n");
+        writeln!(out, "b{:b} {}", self.ticks[0].1, tick_code).unwrap();
+        out.push_str(&variables.initial);
+        out.push_str("$end\n");
+        
+        let mut ticks = self.ticks.iter().skip(1).peekable();
+        let mut changes = variables.changes.into_iter().peekable();
+        let mut time = self.start;
+        loop {
+            // ticks happen before the changes they cause
+            let next_tick = ticks.peek().map(|(time, _)| *time);
+            let next_change = changes.peek().map(|(time, _)| *time);
+            let next = match (next_tick, next_change) {
+                (Some(tick), Some(change)) => tick.min(change),
+                (Some(t), None) | (None, Some(t)) => t,
+                (None, None) => break,
+            };
+            
+            if next != time {
+                time = next;
+                writeln!(out, "#{}", time).unwrap();
+            }
+            
+            if next_tick == Some(next) {
+                writeln!(out, "b{:b} {}", ticks.next().unwrap().1, tick_code).unwrap();
+            } else {
+                out.push_str(&changes.next().unwrap().1);
+            }
+        }
+        
+        out
+    }
+    
+    /// Declares a variable for every subnet and width that showed up while recording, followed by
+    /// the scopes of the subcircuits. Collects the values of the variables on the way
+    fn declare(&self, variables: &mut Variables) {
+        let mut widths = BTreeMap::<_, Vec<_>>::new(); // <subnet, its widths in the order they showed up>
+        let values = self.initial.iter().chain(self.entries.iter().map(|entry| (&entry.subnet, &entry.value)));
+        for (subnet, value) in values {
+            let widths = widths.entry(*subnet).or_default();
+            if !widths.contains(&value.width()) {
+                widths.push(value.width());
+            }
+        }
+        let mut codes = BTreeMap::new();
+        for (subnet, widths) in &widths {
+            for (i, width) in widths.iter().enumerate() {
+                let code = identifier(variables.count);
+                variables.count += 1;
+                if i == 0 {
+                    writeln!(variables.header, "$var wire {} {} subnet_{} $end", width, code, subnet).unwrap();
+                } else {
+                    writeln!(variables.header, "$var wire {} {} subnet_{}_{} $end", width, code, subnet, width).unwrap();
+                }
+                codes.insert((*subnet, *width), code);
+            }
+        }
+        
+        for (subnet, value) in &self.initial {
+            variables.initial.push_str(&change(value, &codes[&(*subnet, value.width())]));
+        }
+        for entry in &self.entries {
+            variables.changes.push((entry.time, change(&entry.value, &codes[&(entry.subnet, entry.value.width())])));
+        }
+        
+        for (component, trace) in &self.subcircuits {
+            writeln!(variables.header, "$scope module c{} $end", component).unwrap();
+            trace.declare(variables);
+            variables.header.push_str("$upscope $end\n");
+        }
+    }
+}
+
+/// What the traces of a circuit and its subcircuits are written as, collected before writing it
+struct Variables {
+    count: usize,
+    header: String,
+    initial: String,
+    changes: Vec<(u64, String)>, // <time, the change as it is written>
+}
+
+/// The short name a variable has in the dump, made out of the printable ASCII characters
+fn identifier(mut n: usize) -> String {
+    const FIRST: u8 = b'!';
+    const COUNT: usize = (b'~' - b'!' + 1) as usize;
+    
+    let mut id = String::new();
+    loop {
+        id.push((FIRST + (n % COUNT) as u8) as char);
+        n /= COUNT;
+        if n == 0 {
+            return id;
+        }
+        n -= 1;
+    }
+}
+
+fn change(value: &Value, code: &str) -> String {
+    let bit = |state: &SubnetState| match state {
+        SubnetState::Floating => 'z',
+        SubnetState::Off => '0',
+        SubnetState::On => '1',
+        SubnetState::Error => 'x',
+    };
+    
+    if value.width() == 1 {
+        format!("{}{}\n", bit(&value.get(0)), code)
+    } else {
+        format!("b{} {}\n", value.bits().iter().rev().map(bit).collect::<String>(), code)
+    }
+}
@@ -0,0 +1,612 @@
+use crate::data::{Data, EdgeDirection};
+use crate::data::component::{Component, ComponentId};
+use crate::data::component::parameter::{find_parameter, ParameterValue};
+use crate::data::error::LogikError;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// The words Verilog reserves, names that are one of them get a number added
+const KEYWORDS: &[&str] = &[
+    "always", "and", "assign", "automatic", "begin", "buf", "bufif0", "bufif1", "case", "casex",
+    "casez", "cell", "cmos", "config", "deassign", "default", "defparam", "design", "disable", "edge",
+    "else", "end", "endcase", "endconfig", "endfunction", "endgenerate", "endmodule", "endprimitive",
+    "endspecify", "endtable", "endtask", "event", "for", "force", "forever", "fork", "function",
+    "generate", "genvar", "highz0", "highz1", "if", "ifnone", "incdir", "include", "initial", "inout",
+    "input", "instance", "integer", "join", "large", "liblist", "library", "localparam",
+    "macromodule", "medium", "module", "nand", "negedge", "nmos", "nor", "noshowcancelled", "not",
+    "notif0", "notif1", "or", "output", "parameter", "pmos", "posedge", "primitive", "pull0", "pull1",
+    "pulldown", "pullup", "pulsestyle_ondetect", "pulsestyle_onevent", "rcmos", "real", "realtime",
+    "reg", "release", "repeat", "rnmos", "rpmos", "rtran", "rtranif0", "rtranif1", "scalared",
+    "showcancelled", "signed", "small", "specify", "specparam", "strong0", "strong1", "supply0",
+    "supply1", "table", "task", "time", "tran", "tranif0", "tranif1", "tri", "tri0", "tri1", "triand",
+    "trior", "trireg", "unsigned", "use", "uwire", "vectored", "wait", "wand", "weak0", "weak1",
+    "while", "wire", "wor", "xnor", "xor",
+];
+
+impl Data {
+    /// Writes the circuit as a structural Verilog module called `name`, preceded by a module for
+    /// every kind of subcircuit it contains. Clocks, buttons and switches become inputs of the
+    /// module since their values come from outside of the circuit
+    pub(crate) fn to_verilog(&self, name: &str) -> Result<String, LogikError> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(LogikError::InvalidName(String::from(name)));
+        }
+        
+        let mut exporter = Exporter::default();
+        exporter.module(self, name);
+        Ok(exporter.modules.join("\n"))
+    }
+}
+
+/// Hands out Verilog identifiers, each one only once
+#[derive(Default)]
+struct Names {
+    used: HashSet<String>,
+}
+
+impl Names {
+    /// Turns `name` into an identifier that isn't taken yet by replacing the characters Verilog
+    /// doesn't allow and adding a number if needed
+    fn fresh(&mut self, name: &str) -> String {
+        let mut base = name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+            .collect::<String>();
+        if base.is_empty() || base.starts_with(|c: char| c.is_ascii_digit()) {
+            base.insert(0, '_');
+        }
+        
+        let mut candidate = base.clone();
+        let mut number = 1;
+        while self.used.contains(&candidate) || KEYWORDS.contains(&candidate.as_str()) {
+            candidate = format!("{}_{}", base, number);
+            number += 1;
+        }
+        self.used.insert(candidate.clone());
+        candidate
+    }
+}
+
+/// A module that was written, with the names of its ports in the order of the ports of the
+/// subcircuit followed by the inputs it needs for clocks, buttons and switches
+#[derive(Clone)]
+struct Module {
+    name: String,
+    pins: Vec<String>,
+    extras: Vec<String>,
+}
+
+#[derive(Default)]
+struct Exporter {
+    modules: Vec<String>,
+    written: HashMap<String, Module>, // <module text after its name, module>, so equal subcircuits share one
+    names: Names,
+}
+
+impl Exporter {
+    /// Writes the module of a circuit, after the modules of its subcircuits
+    fn module(&mut self, data: &Data, name: &str) -> Module {
+        let mut writer = ModuleWriter::new(data);
+        let mut ids = data.components.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        
+        // the pins are named first so that they keep their labels
+        for (kind, direction) in [(ComponentId::Input, "input"), (ComponentId::Output, "output")].iter() {
+            for id in ids.iter().filter(|id| data.components[id].0.id() == *kind) {
+                writer.pin(*id, direction);
+            }
+        }
+        writer.declare_wires();
+        for id in &ids {
+            writer.write_component(self, *id);
+        }
+        
+        let text = writer.text();
+        if let Some(module) = self.written.get(&text) {
+            return module.clone();
+        }
+        
+        let module = Module { name: self.names.fresh(name), pins: writer.pins, extras: writer.extras };
+        self.modules.push(format!("module {}{}", module.name, text));
+        self.written.insert(text, module.clone());
+        module
+    }
+}
+
+/// Collects the ports, declarations and statements of a single module
+struct ModuleWriter<'a> {
+    data: &'a Data,
+    names: Names,
+    wires: HashMap<i32, String>, // <subnet, wire>
+    ports: Vec<String>,
+    pins: Vec<String>,
+    extras: Vec<String>,
+    declarations: Vec<String>,
+    statements: Vec<String>,
+}
+
+impl<'a> ModuleWriter<'a> {
+    fn new(data: &'a Data) -> Self {
+        Self {
+            data,
+            names: Names::default(),
+            wires: HashMap::new(),
+            ports: Vec::new(),
+            pins: Vec::new(),
+            extras: Vec::new(),
+            declarations: Vec::new(),
+            statements: Vec::new(),
+        }
+    }
+    
+    /// Everything after the name of the module
+    fn text(&self) -> String {
+        let mut out = String::new();
+        if self.ports.is_empty() {
+            out.push_str(";\n");
+        } else {
+            out.push_str(" (\n");
+            out.push_str(&self.ports.iter().map(|port| format!("    {}", port)).collect::<Vec<_>>().join(",\n"));
+            out.push_str("\n);\n");
+        }
+        for line in &self.declarations {
+            writeln!(out, "    {}", line).unwrap();
+        }
+        if !self.declarations.is_empty() && !self.statements.is_empty() {
+            out.push('\n');
+        }
+        for statement in &self.statements {
+            for line in statement.lines() {
+                writeln!(out, "    {}", line).unwrap();
+            }
+        }
+        out.push_str("endmodule\n");
+        out
+    }
+    
+    fn component(&self, id: i32) -> &'a dyn Component {
+        self.data.components[&id].0.as_ref()
+    }
+    
+    fn width(&self, id: i32, port: usize) -> u8 {
+        self.component(id).port_width(port).unwrap()
+    }
+    
+    /// The wire a port is linked to
+    fn linked(&self, id: i32, port: usize) -> Option<String> {
+        self.data.component_edges.get(&id)?
+            .iter()
+            .find(|edge| edge.port == port)
+            .map(|edge| self.wires[&edge.subnet].clone())
+    }
+    
+    /// The wire an input port reads, or `default` if it isn't linked
+    fn input(&self, id: i32, port: usize, default: &str) -> String {
+        self.linked(id, port).unwrap_or_else(|| String::from(default))
+    }
+    
+    /// The wire an input port reads, floating if it isn't linked
+    fn floating_input(&self, id: i32, port: usize) -> String {
+        self.input(id, port, &floating(self.width(id, port)))
+    }
+    
+    /// Drives the wire of an output port, outputs that aren't linked are left out
+    fn drive(&mut self, id: i32, port: usize, expression: &str) {
+        if let Some(wire) = self.linked(id, port) {
+            self.statements.push(format!("assign {} = {};", wire, expression));
+        }
+    }
+    
+    fn pin(&mut self, id: i32, direction: &str) {
+        let component = self.component(id);
+        let label = match find_parameter(component, "label").map(|parameter| parameter.value) {
+            Ok(ParameterValue::String(label)) if !label.is_empty() => label,
+            _ => format!("{}_{}", &direction[..direction.len() - 3], id),
+        };
+        let width = self.width(id, 0);
+        let pin = self.names.fresh(&label);
+        self.ports.push(format!("{} {}{}", direction, range(width), pin));
+        self.pins.push(pin);
+    }
+    
+    fn declare_wires(&mut self) {
+        let mut subnets = self.data.subnets.iter().collect::<Vec<_>>();
+        subnets.sort_by_key(|(id, _)| **id);
+        for (id, subnet) in subnets {
+            let wire = self.names.fresh(&format!("s{}", id));
+            self.declarations.push(format!("wire {}{};", range(subnet.width()), wire));
+            self.wires.insert(*id, wire);
+        }
+    }
+    
+    /// An input of the module for something that is driven from outside of the circuit
+    fn extra(&mut self, name: &str) -> String {
+        let extra = self.names.fresh(name);
+        self.ports.push(format!("input {}", extra));
+        self.extras.push(extra.clone());
+        extra
+    }
+    
+    /// A register holding the value a stateful component starts with
+    fn register(&mut self, id: i32, width: u8) -> String {
+        let register = self.names.fresh(&format!("q{}", id));
+        let initial = self.component(id).stored_value().and_then(|value| value.to_u64()).unwrap_or(0);
+        self.declarations.push(format!("reg {}{} = {};", range(width), register, literal(initial, width)));
+        register
+    }
+    
+    fn write_component(&mut self, exporter: &mut Exporter, id: i32) {
+        let component = self.component(id);
+        let int = |name: &str| match find_parameter(component, name).map(|parameter| parameter.value) {
+            Ok(ParameterValue::Int(value)) => value,
+            Ok(ParameterValue::Bool(value)) => value as i64,
+            _ => 0,
+        };
+        
+        match component.id() {
+            ComponentId::Input => {
+                let pin = self.pins[self.pins_before(id)].clone();
+                self.drive(id, 0, &pin);
+            }
+            ComponentId::Output => {
+                let pin = self.pins[self.pins_before(id)].clone();
+                let value = self.floating_input(id, 0);
+                self.statements.push(format!("assign {} = {};", pin, value));
+            }
+            ComponentId::Custom => {
+                let subcircuit = component.subcircuit().unwrap();
+                let module = exporter.module(&subcircuit.circuit(), subcircuit.name());
+                let instance = self.names.fresh(&format!("c{}", id));
+                let mut connections = module.pins.iter()
+                    .enumerate()
+                    .filter_map(|(port, pin)| self.linked(id, port).map(|wire| format!(".{}({})", pin, wire)))
+                    .collect::<Vec<_>>();
+                for extra in &module.extras {
+                    let outer = self.extra(&format!("{}_{}", instance, extra));
+                    connections.push(format!(".{}({})", extra, outer));
+                }
+                self.statements.push(format!("{} {} ({});", module.name, instance, connections.join(", ")));
+            }
+            ComponentId::Constant => {
+                let value = component.parameters()[0].value.to_text();
+                self.drive(id, 0, &format!("{}'b{}", value.len(), value));
+            }
+            ComponentId::Clock | ComponentId::Button | ComponentId::Switch => {
+                let extra = self.extra(&format!("{}{}", component.id().name().to_lowercase(), id));
+                self.drive(id, 0, &extra);
+            }
+            ComponentId::LED | ComponentId::Probe => {}
+            ComponentId::Buffer | ComponentId::Not => {
+                let input = self.floating_input(id, 0);
+                let operator = if component.id() == ComponentId::Not { "~" } else { "" };
+                self.drive(id, 1, &format!("{}{}", operator, input));
+            }
+            ComponentId::And | ComponentId::Nand | ComponentId::Or | ComponentId::Nor | ComponentId::Xor | ComponentId::Xnor => {
+                let inputs = int("inputs") as usize;
+                let output = match self.linked(id, inputs) {
+                    Some(output) => output,
+                    None => return,
+                };
+                let operands = (0..inputs).map(|port| self.floating_input(id, port)).collect::<Vec<_>>();
+                let one_hot = int("one_hot") == 1 && inputs > 2;
+                if one_hot {
+                    let comparison = if component.id() == ComponentId::Xor { "==" } else { "!=" };
+                    self.statements.push(format!("assign {} = ({}) {} 1;", output, operands.join(" + "), comparison));
+                } else {
+                    let gate = self.names.fresh(&format!("c{}", id));
+                    let primitive = component.id().name().to_lowercase();
+                    self.statements.push(format!("{} {} ({}, {});", primitive, gate, output, operands.join(", ")));
+                }
+            }
+            ComponentId::TriStateBuffer | ComponentId::TriStateInverter => {
+                if let Some(output) = self.linked(id, 2) {
+                    let (input, enable) = (self.floating_input(id, 0), self.floating_input(id, 1));
+                    let primitive = if component.id() == ComponentId::TriStateBuffer { "bufif1" } else { "notif1" };
+                    let gate = self.names.fresh(&format!("c{}", id));
+                    self.statements.push(format!("{} {} ({}, {}, {});", primitive, gate, output, input, enable));
+                }
+            }
+            ComponentId::TransmissionGate => {
+                if let (Some(a), Some(b)) = (self.linked(id, 0), self.linked(id, 1)) {
+                    let width = self.width(id, 0);
+                    let enable = self.floating_input(id, 2);
+                    for i in 0..width {
+                        self.statements.push(format!("tranif1 ({}, {}, {});", bit(&a, width, i), bit(&b, width, i), enable));
+                    }
+                }
+            }
+            ComponentId::PullUp | ComponentId::PullDown => {
+                if let Some(wire) = self.linked(id, 0) {
+                    let width = self.width(id, 0);
+                    let primitive = component.id().name().to_lowercase();
+                    for i in 0..width {
+                        self.statements.push(format!("{} ({});", primitive, bit(&wire, width, i)));
+                    }
+                }
+            }
+            ComponentId::Splitter => self.splitter(id),
+            ComponentId::DFlipFlop | ComponentId::TFlipFlop => {
+                let register = self.register(id, 1);
+                let (input, clock) = (self.floating_input(id, 0), self.floating_input(id, 1));
+                let update = if component.id() == ComponentId::DFlipFlop {
+                    format!("{} <= {};", register, input)
+                } else {
+                    format!("if ({}) {} <= ~{};", input, register, register)
+                };
+                let update = self.unless_disabled(id, 2, &update);
+                self.statements.push(format!("always @(posedge {})\n    {}", clock, update));
+                self.drive(id, 3, &register);
+                self.drive(id, 4, &format!("~{}", register));
+            }
+            ComponentId::JKFlipFlop | ComponentId::SRFlipFlop => {
+                let register = self.register(id, 1);
+                let (set, reset) = (self.floating_input(id, 0), self.floating_input(id, 1));
+                let clock = self.floating_input(id, 2);
+                let mut cases = format!("case ({{{}, {}}})\n    2'b10: {} <= 1'b1;\n    2'b01: {} <= 1'b0;\n", set, reset, register, register);
+                if component.id() == ComponentId::JKFlipFlop {
+                    writeln!(cases, "    2'b11: {} <= ~{};", register, register).unwrap();
+                }
+                cases.push_str("    default: ;\nendcase");
+                let update = self.unless_disabled(id, 3, &cases).replace('\n', "\n    ");
+                self.statements.push(format!("always @(posedge {})\n    {}", clock, update));
+                self.drive(id, 4, &register);
+                self.drive(id, 5, &format!("~{}", register));
+            }
+            ComponentId::Register => {
+                let width = int("width") as u8;
+                let register = self.register(id, width);
+                let input = self.floating_input(id, 0);
+                let update = match self.linked(id, 2) {
+                    Some(enable) => format!("if ({}) {} <= {};", enable, register, input),
+                    None => format!("{} <= {};", register, input),
+                };
+                self.clocked(id, 1, 3, &register, width, &update);
+                self.drive(id, 4, &register);
+            }
+            ComponentId::Counter => {
+                let width = int("width") as u8;
+                let max = literal(int("max") as u64, width);
+                let register = self.register(id, width);
+                let input = self.floating_input(id, 0);
+                let (load, enable, up) = (self.input(id, 2, "1'b0"), self.input(id, 3, "1'b1"), self.input(id, 4, "1'b1"));
+                let update = format!(
+                    "if ({load}) {q} <= {d} > {max} ? {max} : {d};\n\
+                    else if ({enable}) {q} <= {up} ? ({q} >= {max} ? {zero} : {q} + 1'b1) : ({q} == {zero} ? {max} : {q} - 1'b1);",
+                    load = load, q = register, d = input, max = max, enable = enable, up = up, zero = literal(0, width)
+                );
+                self.clocked(id, 1, 5, &register, width, &update);
+                self.drive(id, 6, &register);
+                self.drive(id, 7, &format!("{} ? {} == {} : {} == {}", up, register, max, register, literal(0, width)));
+            }
+            ComponentId::ShiftRegister => {
+                let length = int("length") as u8;
+                let register = self.register(id, length);
+                let serial = self.floating_input(id, 0);
+                let (enable, left, load) = (self.input(id, 2, "1'b1"), self.input(id, 3, "1'b1"), self.input(id, 4, "1'b0"));
+                let parallel = self.floating_input(id, 5);
+                let (to_left, to_right) = if length == 1 {
+                    (serial.clone(), serial.clone())
+                } else {
+                    (format!("{{{}[{}:0], {}}}", register, length - 2, serial), format!("{{{}, {}[{}:1]}}", serial, register, length - 1))
+                };
+                let update = format!(
+                    "if ({}) {} <= {};\nelse if ({}) {} <= {} ? {} : {};",
+                    load, register, parallel, enable, register, left, to_left, to_right
+                );
+                self.clocked(id, 1, 6, &register, length, &update);
+                self.drive(id, 7, &register);
+                self.drive(id, 8, &format!("{} ? {} : {}", left, bit(&register, length, length - 1), bit(&register, length, 0)));
+            }
+            ComponentId::RAM | ComponentId::ROM => self.memory(id),
+            ComponentId::Mux => {
+                let (select, width, enable) = (int("select") as u32, int("width") as u8, int("enable") == 1);
+                let inputs = 1 << select;
+                let selector = self.floating_input(id, inputs);
+                let mut expression = self.floating_input(id, inputs - 1);
+                for input in (0..inputs - 1).rev() {
+                    expression = format!("{} == {} ? {} : {}", selector, input, self.floating_input(id, input), expression);
+                }
+                if enable {
+                    expression = format!("{} ? {} : {}", self.floating_input(id, inputs + 2), expression, floating(width));
+                }
+                self.drive(id, inputs + 1, &expression);
+            }
+            ComponentId::Demux | ComponentId::Decoder => {
+                let demux = component.id() == ComponentId::Demux;
+                let (select, enable) = (int("select") as usize, int("enable") == 1);
+                let first = if demux { 2 } else { 1 };
+                let outputs = 1 << select;
+                let selector = self.floating_input(id, first - 1);
+                let input = if demux { self.floating_input(id, 0) } else { String::from("1'b1") };
+                let width = self.width(id, first);
+                for output in 0..outputs {
+                    let mut expression = format!("{} == {} ? {} : {}", selector, output, input, literal(0, width));
+                    if enable {
+                        expression = format!("{} ? {} : {}", self.floating_input(id, first + outputs), expression, floating(width));
+                    }
+                    self.drive(id, first + output, &expression);
+                }
+            }
+            ComponentId::PriorityEncoder => {
+                let (select, enable) = (int("select") as u8, int("enable") == 1);
+                let inputs = 1 << select;
+                let bits = (0..inputs).map(|input| self.input(id, input, "1'b0")).collect::<Vec<_>>();
+                let mut number = floating(select);
+                for (input, bit) in bits.iter().enumerate() {
+                    number = format!("{} ? {} : {}", bit, literal(input as u64, select), number);
+                }
+                let mut valid = bits.join(" | ");
+                if enable {
+                    let enable = self.input(id, inputs + 2, "1'b1");
+                    number = format!("{} ? {} : {}", enable, number, floating(select));
+                    valid = format!("{} && ({})", enable, valid);
+                }
+                self.drive(id, inputs, &number);
+                self.drive(id, inputs + 1, &valid);
+            }
+            ComponentId::Adder | ComponentId::Subtractor | ComponentId::Multiplier => {
+                let width = int("width") as u8;
+                let (a, b) = (self.floating_input(id, 0), self.floating_input(id, 1));
+                let carry = self.input(id, 2, &literal(0, self.width(id, 2)));
+                let (result_width, expression) = match component.id() {
+                    ComponentId::Adder => (width + 1, format!("{} + {} + {}", a, b, carry)),
+                    ComponentId::Subtractor => (width + 1, format!("{{1'b0, {}}} - {} - {}", a, b, carry)),
+                    _ => (width * 2, format!("{} * {} + {}", a, b, carry)),
+                };
+                let result = self.names.fresh(&format!("r{}", id));
+                self.declarations.push(format!("wire {}{} = {};", range(result_width), result, expression));
+                self.drive(id, 3, &format!("{}[{}:0]", result, width - 1));
+                let high = if result_width == width + 1 {
+                    format!("{}[{}]", result, width)
+                } else {
+                    format!("{}[{}:{}]", result, result_width - 1, width)
+                };
+                self.drive(id, 4, &high);
+            }
+            ComponentId::Divider => {
+                let (a, b) = (self.floating_input(id, 0), self.floating_input(id, 1));
+                self.drive(id, 2, &format!("{} / {}", a, b));
+                self.drive(id, 3, &format!("{} % {}", a, b));
+            }
+            ComponentId::Comparator => {
+                let (mut a, mut b) = (self.floating_input(id, 0), self.floating_input(id, 1));
+                if int("signed") == 1 {
+                    a = format!("$signed({})", a);
+                    b = format!("$signed({})", b);
+                }
+                for (port, operator) in [(2, "<"), (3, "=="), (4, ">")].iter() {
+                    self.drive(id, *port, &format!("{} {} {}", a, operator, b));
+                }
+            }
+        }
+    }
+    
+    /// How many pins of the module come before the pin of an `Input` or `Output` component
+    fn pins_before(&self, id: i32) -> usize {
+        let kind = self.component(id).id();
+        let inputs = self.data.components.values().filter(|component| component.0.id() == ComponentId::Input).count();
+        let before = self.data.components.iter()
+            .filter(|(other, component)| component.0.id() == kind && **other < id)
+            .count();
+        if kind == ComponentId::Output { inputs + before } else { before }
+    }
+    
+    /// An update of a flip-flop that only happens while its disable port isn't on
+    fn unless_disabled(&self, id: i32, disable: usize, update: &str) -> String {
+        match self.linked(id, disable) {
+            Some(disable) => format!("if (!{}) {}", disable, update),
+            None => String::from(update),
+        }
+    }
+    
+    /// An update on the rising edge of the clock, with a clear that doesn't wait for the clock
+    /// when the clear port is linked
+    fn clocked(&mut self, id: i32, clock: usize, clear: usize, register: &str, width: u8, update: &str) {
+        let clock = self.floating_input(id, clock);
+        let update = update.lines().map(|line| format!("    {}", line)).collect::<Vec<_>>().join("\n");
+        self.statements.push(match self.linked(id, clear) {
+            Some(clear) => format!(
+                "always @(posedge {} or posedge {})\n    if ({}) {} <= {};\n    else {}",
+                clock, clear, clear, register, literal(0, width), update.trim_start()
+            ),
+            None => format!("always @(posedge {})\n{}", clock, update),
+        });
+    }
+    
+    /// A RAM or ROM as an array of registers that starts out with the words it holds now
+    fn memory(&mut self, id: i32) {
+        let component = self.component(id);
+        let memory = component.memory().unwrap();
+        let (address_width, data_width) = (self.width(id, 0), self.width(id, 1));
+        let array = self.names.fresh(&format!("m{}", id));
+        let counter = self.names.fresh(&format!("i{}", id));
+        self.declarations.push(format!("reg {}{} [0:{}];", range(data_width), array, (1u64 << address_width) - 1));
+        self.declarations.push(format!("integer {};", counter));
+        
+        let mut initial = format!(
+            "initial begin\n    for ({i} = 0; {i} < {size}; {i} = {i} + 1)\n        {m}[{i}] = {zero};\n",
+            i = counter, size = 1u64 << address_width, m = array, zero = literal(0, data_width)
+        );
+        for (address, word) in memory.words() {
+            writeln!(initial, "    {}[{}] = {};", array, address, literal(word, data_width)).unwrap();
+        }
+        initial.push_str("end");
+        self.statements.push(initial);
+        
+        let address = self.floating_input(id, 0);
+        if component.id() == ComponentId::RAM {
+            let (input, enable, clock) = (self.floating_input(id, 1), self.input(id, 2, "1'b0"), self.floating_input(id, 3));
+            self.statements.push(format!("always @(posedge {})\n    if ({}) {}[{}] <= {};", clock, enable, array, address, input));
+            self.drive(id, 4, &format!("{}[{}]", array, address));
+        } else {
+            self.drive(id, 1, &format!("{}[{}]", array, address));
+        }
+    }
+    
+    /// Verilog has no way to connect wires both ways, so every branch is connected in the direction
+    /// its drivers give it. A branch driven by something other than the splitter drives its bits
+    /// of the bus, the other branches are driven by the bus
+    fn splitter(&mut self, id: i32) {
+        let component = self.component(id);
+        let bits = match find_parameter(component, "bits").map(|parameter| parameter.value) {
+            Ok(ParameterValue::String(bits)) => bits.split(',').map(|branch| branch.parse::<usize>().ok()).collect::<Vec<_>>(),
+            _ => return,
+        };
+        let bus = match self.linked(id, 0) {
+            Some(bus) => bus,
+            None => return,
+        };
+        let bus_width = bits.len() as u8;
+        
+        for port in 1..component.ports() {
+            let branch = match self.linked(id, port) {
+                Some(branch) => branch,
+                None => continue,
+            };
+            let width = self.width(id, port);
+            let bus_bits = bits.iter()
+                .enumerate()
+                .filter(|(_, branch)| **branch == Some(port - 1))
+                .map(|(bit, _)| bit as u8)
+                .collect::<Vec<_>>();
+            
+            if self.driven_by_others(id, port) {
+                for (i, bus_bit) in bus_bits.iter().enumerate() {
+                    self.statements.push(format!("assign {} = {};", bit(&bus, bus_width, *bus_bit), bit(&branch, width, i as u8)));
+                }
+            } else {
+                let parts = bus_bits.iter().rev().map(|bus_bit| bit(&bus, bus_width, *bus_bit)).collect::<Vec<_>>();
+                let value = if parts.len() == 1 { parts[0].clone() } else { format!("{{{}}}", parts.join(", ")) };
+                self.statements.push(format!("assign {} = {};", branch, value));
+            }
+        }
+    }
+    
+    /// Whether anything but the component itself drives the subnet a port is linked to
+    fn driven_by_others(&self, id: i32, port: usize) -> bool {
+        let subnet = match self.data.component_edges.get(&id).and_then(|edges| edges.iter().find(|edge| edge.port == port)) {
+            Some(edge) => edge.subnet,
+            None => return false,
+        };
+        self.data.subnet_edges[&subnet]
+            .iter()
+            .any(|edge| edge.component != id && edge.direction != EdgeDirection::ToComponent)
+    }
+}
+
+/// The range of a declaration, single bits are declared without one
+fn range(width: u8) -> String {
+    if width == 1 { String::new() } else { format!("[{}:0] ", width - 1) }
+}
+
+/// A single bit of a wire or register, which is the wire itself if it is a single bit wide
+fn bit(name: &str, width: u8, bit: u8) -> String {
+    if width == 1 { String::from(name) } else { format!("{}[{}]", name, bit) }
+}
+
+fn literal(value: u64, width: u8) -> String {
+    format!("{}'d{}", width, value)
+}
+
+fn floating(width: u8) -> String {
+    format!("{}'bz", width)
+}
@@ -0,0 +1,1240 @@
+use crate::data::Data;
+use crate::data::component::{Component, ComponentId};
+use crate::data::component::components::MAX_INPUTS;
+use crate::data::component::parameter::{set_parameter, ParameterValue};
+use crate::data::component::subcircuit::Subcircuit;
+use crate::data::error::LogikError;
+use crate::data::subnet::{SubnetState, MAX_WIDTH};
+use std::collections::{HashMap, HashSet};
+
+/// The symbols of the subset, longer ones come first so that they are matched before their prefixes
+const SYMBOLS: &[&str] = &[
+    "<=", "~^", "^~", "(", ")", "[", "]", "{", "}", ",", ";", ":", ".", "=", "~", "&", "|", "^", "@", "#",
+];
+
+fn invalid(line: usize, reason: String) -> LogikError {
+    LogikError::InvalidVerilog { line, reason }
+}
+
+impl Data {
+    /// Builds a circuit out of a gate level subset of Verilog. The last module that isn't
+    /// instantiated by another one is the circuit, the modules it instantiates become subcircuits.
+    /// Vectors are split into single bits, so every bitwise operator becomes a gate for every bit
+    /// and every register an `always @(posedge ...)` block assigns becomes a `Register`
+    pub(crate) fn from_verilog(text: &str) -> Result<Data, LogikError> {
+        let modules = Parser::new(text)?.modules()?;
+        let instantiated = modules.iter()
+            .flat_map(|module| module.items.iter())
+            .filter_map(|item| match item {
+                Item::Instance { module, .. } => Some(module.as_str()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let top = modules.iter()
+            .rev()
+            .find(|module| !instantiated.contains(module.name.as_str()))
+            .ok_or_else(|| invalid(1, String::from("there is no module that isn't instantiated by another one")))?;
+        
+        build(&modules, top, &mut Vec::new())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(String),
+    Symbol(&'static str),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(text) | Token::Number(text) => format!("'{}'", text),
+            Token::Symbol(symbol) => format!("'{}'", symbol),
+        }
+    }
+}
+
+/// Splits the text into tokens with the lines they are on, comments and compiler directives are
+/// left out
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, LogikError> {
+    let bytes = text.as_bytes();
+    let word = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$';
+    let digit = |byte: u8| byte.is_ascii_hexdigit() || b"xXzZ?_".contains(&byte);
+    
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let start = i;
+        match bytes[i] {
+            b'\n' => {
+                line += 1;
+                i += 1;
+            }
+            byte if byte.is_ascii_whitespace() => i += 1,
+            // directives like `timescale don't change the circuit
+            b'`' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            _ if rest.starts_with(b"//") => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            _ if rest.starts_with(b"/*") => {
+                let end = rest.windows(2)
+                    .position(|pair| pair == b"*/")
+                    .ok_or_else(|| invalid(line, String::from("the comment is never closed")))?;
+                line += rest[..end].iter().filter(|byte| **byte == b'\n').count();
+                i += end + 2;
+            }
+            byte if byte.is_ascii_alphabetic() || byte == b'_' => {
+                while i < bytes.len() && word(bytes[i]) {
+                    i += 1;
+                }
+                tokens.push((line, Token::Word(String::from(&text[start..i]))));
+            }
+            byte if byte.is_ascii_digit() || byte == b'\'' => {
+                while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                if i < bytes.len() && bytes[i] == b'\'' {
+                    i += 1;
+                    if i < bytes.len() && (bytes[i] == b's' || bytes[i] == b'S') {
+                        i += 1;
+                    }
+                    if i < bytes.len() && b"bBoOdDhH".contains(&bytes[i]) {
+                        i += 1;
+                    }
+                    while i < bytes.len() && digit(bytes[i]) {
+                        i += 1;
+                    }
+                }
+                tokens.push((line, Token::Number(String::from(&text[start..i]))));
+            }
+            _ => match SYMBOLS.iter().find(|symbol| rest.starts_with(symbol.as_bytes())) {
+                Some(symbol) => {
+                    tokens.push((line, Token::Symbol(symbol)));
+                    i += symbol.len();
+                }
+                None => {
+                    let character = text[start..].chars().next().unwrap();
+                    return Err(invalid(line, format!("unexpected '{}'", character)));
+                }
+            },
+        }
+    }
+    
+    Ok(tokens)
+}
+
+/// Reads a number like `8'hff`, `4'b10z1` or `12` into its bits, least significant first. Floating
+/// bits are `None`, numbers without a size have no width and take the width they are used with
+fn literal(line: usize, text: &str) -> Result<(Option<u8>, Vec<Option<bool>>), LogikError> {
+    let text = text.replace('_', "");
+    let wrong = || invalid(line, format!("'{}' is not a valid number", text));
+    
+    let (size, base, digits) = match text.split_once('\'') {
+        Some((size, rest)) => {
+            let rest = rest.trim_start_matches(['s', 'S']);
+            let base = rest.chars().next().ok_or_else(wrong)?.to_ascii_lowercase();
+            let size = match size {
+                "" => None,
+                size => match size.parse::<u8>() {
+                    Ok(size) if (1..=MAX_WIDTH).contains(&size) => Some(size),
+                    _ => return Err(invalid(line, format!("'{}' is wider than {} bits", text, MAX_WIDTH))),
+                },
+            };
+            (size, base, &rest[1..])
+        }
+        None => (None, 'd', text.as_str()),
+    };
+    if digits.is_empty() {
+        return Err(wrong());
+    }
+    if digits.contains(['x', 'X']) {
+        return Err(invalid(line, String::from("unknown bits can't be imported, only 0, 1 and z")));
+    }
+    
+    let mut bits = match base {
+        'd' => match digits.parse::<u64>() {
+            Ok(number) => (0..64).map(|bit| Some(number >> bit & 1 == 1)).collect::<Vec<_>>(),
+            Err(_) if digits.chars().all(|c| "zZ?".contains(c)) => vec![None],
+            Err(_) => return Err(wrong()),
+        },
+        base => {
+            let bits_per_digit = match base {
+                'b' => 1,
+                'o' => 3,
+                'h' => 4,
+                _ => return Err(wrong()),
+            };
+            let mut bits = Vec::new();
+            for c in digits.chars().rev() {
+                match c {
+                    'z' | 'Z' | '?' => bits.extend(vec![None; bits_per_digit]),
+                    c => {
+                        let value = c.to_digit(1 << bits_per_digit).ok_or_else(wrong)?;
+                        bits.extend((0..bits_per_digit).map(|bit| Some(value >> bit & 1 == 1)));
+                    }
+                }
+            }
+            bits
+        }
+    };
+    
+    if let Some(size) = size {
+        bits = resize(&bits, size);
+    } else if bits.len() > MAX_WIDTH as usize {
+        bits = resize(&bits, MAX_WIDTH);
+    }
+    Ok((size, bits))
+}
+
+/// Cuts the bits of a number down to `width` or extends them, with floating bits if the highest bit
+/// floats and with 0 otherwise
+fn resize(bits: &[Option<bool>], width: u8) -> Vec<Option<bool>> {
+    let fill = match bits.last() {
+        Some(None) => None,
+        _ => Some(false),
+    };
+    (0..width as usize).map(|bit| bits.get(bit).copied().unwrap_or(fill)).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    And,
+    Or,
+    Xor,
+    Xnor,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Name(String),
+    /// A bit or a range of bits of a net, with the numbers as they are written
+    Select(String, u32, u32),
+    Literal(Option<u8>, Vec<Option<bool>>),
+    /// The parts in the order they are written, so the most significant comes first
+    Concat(Vec<Expr>),
+    Not(Box<Expr>),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Input,
+    Output,
+}
+
+/// What a declaration says about the nets it declares
+#[derive(Debug, Clone, Copy)]
+struct Declaration {
+    direction: Option<Direction>,
+    reg: bool,
+    lsb: u32,
+    width: u8,
+}
+
+#[derive(Debug)]
+struct Net {
+    direction: Option<Direction>,
+    reg: bool,
+    lsb: u32,
+    width: u8,
+    initial: Option<(usize, Expr)>,
+}
+
+/// An assignment in an `always` block, which only happens while `enable` is on
+#[derive(Debug)]
+struct Update {
+    line: usize,
+    target: Expr,
+    value: Expr,
+    enable: Option<Expr>,
+}
+
+#[derive(Debug)]
+enum Connections {
+    Ordered(Vec<Option<Expr>>),
+    Named(Vec<(String, Option<Expr>)>),
+}
+
+#[derive(Debug)]
+enum Item {
+    Assign { line: usize, target: Expr, value: Expr },
+    Gate { line: usize, primitive: String, terminals: Vec<Expr> },
+    Always { line: usize, clock: Expr, updates: Vec<Update> },
+    Instance { line: usize, module: String, connections: Connections },
+}
+
+#[derive(Debug)]
+struct Module {
+    name: String,
+    line: usize,
+    ports: Vec<String>,
+    nets: HashMap<String, Net>,
+    items: Vec<Item>,
+}
+
+impl Module {
+    /// The ports in the order of the ports of its subcircuit, inputs first
+    fn pins(&self) -> Vec<&str> {
+        let with = |direction| self.ports.iter()
+            .filter(move |port| self.nets[port.as_str()].direction == Some(direction))
+            .map(|port| port.as_str());
+        with(Direction::Input).chain(with(Direction::Output)).collect()
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(text: &str) -> Result<Self, LogikError> {
+        Ok(Self { tokens: tokenize(text)?, position: 0 })
+    }
+    
+    /// The line of the next token, or of the last one at the end
+    fn line(&self) -> usize {
+        self.tokens.get(self.position)
+            .or_else(|| self.tokens.last())
+            .map(|(line, _)| *line)
+            .unwrap_or(1)
+    }
+    
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+    
+    fn next(&mut self) -> Result<Token, LogikError> {
+        let token = self.peek()
+            .cloned()
+            .ok_or_else(|| invalid(self.line(), String::from("unexpected end of the file")))?;
+        self.position += 1;
+        Ok(token)
+    }
+    
+    /// Whether the next token is the keyword or symbol `text`
+    fn is(&self, text: &str) -> bool {
+        match self.peek() {
+            Some(Token::Word(word)) => word == text,
+            Some(Token::Symbol(symbol)) => *symbol == text,
+            _ => false,
+        }
+    }
+    
+    fn eat(&mut self, text: &str) -> bool {
+        let found = self.is(text);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+    
+    fn unexpected(&self, expected: &str) -> LogikError {
+        match self.peek() {
+            Some(token) => invalid(self.line(), format!("expected {} but found {}", expected, token.describe())),
+            None => invalid(self.line(), format!("expected {} but the file ended", expected)),
+        }
+    }
+    
+    fn expect(&mut self, text: &str) -> Result<(), LogikError> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", text)))
+        }
+    }
+    
+    fn name(&mut self) -> Result<String, LogikError> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let word = word.clone();
+                self.position += 1;
+                Ok(word)
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+    
+    /// A plain decimal number, as used in ranges
+    fn index(&mut self) -> Result<u32, LogikError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Number(text) => text.replace('_', "")
+                .parse()
+                .map_err(|_| invalid(line, format!("expected an index but found '{}'", text))),
+            token => Err(invalid(line, format!("expected an index but found {}", token.describe()))),
+        }
+    }
+    
+    fn modules(mut self) -> Result<Vec<Module>, LogikError> {
+        let mut modules = Vec::<Module>::new();
+        while self.peek().is_some() {
+            self.expect("module")?;
+            let module = self.module()?;
+            if modules.iter().any(|other| other.name == module.name) {
+                return Err(invalid(module.line, format!("there already is a module called {}", module.name)));
+            }
+            modules.push(module);
+        }
+        Ok(modules)
+    }
+    
+    fn module(&mut self) -> Result<Module, LogikError> {
+        let line = self.line();
+        let name = self.name()?;
+        let mut module = Module { name, line, ports: Vec::new(), nets: HashMap::new(), items: Vec::new() };
+        
+        if self.eat("(") && !self.eat(")") {
+            // a header that declares its ports keeps the last direction and range until the next one
+            let mut declaration = None;
+            loop {
+                if self.is("input") || self.is("output") || self.is("inout") {
+                    declaration = Some(self.declaration()?);
+                }
+                let line = self.line();
+                let port = self.name()?;
+                if module.ports.contains(&port) {
+                    return Err(invalid(line, format!("{} is a port twice", port)));
+                }
+                if let Some(declaration) = declaration {
+                    declare(&mut module, line, &port, declaration)?;
+                }
+                module.ports.push(port);
+                
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+        }
+        self.expect(";")?;
+        
+        while !self.eat("endmodule") {
+            self.item(&mut module)?;
+        }
+        
+        if let Some(port) = module.ports.iter().find(|port| module.nets.get(port.as_str()).and_then(|net| net.direction).is_none()) {
+            return Err(invalid(module.line, format!("port {} is neither an input nor an output", port)));
+        }
+        Ok(module)
+    }
+    
+    /// The head of a declaration up to the names
+    fn declaration(&mut self) -> Result<Declaration, LogikError> {
+        let line = self.line();
+        let direction = match self.next()? {
+            Token::Word(word) if word == "input" => Some(Direction::Input),
+            Token::Word(word) if word == "output" => Some(Direction::Output),
+            Token::Word(word) if word == "wire" || word == "reg" => {
+                self.position -= 1;
+                None
+            }
+            Token::Word(word) if word == "inout" => return Err(invalid(line, String::from("inout ports aren't supported"))),
+            token => return Err(invalid(line, format!("expected a declaration but found {}", token.describe()))),
+        };
+        let reg = if self.eat("reg") { true } else { self.eat("wire"); false };
+        
+        if !self.eat("[") {
+            return Ok(Declaration { direction, reg, lsb: 0, width: 1 });
+        }
+        let line = self.line();
+        let msb = self.index()?;
+        self.expect(":")?;
+        let lsb = self.index()?;
+        self.expect("]")?;
+        if msb < lsb {
+            return Err(invalid(line, format!("the range [{}:{}] is ascending, only descending ranges are supported", msb, lsb)));
+        }
+        if msb - lsb >= MAX_WIDTH as u32 {
+            return Err(invalid(line, format!("a net can be at most {} bits wide", MAX_WIDTH)));
+        }
+        Ok(Declaration { direction, reg, lsb, width: (msb - lsb + 1) as u8 })
+    }
+    
+    fn item(&mut self, module: &mut Module) -> Result<(), LogikError> {
+        let line = self.line();
+        let word = match self.peek() {
+            Some(Token::Word(word)) => word.clone(),
+            _ => return Err(self.unexpected("a declaration, assignment or instance")),
+        };
+        
+        match word.as_str() {
+            "input" | "output" | "inout" | "wire" | "reg" => {
+                let declaration = self.declaration()?;
+                loop {
+                    let line = self.line();
+                    let name = self.name()?;
+                    if declaration.direction.is_some() && !module.ports.contains(&name) {
+                        return Err(invalid(line, format!("{} is not a port of module {}", name, module.name)));
+                    }
+                    declare(module, line, &name, declaration)?;
+                    
+                    if self.eat("=") {
+                        let value = self.expression()?;
+                        if declaration.reg {
+                            module.nets.get_mut(&name).unwrap().initial = Some((line, value));
+                        } else {
+                            module.items.push(Item::Assign { line, target: Expr::Name(name), value });
+                        }
+                    }
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                self.expect(";")
+            }
+            "assign" => {
+                self.position += 1;
+                loop {
+                    let line = self.line();
+                    let target = self.expression()?;
+                    self.expect("=")?;
+                    let value = self.expression()?;
+                    module.items.push(Item::Assign { line, target, value });
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                self.expect(";")
+            }
+            "and" | "nand" | "or" | "nor" | "xor" | "xnor" | "buf" | "not" | "bufif0" | "bufif1" | "notif0" | "notif1" => {
+                self.position += 1;
+                if self.is("#") {
+                    return Err(invalid(line, String::from("delays aren't supported")));
+                }
+                if !self.is("(") {
+                    self.name()?;
+                }
+                self.expect("(")?;
+                let mut terminals = vec![self.expression()?];
+                while self.eat(",") {
+                    terminals.push(self.expression()?);
+                }
+                self.expect(")")?;
+                module.items.push(Item::Gate { line, primitive: word, terminals });
+                self.expect(";")
+            }
+            "always" => {
+                self.position += 1;
+                self.expect("@")?;
+                self.expect("(")?;
+                if self.is("negedge") {
+                    return Err(invalid(line, String::from("only registers that change on the rising edge are supported")));
+                }
+                self.expect("posedge")?;
+                let clock = self.expression()?;
+                if self.is("or") || self.is(",") {
+                    return Err(invalid(line, String::from("registers can only depend on a single clock edge")));
+                }
+                self.expect(")")?;
+                
+                let mut updates = Vec::new();
+                self.statement(None, &mut updates)?;
+                module.items.push(Item::Always { line, clock, updates });
+                Ok(())
+            }
+            "initial" | "parameter" | "localparam" | "function" | "task" | "generate" | "integer" | "supply0" | "supply1" | "tri" => {
+                Err(invalid(line, format!("'{}' is not supported", word)))
+            }
+            _ => {
+                self.position += 1;
+                if self.is("#") {
+                    return Err(invalid(line, String::from("module parameters aren't supported")));
+                }
+                self.name()?;
+                let connections = self.connections()?;
+                module.items.push(Item::Instance { line, module: word, connections });
+                self.expect(";")
+            }
+        }
+    }
+    
+    /// The connections of an instance, either all by position or all by name
+    fn connections(&mut self) -> Result<Connections, LogikError> {
+        self.expect("(")?;
+        if self.eat(")") {
+            return Ok(Connections::Ordered(Vec::new()));
+        }
+        
+        let connections = if self.is(".") {
+            let mut named = Vec::new();
+            loop {
+                self.expect(".")?;
+                let port = self.name()?;
+                self.expect("(")?;
+                let expr = if self.is(")") { None } else { Some(self.expression()?) };
+                self.expect(")")?;
+                named.push((port, expr));
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            Connections::Named(named)
+        } else {
+            let mut ordered = Vec::new();
+            loop {
+                ordered.push(if self.is(",") || self.is(")") { None } else { Some(self.expression()?) });
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            Connections::Ordered(ordered)
+        };
+        self.expect(")")?;
+        Ok(connections)
+    }
+    
+    /// A statement of an `always` block, the conditions of the `if` statements around it become the
+    /// enable of the registers it assigns
+    fn statement(&mut self, enable: Option<Expr>, updates: &mut Vec<Update>) -> Result<(), LogikError> {
+        let line = self.line();
+        if self.eat("begin") {
+            while !self.eat("end") {
+                self.statement(enable.clone(), updates)?;
+            }
+        } else if self.eat("if") {
+            self.expect("(")?;
+            let condition = self.expression()?;
+            self.expect(")")?;
+            let enable = match enable {
+                Some(outer) => Expr::Binary(Operator::And, Box::new(outer), Box::new(condition)),
+                None => condition,
+            };
+            self.statement(Some(enable), updates)?;
+            if self.is("else") {
+                return Err(invalid(self.line(), String::from("else branches aren't supported")));
+            }
+        } else {
+            let target = self.expression()?;
+            if !self.eat("<=") {
+                self.expect("=")?;
+            }
+            let value = self.expression()?;
+            self.expect(";")?;
+            updates.push(Update { line, target, value, enable });
+        }
+        Ok(())
+    }
+    
+    /// An expression of bitwise operators, `|` binds the loosest and `&` the tightest
+    fn expression(&mut self) -> Result<Expr, LogikError> {
+        let mut expr = self.xor()?;
+        while self.eat("|") {
+            expr = Expr::Binary(Operator::Or, Box::new(expr), Box::new(self.xor()?));
+        }
+        Ok(expr)
+    }
+    
+    fn xor(&mut self) -> Result<Expr, LogikError> {
+        let mut expr = self.and()?;
+        loop {
+            let operator = if self.eat("^") {
+                Operator::Xor
+            } else if self.eat("~^") || self.eat("^~") {
+                Operator::Xnor
+            } else {
+                return Ok(expr);
+            };
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(self.and()?));
+        }
+    }
+    
+    fn and(&mut self) -> Result<Expr, LogikError> {
+        let mut expr = self.unary()?;
+        while self.eat("&") {
+            expr = Expr::Binary(Operator::And, Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+    
+    fn unary(&mut self) -> Result<Expr, LogikError> {
+        if self.eat("~") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        
+        let line = self.line();
+        match self.next()? {
+            Token::Symbol("(") => {
+                let expr = self.expression()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Symbol("{") => {
+                // a replication like {4{a}}
+                if let (Some(Token::Number(_)), Some((_, Token::Symbol("{")))) = (self.peek(), self.tokens.get(self.position + 1)) {
+                    let count = self.index()?;
+                    self.expect("{")?;
+                    let part = self.concatenation()?;
+                    self.expect("}")?;
+                    return Ok(Expr::Concat(vec![part; count as usize]));
+                }
+                self.concatenation()
+            }
+            Token::Number(text) => {
+                let (width, bits) = literal(line, &text)?;
+                Ok(Expr::Literal(width, bits))
+            }
+            Token::Word(name) => {
+                if !self.eat("[") {
+                    return Ok(Expr::Name(name));
+                }
+                let msb = self.index()?;
+                let lsb = if self.eat(":") { self.index()? } else { msb };
+                self.expect("]")?;
+                Ok(Expr::Select(name, msb, lsb))
+            }
+            token => Err(invalid(line, format!("expected an expression but found {}", token.describe()))),
+        }
+    }
+    
+    /// The parts of a concatenation after its opening brace, up to and including the closing one
+    fn concatenation(&mut self) -> Result<Expr, LogikError> {
+        let mut parts = vec![self.expression()?];
+        while self.eat(",") {
+            parts.push(self.expression()?);
+        }
+        self.expect("}")?;
+        Ok(Expr::Concat(parts))
+    }
+}
+
+/// Declares a net, a port can be declared a second time as a `reg` or `wire` of the same width
+fn declare(module: &mut Module, line: usize, name: &str, declaration: Declaration) -> Result<(), LogikError> {
+    let Declaration { direction, reg, lsb, width } = declaration;
+    match module.nets.get_mut(name) {
+        None => {
+            module.nets.insert(String::from(name), Net { direction, reg, lsb, width, initial: None });
+        }
+        Some(net) if net.direction.is_some() != direction.is_some() && net.lsb == lsb && net.width == width => {
+            net.direction = net.direction.or(direction);
+            net.reg |= reg;
+        }
+        Some(_) => return Err(invalid(line, format!("{} is declared twice", name))),
+    }
+    Ok(())
+}
+
+/// Builds the circuit of a module, `stack` holds the names of the modules being built around it
+fn build(modules: &[Module], module: &Module, stack: &mut Vec<String>) -> Result<Data, LogikError> {
+    if stack.contains(&module.name) {
+        return Err(invalid(module.line, format!("module {} contains itself", module.name)));
+    }
+    stack.push(module.name.clone());
+    
+    let mut builder = Builder::new(module);
+    builder.pins()?;
+    for item in &module.items {
+        builder.item(modules, item, stack)?;
+    }
+    
+    stack.pop();
+    builder.finish()
+}
+
+type Links = Vec<Option<Vec<usize>>>;
+type Splitter = (Box<dyn Component>, Vec<Option<i32>>); // <splitter, subnet of every port>
+
+/// Puts a module together bit by bit. Every bit of every net is a node, and nodes that are assigned
+/// to each other are joined. Only once everything is known the joined nodes become subnets, and the
+/// ports that are wider than a bit get a bus with a splitter that connects it to its bits
+struct Builder<'a> {
+    module: &'a Module,
+    data: Data,
+    parents: Vec<usize>,
+    nets: HashMap<&'a str, Vec<usize>>, // <net, its nodes with the least significant bit first>
+    initial: HashMap<usize, bool>,
+    constants: [Option<usize>; 2],
+    components: Vec<(Box<dyn Component>, Links, Option<String>)>, // <component, nodes of every port, state>
+    subnets: HashMap<usize, i32>, // <joined node, subnet>
+    buses: HashMap<Vec<i32>, i32>, // <subnets of the bits, bus>
+    splitters: Vec<Splitter>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(module: &'a Module) -> Self {
+        let mut data = Data::new();
+        // components are simulated once everything is linked
+        data.simulation.stepping = true;
+        
+        Self {
+            module,
+            data,
+            parents: Vec::new(),
+            nets: HashMap::new(),
+            initial: HashMap::new(),
+            constants: [None; 2],
+            components: Vec::new(),
+            subnets: HashMap::new(),
+            buses: HashMap::new(),
+            splitters: Vec::new(),
+        }
+    }
+    
+    fn node(&mut self) -> usize {
+        self.parents.push(self.parents.len());
+        self.parents.len() - 1
+    }
+    
+    fn find(&mut self, node: usize) -> usize {
+        let mut root = node;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut node = node;
+        while self.parents[node] != root {
+            let parent = self.parents[node];
+            self.parents[node] = root;
+            node = parent;
+        }
+        root
+    }
+    
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+    
+    fn add(&mut self, component: Box<dyn Component>, links: Links) {
+        self.components.push((component, links, None));
+    }
+    
+    /// The `Input` and `Output` components for the ports and the nodes of every net
+    fn pins(&mut self) -> Result<(), LogikError> {
+        let module = self.module;
+        for (name, net) in &module.nets {
+            let nodes = (0..net.width).map(|_| self.node()).collect::<Vec<_>>();
+            if let Some((line, initial)) = &net.initial {
+                let bits = match initial {
+                    Expr::Literal(_, bits) => resize(bits, net.width),
+                    _ => return Err(invalid(*line, format!("the initial value of {} has to be a number", name))),
+                };
+                for (node, bit) in nodes.iter().zip(bits) {
+                    if let Some(bit) = bit {
+                        self.initial.insert(*node, bit);
+                    }
+                }
+            }
+            self.nets.insert(name.as_str(), nodes);
+        }
+        
+        for pin in module.pins() {
+            let kind = match module.nets[pin].direction {
+                Some(Direction::Input) => ComponentId::Input,
+                _ => ComponentId::Output,
+            };
+            let mut component = kind.build()?;
+            set_parameter(component.as_mut(), "label", ParameterValue::String(String::from(pin)))?;
+            set_parameter(component.as_mut(), "width", ParameterValue::Int(module.nets[pin].width as i64))?;
+            let nodes = self.nets[pin].clone();
+            self.add(component, vec![Some(nodes)]);
+        }
+        Ok(())
+    }
+    
+    fn net(&self, line: usize, name: &str) -> Result<&'a Net, LogikError> {
+        self.module.nets.get(name).ok_or_else(|| invalid(line, format!("{} is not declared", name)))
+    }
+    
+    /// The nodes of a bit or range of a net
+    fn select(&self, line: usize, name: &str, msb: u32, lsb: u32) -> Result<Vec<usize>, LogikError> {
+        let net = self.net(line, name)?;
+        if msb < lsb || lsb < net.lsb || msb >= net.lsb + net.width as u32 {
+            return Err(invalid(line, format!("{}[{}:{}] is out of range", name, msb, lsb)));
+        }
+        Ok(self.nets[name][(lsb - net.lsb) as usize..=(msb - net.lsb) as usize].to_vec())
+    }
+    
+    /// The width of an expression, numbers without a size have none
+    fn width(&self, line: usize, expr: &Expr) -> Result<Option<u8>, LogikError> {
+        Ok(match expr {
+            Expr::Name(name) => Some(self.net(line, name)?.width),
+            Expr::Select(name, msb, lsb) => Some(self.select(line, name, *msb, *lsb)?.len() as u8),
+            Expr::Literal(width, _) => *width,
+            Expr::Concat(parts) => {
+                let mut width = 0u32;
+                for part in parts {
+                    width += self.width(line, part)?
+                        .ok_or_else(|| invalid(line, String::from("numbers in a concatenation need a size")))? as u32;
+                }
+                if width > MAX_WIDTH as u32 {
+                    return Err(invalid(line, format!("the concatenation is wider than {} bits", MAX_WIDTH)));
+                }
+                Some(width as u8)
+            }
+            Expr::Not(inner) => self.width(line, inner)?,
+            Expr::Binary(_, a, b) => match (self.width(line, a)?, self.width(line, b)?) {
+                (Some(a), Some(b)) if a != b => {
+                    return Err(invalid(line, format!("the operands are {} and {} bits wide", a, b)));
+                }
+                (a, b) => a.or(b),
+            },
+        })
+    }
+    
+    /// The nodes an assignment drives
+    fn target(&self, line: usize, expr: &Expr) -> Result<Vec<usize>, LogikError> {
+        match expr {
+            Expr::Name(name) => {
+                self.net(line, name)?;
+                Ok(self.nets[name.as_str()].clone())
+            }
+            Expr::Select(name, msb, lsb) => self.select(line, name, *msb, *lsb),
+            Expr::Concat(parts) => {
+                let mut nodes = Vec::new();
+                for part in parts.iter().rev() {
+                    nodes.extend(self.target(line, part)?);
+                }
+                Ok(nodes)
+            }
+            _ => Err(invalid(line, String::from("only nets, bits of them and concatenations of them can be assigned"))),
+        }
+    }
+    
+    /// The nodes that carry the value of an expression, which has to be `width` bits wide
+    fn read(&mut self, line: usize, expr: &Expr, width: u8) -> Result<Vec<usize>, LogikError> {
+        let nodes = match expr {
+            Expr::Literal(Some(size), _) if *size != width => {
+                return Err(invalid(line, format!("expected {} bits but the value has {}", width, size)));
+            }
+            Expr::Literal(_, bits) => resize(bits, width).into_iter()
+                .map(|bit| match bit {
+                    Some(bit) => self.constant(bit),
+                    None => self.node(),
+                })
+                .collect(),
+            Expr::Name(_) | Expr::Select(..) => self.target(line, expr)?,
+            Expr::Concat(parts) => {
+                let mut nodes = Vec::new();
+                for part in parts.iter().rev() {
+                    let width = self.width(line, part)?
+                        .ok_or_else(|| invalid(line, String::from("numbers in a concatenation need a size")))?;
+                    nodes.extend(self.read(line, part, width)?);
+                }
+                nodes
+            }
+            Expr::Not(inner) => match inner.as_ref() {
+                Expr::Binary(operator, a, b) => self.operation(line, *operator, a, b, true, width)?,
+                inner => {
+                    let inputs = self.read(line, inner, width)?;
+                    inputs.into_iter().map(|input| self.gate(ComponentId::Not, vec![input])).collect::<Result<_, _>>()?
+                }
+            },
+            Expr::Binary(operator, a, b) => self.operation(line, *operator, a, b, false, width)?,
+        };
+        
+        if nodes.len() != width as usize {
+            return Err(invalid(line, format!("expected {} bits but the value has {}", width, nodes.len())));
+        }
+        Ok(nodes)
+    }
+    
+    /// A gate for every bit of an operation, chains of the same associative operator share a gate
+    fn operation(&mut self, line: usize, operator: Operator, a: &Expr, b: &Expr, inverted: bool, width: u8) -> Result<Vec<usize>, LogikError> {
+        let mut operands = Vec::new();
+        for operand in &[a, b] {
+            flatten(operand, operator, &mut operands);
+        }
+        
+        let kind = match (operator, inverted) {
+            (Operator::And, false) => ComponentId::And,
+            (Operator::And, true) => ComponentId::Nand,
+            (Operator::Or, false) => ComponentId::Or,
+            (Operator::Or, true) => ComponentId::Nor,
+            (Operator::Xor, false) | (Operator::Xnor, true) => ComponentId::Xor,
+            (Operator::Xor, true) | (Operator::Xnor, false) => ComponentId::Xnor,
+        };
+        let inputs = operands.into_iter()
+            .map(|operand| self.read(line, operand, width))
+            .collect::<Result<Vec<_>, _>>()?;
+        (0..width as usize)
+            .map(|bit| self.gate(kind, inputs.iter().map(|nodes| nodes[bit]).collect()))
+            .collect()
+    }
+    
+    /// A single bit gate, returns the node of its output. Gates with more inputs than a gate can
+    /// have are split up
+    fn gate(&mut self, kind: ComponentId, inputs: Vec<usize>) -> Result<usize, LogikError> {
+        if inputs.len() > MAX_INPUTS {
+            let inner = match kind {
+                ComponentId::Nand => ComponentId::And,
+                ComponentId::Nor => ComponentId::Or,
+                ComponentId::Xnor => ComponentId::Xor,
+                kind => kind,
+            };
+            let chunks = inputs.chunks(MAX_INPUTS)
+                .map(|chunk| if chunk.len() == 1 { Ok(chunk[0]) } else { self.gate(inner, chunk.to_vec()) })
+                .collect::<Result<Vec<_>, _>>()?;
+            return self.gate(kind, chunks);
+        }
+        
+        let component = match kind {
+            ComponentId::Buffer | ComponentId::Not => kind.build()?,
+            kind => kind.build_with_params(&[inputs.len() as i32])?,
+        };
+        let output = self.node();
+        let mut links = inputs.into_iter().map(|input| Some(vec![input])).collect::<Vec<_>>();
+        links.push(Some(vec![output]));
+        self.add(component, links);
+        Ok(output)
+    }
+    
+    /// A node that is always on or always off, shared by every number in the module
+    fn constant(&mut self, on: bool) -> usize {
+        if let Some(node) = self.constants[on as usize] {
+            return node;
+        }
+        
+        let node = self.node();
+        let mut component = ComponentId::Constant.build().unwrap();
+        let value = if on { SubnetState::On } else { SubnetState::Off };
+        set_parameter(component.as_mut(), "value", ParameterValue::Bits(value.into())).unwrap();
+        self.add(component, vec![Some(vec![node])]);
+        self.constants[on as usize] = Some(node);
+        node
+    }
+    
+    fn item(&mut self, modules: &[Module], item: &Item, stack: &mut Vec<String>) -> Result<(), LogikError> {
+        match item {
+            Item::Assign { line, target, value } => {
+                let targets = self.target(*line, target)?;
+                let values = self.read(*line, value, targets.len() as u8)?;
+                for (target, value) in targets.into_iter().zip(values) {
+                    self.join(target, value);
+                }
+            }
+            Item::Gate { line, primitive, terminals } => self.primitive(*line, primitive, terminals)?,
+            Item::Always { line, clock, updates } => {
+                let clock = self.read(*line, clock, 1)?;
+                for update in updates {
+                    let line = update.line;
+                    let targets = self.target(line, &update.target)?;
+                    if let Some(name) = self.wire(&update.target) {
+                        return Err(invalid(line, format!("{} is assigned in an always block but isn't a reg", name)));
+                    }
+                    let value = self.read(line, &update.value, targets.len() as u8)?;
+                    let enable = match &update.enable {
+                        Some(enable) => Some(self.read(line, enable, 1)?),
+                        None => None,
+                    };
+                    
+                    let state = targets.iter()
+                        .enumerate()
+                        .filter(|(_, node)| self.initial.get(node) == Some(&true))
+                        .fold(0u64, |state, (bit, _)| state | 1 << bit);
+                    let component = ComponentId::Register.build_with_params(&[targets.len() as i32])?;
+                    let links = vec![Some(value), Some(clock.clone()), enable, None, Some(targets)];
+                    self.components.push((component, links, Some(format!("{:x}", state))));
+                }
+            }
+            Item::Instance { line, module, connections } => self.instance(modules, *line, module, connections, stack)?,
+        }
+        Ok(())
+    }
+    
+    /// The first net of a target that isn't declared as a `reg`
+    fn wire<'e>(&self, target: &'e Expr) -> Option<&'e str> {
+        match target {
+            Expr::Name(name) | Expr::Select(name, ..) => match self.module.nets.get(name) {
+                Some(net) if !net.reg => Some(name),
+                _ => None,
+            },
+            Expr::Concat(parts) => parts.iter().find_map(|part| self.wire(part)),
+            _ => None,
+        }
+    }
+    
+    /// A gate primitive, the outputs come before the inputs
+    fn primitive(&mut self, line: usize, primitive: &str, terminals: &[Expr]) -> Result<(), LogikError> {
+        let bit = |builder: &mut Self, expr: &Expr| builder.read(line, expr, 1).map(|nodes| nodes[0]);
+        let output = |builder: &Self, expr: &Expr| match builder.target(line, expr)?.as_slice() {
+            [node] => Ok(*node),
+            _ => Err(invalid(line, String::from("the terminals of a gate have to be a single bit"))),
+        };
+        
+        match primitive {
+            "buf" | "not" => {
+                if terminals.len() < 2 {
+                    return Err(invalid(line, format!("{} needs an output and an input", primitive)));
+                }
+                let input = bit(self, terminals.last().unwrap())?;
+                let kind = if primitive == "buf" { ComponentId::Buffer } else { ComponentId::Not };
+                for terminal in &terminals[..terminals.len() - 1] {
+                    let target = output(self, terminal)?;
+                    let node = self.gate(kind, vec![input])?;
+                    self.join(target, node);
+                }
+            }
+            "bufif0" | "bufif1" | "notif0" | "notif1" => {
+                if terminals.len() != 3 {
+                    return Err(invalid(line, format!("{} needs an output, an input and an enable", primitive)));
+                }
+                let target = output(self, &terminals[0])?;
+                let input = bit(self, &terminals[1])?;
+                let mut enable = bit(self, &terminals[2])?;
+                if primitive.ends_with('0') {
+                    enable = self.gate(ComponentId::Not, vec![enable])?;
+                }
+                let kind = if primitive.starts_with("buf") { ComponentId::TriStateBuffer } else { ComponentId::TriStateInverter };
+                self.add(kind.build()?, vec![Some(vec![input]), Some(vec![enable]), Some(vec![target])]);
+            }
+            _ => {
+                if terminals.len() < 3 {
+                    return Err(invalid(line, format!("{} needs an output and at least two inputs", primitive)));
+                }
+                let target = output(self, &terminals[0])?;
+                let inputs = terminals[1..].iter().map(|terminal| bit(self, terminal)).collect::<Result<Vec<_>, _>>()?;
+                let kind = match primitive {
+                    "and" => ComponentId::And,
+                    "nand" => ComponentId::Nand,
+                    "or" => ComponentId::Or,
+                    "nor" => ComponentId::Nor,
+                    "xor" => ComponentId::Xor,
+                    _ => ComponentId::Xnor,
+                };
+                let node = self.gate(kind, inputs)?;
+                self.join(target, node);
+            }
+        }
+        Ok(())
+    }
+    
+    /// An instance of another module of the file as a subcircuit
+    fn instance(&mut self, modules: &[Module], line: usize, name: &str, connections: &Connections, stack: &mut Vec<String>) -> Result<(), LogikError> {
+        let module = modules.iter()
+            .find(|module| module.name == name)
+            .ok_or_else(|| invalid(line, format!("there is no module called {}", name)))?;
+        let circuit = build(modules, module, stack)?;
+        if !self.data.definitions.contains_key(name) {
+            self.data.define_subcircuit(name, &circuit)?;
+        }
+        
+        let pins = module.pins();
+        let port = |pin: &str| pins.iter()
+            .position(|other| *other == pin)
+            .ok_or_else(|| invalid(line, format!("module {} has no port {}", name, pin)));
+        let mut connected = Vec::new();
+        match connections {
+            Connections::Ordered(exprs) => {
+                if exprs.len() > module.ports.len() {
+                    return Err(invalid(line, format!("module {} has {} ports but {} are connected", name, module.ports.len(), exprs.len())));
+                }
+                for (pin, expr) in module.ports.iter().zip(exprs) {
+                    if let Some(expr) = expr {
+                        connected.push((port(pin)?, expr));
+                    }
+                }
+            }
+            Connections::Named(named) => {
+                for (pin, expr) in named {
+                    let port = port(pin)?;
+                    if connected.iter().any(|(other, _)| *other == port) {
+                        return Err(invalid(line, format!("port {} is connected twice", pin)));
+                    }
+                    if let Some(expr) = expr {
+                        connected.push((port, expr));
+                    }
+                }
+            }
+        }
+        
+        let mut links = vec![None; pins.len()];
+        for (port, expr) in connected {
+            let net = &module.nets[pins[port]];
+            let nodes = match net.direction {
+                Some(Direction::Input) => self.read(line, expr, net.width)?,
+                _ => self.target(line, expr)?,
+            };
+            if nodes.len() != net.width as usize {
+                return Err(invalid(line, format!("port {} is {} bits wide but is connected to {}", pins[port], net.width, nodes.len())));
+            }
+            links[port] = Some(nodes);
+        }
+        
+        self.add(Box::new(Subcircuit::new(name, circuit)), links);
+        Ok(())
+    }
+    
+    /// The subnet for a port linked to `nodes`, with a bus if there is more than one
+    fn subnet(&mut self, nodes: &[usize]) -> Result<i32, LogikError> {
+        let mut bits = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let root = self.find(*node);
+            let subnet = match self.subnets.get(&root) {
+                Some(subnet) => *subnet,
+                None => {
+                    let subnet = self.subnets.len() as i32 + self.buses.len() as i32 + 1;
+                    self.data.add_subnet(subnet)?;
+                    self.subnets.insert(root, subnet);
+                    subnet
+                }
+            };
+            bits.push(subnet);
+        }
+        if bits.len() == 1 {
+            return Ok(bits[0]);
+        }
+        if let Some(bus) = self.buses.get(&bits) {
+            return Ok(*bus);
+        }
+        
+        let bus = self.subnets.len() as i32 + self.buses.len() as i32 + 1;
+        self.data.add_subnet_with_width(bus, bits.len() as u8)?;
+        let width = bits.len() as i64;
+        let mut splitter = ComponentId::Splitter.build()?;
+        set_parameter(splitter.as_mut(), "width", ParameterValue::Int(width))?;
+        set_parameter(splitter.as_mut(), "fan_out", ParameterValue::Int(width))?;
+        let mut links = vec![Some(bus)];
+        links.extend(bits.iter().map(|bit| Some(*bit)));
+        self.splitters.push((splitter, links));
+        self.buses.insert(bits, bus);
+        Ok(bus)
+    }
+    
+    fn finish(mut self) -> Result<Data, LogikError> {
+        let mut placed = Vec::new();
+        for (component, links, state) in std::mem::take(&mut self.components) {
+            let links = links.iter()
+                .map(|nodes| nodes.as_ref().map(|nodes| self.subnet(nodes)).transpose())
+                .collect::<Result<Vec<_>, _>>()?;
+            placed.push((component, links, state));
+        }
+        
+        let mut states = Vec::new();
+        for (component, links, state) in placed {
+            let id = self.data.add_component(component, links)?;
+            if let Some(state) = state {
+                states.push((id, state));
+            }
+        }
+        for (splitter, links) in std::mem::take(&mut self.splitters) {
+            self.data.add_component(splitter, links)?;
+        }
+        for (id, state) in states {
+            self.data.components[&id].0.load_state(&state);
+        }
+        
+        self.data.resimulate();
+        self.data.set_stepping(false);
+        self.data.simulation.time = 0;
+        Ok(self.data)
+    }
+}
+
+/// Collects the operands of a chain of the same operator, like the three of `a & b & c`
+fn flatten<'e>(expr: &'e Expr, operator: Operator, operands: &mut Vec<&'e Expr>) {
+    match expr {
+        Expr::Binary(inner, a, b) if *inner == operator && operator != Operator::Xnor => {
+            flatten(a, operator, operands);
+            flatten(b, operator, operands);
+        }
+        expr => operands.push(expr),
+    }
+}
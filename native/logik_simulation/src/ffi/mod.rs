@@ -1,129 +1,951 @@
-use crate::data::Data;
-use std::iter;
+use crate::data::{Data, ClockPeriod};
+use crate::data::analysis::synthesis::{Form, Function, Synthesis};
 use crate::data::subnet::SubnetState;
-use crate::data::component::components::*;
-use crate::data::component::{Component, ComponentId};
-use crate::data::component::statefuls::*;
+use crate::data::error::LogikError;
+use crate::data::component::ComponentId;
+use crate::data::component::parameter::{ParameterKind, ParameterValue};
+use crate::data::component::memory::ImageFormat;
+use std::cell::RefCell;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
 
 #[cfg(test)]
 mod test;
 
+thread_local! {
+    static LAST_ERROR: RefCell<Option<LogikError>> = const { RefCell::new(None) };
+}
+
+/// Every instance handed out by `init` that hasn't been passed to `exit` yet, keyed by address
+static HANDLES: Mutex<BTreeMap<usize, HandleState>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+enum HandleState {
+    Live,
+    /// A call on this instance panicked, so its data can't be trusted anymore
+    Poisoned,
+}
+
+fn handles() -> std::sync::MutexGuard<'static, BTreeMap<usize, HandleState>> {
+    HANDLES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn check_handle(data: *mut Data) -> Result<(), LogikError> {
+    match handles().get(&(data as usize)) {
+        Some(HandleState::Live) => Ok(()),
+        Some(HandleState::Poisoned) => Err(LogikError::Poisoned),
+        None => Err(LogikError::InvalidHandle),
+    }
+}
+
+/// Runs `f` on the instance behind `data` if it is a live handle, making sure that no panic
+/// unwinds into the caller. A panic poisons the instance so that later calls on it fail
+fn with_data<T>(data: *mut Data, on_error: T, f: impl FnOnce(&mut Data) -> Result<T, LogikError>) -> T {
+    if let Err(e) = check_handle(data) {
+        return report(Err(e), on_error);
+    }
+    
+    let data_ref = unsafe { &mut *data };
+    match panic::catch_unwind(AssertUnwindSafe(|| f(data_ref))) {
+        Ok(res) => report(res, on_error),
+        Err(payload) => {
+            handles().insert(data as usize, HandleState::Poisoned);
+            
+            let message = if let Some(s) = payload.downcast_ref::<&str>() {
+                s.to_string()
+            } else if let Some(s) = payload.downcast_ref::<String>() {
+                s.clone()
+            } else {
+                String::from("unknown panic")
+            };
+            report(Err(LogikError::Panicked(message)), on_error)
+        }
+    }
+}
+
+/// Remembers the outcome of a call so that it can be queried with `last_error`, and returns what
+/// the caller should see if the call failed
+fn report<T>(result: Result<T, LogikError>, on_error: T) -> T {
+    LAST_ERROR.with(|last| match result {
+        Ok(t) => {
+            *last.borrow_mut() = None;
+            t
+        }
+        Err(e) => {
+            *last.borrow_mut() = Some(e);
+            on_error
+        }
+    })
+}
+
+/// Copies as much of `src` as fits into a buffer owned by the caller
+fn write_buffer<T: Copy>(dest: *mut T, len: i32, src: &[T]) {
+    if dest.is_null() {
+        return;
+    }
+    
+    let dest = unsafe { std::slice::from_raw_parts_mut(dest, len.max(0) as usize) };
+    for (d, s) in dest.iter_mut().zip(src) {
+        *d = *s;
+    }
+}
+
+/// Writes a nul terminated copy of `src` into `dest` if it fits into its `len` bytes and returns the
+/// length of `src` without the terminator, so that callers can retry with a bigger buffer
+fn write_str(dest: *mut u8, len: i32, src: &str) -> i32 {
+    if src.len() < len.max(0) as usize {
+        let mut bytes = String::from(src).into_bytes();
+        bytes.push(0);
+        write_buffer(dest, len, &bytes);
+    }
+    src.len() as i32
+}
+
+/// Reads a nul terminated UTF-8 string owned by the caller
+fn read_str<'a>(s: *const c_char) -> Result<&'a str, LogikError> {
+    if s.is_null() {
+        return Err(LogikError::Io(String::from("the string is null")));
+    }
+    
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map_err(|_| LogikError::Io(String::from("the string is not valid UTF-8")))
+}
+
+/// Reads `len` values owned by the caller, a null pointer is read as none
+fn read_buffer<'a, T>(src: *const T, len: i32) -> &'a [T] {
+    if src.is_null() || len <= 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(src, len as usize) }
+    }
+}
+
+/// Reads the delays to use for the kinds of components in `kinds` in a timing analysis
+fn read_delays(kinds: *const i32, delays: *const u32, len: i32) -> Result<HashMap<ComponentId, u32>, LogikError> {
+    read_buffer(kinds, len)
+        .iter()
+        .zip(read_buffer(delays, len))
+        .map(|(kind, delay)| Ok((ComponentId::try_from(*kind)?, *delay)))
+        .collect()
+}
+
+/// Returns the error code of the last call made on this thread, or 0 if it succeeded
+#[no_mangle]
+pub extern "C" fn last_error() -> i32 {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map(|e| e.code()).unwrap_or(0))
+}
+
+/// Writes a nul terminated description of the last error into `buffer` which has room for `len`
+/// bytes. Returns the length of the message without the terminator, or 0 if there was no error
+#[no_mangle]
+pub extern "C" fn last_error_message(buffer: *mut u8, len: i32) -> i32 {
+    let message = LAST_ERROR.with(|last| last.borrow().as_ref().map(|e| e.to_string()));
+    let message = match message {
+        Some(t) => t,
+        None => return 0,
+    };
+    
+    if !buffer.is_null() && len > 0 {
+        let n = message.len().min(len as usize - 1);
+        let buffer = unsafe { std::slice::from_raw_parts_mut(buffer, n + 1) };
+        buffer[..n].copy_from_slice(&message.as_bytes()[..n]);
+        buffer[n] = 0;
+    }
+    
+    message.len() as i32
+}
+
 #[no_mangle]
 pub extern "C" fn init() -> *mut Data {
-    Box::into_raw(Box::new(Data::new()))
+    let data = Box::into_raw(Box::new(Data::new()));
+    handles().insert(data as usize, HandleState::Live);
+    data
 }
 
+/// Frees an instance, poisoned instances can be freed as well
 #[no_mangle]
 pub extern "C" fn exit(data: *mut Data) {
-    unsafe { drop(Box::from_raw(data)) };
+    if handles().remove(&(data as usize)).is_none() {
+        report::<()>(Err(LogikError::InvalidHandle), ());
+        return;
+    }
+    
+    let res = panic::catch_unwind(AssertUnwindSafe(|| unsafe { drop(Box::from_raw(data)) }));
+    report(res.map_err(|_| LogikError::Panicked(String::from("panicked while freeing"))), ());
 }
 
 #[no_mangle]
 pub extern "C" fn add_subnet(data: *mut Data, id: i32) -> bool {
-    let data = unsafe { &mut *data};
-    
-    data.add_subnet(id)
+    with_data(data, false, |data| data.add_subnet(id).map(|_| true))
+}
+
+#[no_mangle]
+pub extern "C" fn add_subnet_with_width(data: *mut Data, id: i32, width: u8) -> bool {
+    with_data(data, false, |data| data.add_subnet_with_width(id, width).map(|_| true))
 }
 
 #[no_mangle]
 pub extern "C" fn remove_subnet(data: *mut Data, id: i32) -> bool {
-    let data = unsafe { &mut *data};
-    
-    data.remove_subnet(id)
+    with_data(data, false, |data| data.remove_subnet(id).map(|_| true))
+}
+
+#[no_mangle]
+pub extern "C" fn add_component(data: *mut Data, component: i32) -> i32 {
+    with_data(data, -1, |data| place_component(data, component, &[]))
 }
 
+/// Adds a component built with the given parameters, see INTEROP.md for the parameters each kind
+/// of component takes
 #[no_mangle]
-pub extern "C" fn add_component(data: *mut Data, component: ComponentId) -> i32 {
-    let data = unsafe { &mut *data};
+pub extern "C" fn add_component_with_params(data: *mut Data, component: i32, params: *const i32, len: i32) -> i32 {
+    with_data(data, -1, |data| {
+        let params = read_buffer(params, len);
     
-    let comp: Box<dyn Component> = match component {
-        ComponentId::Constant => Box::new(Constant::new()),
-        ComponentId::Output => Box::new(OutputGate {}),
-        ComponentId::Input => Box::new(InputGate {}),
-        ComponentId::LED => Box::new(LED {}),
-        ComponentId::Button => Box::new(Button::new()),
-        ComponentId::Switch => Box::new(Switch::new()),
-        ComponentId::Buffer => Box::new(Buffer {}),
-        ComponentId::Not => Box::new(NOT {}),
-        ComponentId::And => Box::new(AND {}),
-        ComponentId::Nand => Box::new(NAND {}),
-        ComponentId::Or => Box::new(OR {}),
-        ComponentId::Nor => Box::new(NOR {}),
-        ComponentId::Xor => Box::new(XOR {}),
-        ComponentId::Xnor => Box::new(XNOR {}),
-        ComponentId::TriStateBuffer => Box::new(TriBuffer {}),
-        ComponentId::TriStateInverter => Box::new(TriInverter {}),
-        ComponentId::DFlipFlop => Box::new(DFlipFlop::new()),
-        ComponentId::TFlipFlop => Box::new(TFlipFlop::new()),
-        ComponentId::JKFlipFlop => Box::new(JKFlipFlop::new()),
-        ComponentId::SRFlipFlop => Box::new(SRFlipFlop::new()),
-        ComponentId::Probe => Box::new(Probe {}),
-        ComponentId::Clock => Box::new(Clock::new()),
-    };
+        place_component(data, component, params)
+    })
+}
     
-    let p = comp.ports();
+fn place_component(data: &mut Data, component: i32, params: &[i32]) -> Result<i32, LogikError> {
+    let component = ComponentId::try_from(component)?;
+    let comp = component.build_with_params(params)?;
     
-    let res = data.add_component(comp, iter::repeat(None).take(p).collect()).unwrap();
+    let p = comp.ports();
+    let res = data.add_component(comp, vec![None; p])?;
     
     if component == ComponentId::Clock {
         data.clock(res);
     }
     
-    res
+    Ok(res)
+}
+
+/// The number of parameters a component has, they are numbered from 0 for `component_parameter_name`
+#[no_mangle]
+pub extern "C" fn component_parameter_count(data: *mut Data, component: i32) -> i32 {
+    with_data(data, -1, |data| Ok(data.parameters(component)?.len() as i32))
+}
+
+/// Writes the nul terminated name of a parameter into `buffer` if it fits, returns the length of
+/// the name
+#[no_mangle]
+pub extern "C" fn component_parameter_name(data: *mut Data, component: i32, index: i32, buffer: *mut u8, len: i32) -> i32 {
+    with_data(data, -1, |data| {
+        let parameters = data.parameters(component)?;
+        let parameter = usize::try_from(index).ok()
+            .and_then(|index| parameters.get(index))
+            .ok_or_else(|| LogikError::UnknownParameter(index.to_string()))?;
+        Ok(write_str(buffer, len, parameter.name))
+    })
+}
+
+/// The kind of value a parameter takes, 0 for integers, 1 for booleans, 2 for strings and 3 for bits
+#[no_mangle]
+pub extern "C" fn component_parameter_kind(data: *mut Data, component: i32, name: *const c_char) -> i32 {
+    with_data(data, -1, |data| Ok(data.parameter(component, read_str(name)?)?.kind.code()))
+}
+
+/// Writes the smallest and largest value an integer parameter can have into `min` and `max`
+#[no_mangle]
+pub extern "C" fn component_parameter_range(data: *mut Data, component: i32, name: *const c_char, min: *mut i64, max: *mut i64) -> bool {
+    with_data(data, false, |data| {
+        let name = read_str(name)?;
+        match data.parameter(component, name)?.kind {
+            ParameterKind::Int { min: lowest, max: highest } => {
+                write_buffer(min, 1, &[lowest]);
+                write_buffer(max, 1, &[highest]);
+                Ok(true)
+            }
+            _ => Err(not_a(name, "an integer")),
+        }
+    })
+}
+
+fn not_a(name: &str, kind: &str) -> LogikError {
+    LogikError::InvalidParameter(format!("{} is not {}", name, kind))
+}
+
+#[no_mangle]
+pub extern "C" fn get_parameter_int(data: *mut Data, component: i32, name: *const c_char, value: *mut i64) -> bool {
+    with_data(data, false, |data| {
+        let name = read_str(name)?;
+        match data.parameter(component, name)?.value {
+            ParameterValue::Int(v) => {
+                write_buffer(value, 1, &[v]);
+                Ok(true)
+            }
+            _ => Err(not_a(name, "an integer")),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn set_parameter_int(data: *mut Data, component: i32, name: *const c_char, value: i64) -> bool {
+    with_data(data, false, |data| {
+        data.set_parameter(component, read_str(name)?, ParameterValue::Int(value))?;
+        Ok(true)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn get_parameter_bool(data: *mut Data, component: i32, name: *const c_char, value: *mut bool) -> bool {
+    with_data(data, false, |data| {
+        let name = read_str(name)?;
+        match data.parameter(component, name)?.value {
+            ParameterValue::Bool(v) => {
+                write_buffer(value, 1, &[v]);
+                Ok(true)
+            }
+            _ => Err(not_a(name, "a boolean")),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn set_parameter_bool(data: *mut Data, component: i32, name: *const c_char, value: bool) -> bool {
+    with_data(data, false, |data| {
+        data.set_parameter(component, read_str(name)?, ParameterValue::Bool(value))?;
+        Ok(true)
+    })
+}
+
+/// Writes the nul terminated value of a string parameter into `buffer` if it fits, returns the length
+/// of the value
+#[no_mangle]
+pub extern "C" fn get_parameter_string(data: *mut Data, component: i32, name: *const c_char, buffer: *mut u8, len: i32) -> i32 {
+    with_data(data, -1, |data| {
+        let name = read_str(name)?;
+        match data.parameter(component, name)?.value {
+            ParameterValue::String(value) => Ok(write_str(buffer, len, &value)),
+            _ => Err(not_a(name, "a string")),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn set_parameter_string(data: *mut Data, component: i32, name: *const c_char, value: *const c_char) -> bool {
+    with_data(data, false, |data| {
+        let value = String::from(read_str(value)?);
+        data.set_parameter(component, read_str(name)?, ParameterValue::String(value))?;
+        Ok(true)
+    })
+}
+
+/// Writes the bits of a bits parameter into `values`, least significant bit first, and returns how
+/// many bits it has
+#[no_mangle]
+pub extern "C" fn get_parameter_bits(data: *mut Data, component: i32, name: *const c_char, values: *mut SubnetState, len: i32) -> i32 {
+    with_data(data, -1, |data| {
+        let name = read_str(name)?;
+        match data.parameter(component, name)?.value {
+            ParameterValue::Bits(value) => {
+                write_buffer(values, len, value.bits());
+                Ok(value.width() as i32)
+            }
+            _ => Err(not_a(name, "bits")),
+        }
+    })
+}
+
+/// Sets a bits parameter to the `len` states in `values`, least significant bit first. The states
+/// are given by their number, only `Off` (1) and `On` (2) are accepted
+#[no_mangle]
+pub extern "C" fn set_parameter_bits(data: *mut Data, component: i32, name: *const c_char, values: *const u8, len: i32) -> bool {
+    with_data(data, false, |data| {
+        let name = read_str(name)?;
+        let values = read_buffer(values, len);
+        let bits = values.iter()
+            .map(|value| match value {
+                1 => Ok(SubnetState::Off),
+                2 => Ok(SubnetState::On),
+                _ => Err(LogikError::InvalidParameter(format!("every bit of {} has to be on or off", name))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        data.set_parameter(component, name, ParameterValue::Bits(bits.into()))?;
+        Ok(true)
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn remove_component(data: *mut Data, id: i32) -> bool {
-    let data = unsafe { &mut *data };
-    
-    data.remove_component(id)
+    with_data(data, false, |data| data.remove_component(id).map(|_| true))
 }
 
 #[no_mangle]
 pub extern "C" fn link(data: *mut Data, component: i32, port: i32, subnet: i32) -> bool {
-    let data = unsafe { &mut *data };
-    
-    data.link(component, port as usize, subnet)
+    with_data(data, false, |data| data.link(component, port as usize, subnet).map(|_| true))
 }
 
 #[no_mangle]
 pub extern "C" fn unlink(data: *mut Data, component: i32, port: i32, subnet: i32) -> bool {
-    let data = unsafe { &mut *data };
-    
-    data.unlink(component, port as usize, subnet)
+    with_data(data, false, |data| data.unlink(component, port as usize, subnet).map(|_| true))
 }
 
 #[no_mangle]
 pub extern "C" fn tick(data: *mut Data) {
-    let data = unsafe { &mut *data };
-    
-    data.time_step();
+    with_data(data, (), |data| {
+        data.time_step();
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn set_component_delay(data: *mut Data, id: i32, delay: u32) -> bool {
+    with_data(data, false, |data| data.set_delay(id, delay).map(|_| true))
+}
+
+/// Returns the delay of a component, or -1 if there is no such component
+#[no_mangle]
+pub extern "C" fn component_delay(data: *mut Data, id: i32) -> i64 {
+    with_data(data, -1, |data| data.delay(id).map(|e| e as i64))
+}
+
+#[no_mangle]
+pub extern "C" fn set_stepping(data: *mut Data, stepping: bool) {
+    with_data(data, (), |data| {
+        data.set_stepping(stepping);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn advance_time(data: *mut Data, units: u32) {
+    with_data(data, (), |data| {
+        data.advance_time(units);
+        Ok(())
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn simulation_time(data: *mut Data) -> u64 {
+    with_data(data, 0, |data| Ok(data.time()))
 }
 
 #[no_mangle]
 pub extern "C" fn subnet_state(data: *mut Data, subnet: i32) -> SubnetState {
-    let data = unsafe { &mut *data };
+    with_data(data, SubnetState::Floating, |data| data.subnet_state(subnet))
+}
+
+/// Writes the bits of a subnet, least significant first, into `values` which has room for `len`
+/// states. Returns the width of the subnet, or -1 if there is no such subnet
+#[no_mangle]
+pub extern "C" fn subnet_value(data: *mut Data, subnet: i32, values: *mut SubnetState, len: i32) -> i32 {
+    with_data(data, -1, |data| data.subnet_value(subnet).map(|value| {
+        write_buffer(values, len, value.bits());
+        value.width() as i32
+    }))
+}
+
+/// Writes the ids of the subnets that are stuck in the error state because they oscillated into
+/// `subnets` which has room for `len` ids. Returns how many such subnets there are
+#[no_mangle]
+pub extern "C" fn oscillating_subnets(data: *mut Data, subnets: *mut i32, len: i32) -> i32 {
+    with_data(data, 0, |data| {
+        let oscillating = data.oscillating_subnets();
+    
+        write_buffer(subnets, len, &oscillating);
     
-    data.subnet_state(subnet).unwrap()
+        Ok(oscillating.len() as i32)
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn port_state(data: *mut Data, component: i32, port: i32) -> SubnetState {
-    let data = unsafe { &mut *data };
-    
-    data.port_state(component, port as usize).unwrap_or(SubnetState::Floating)
+    with_data(data, SubnetState::Floating, |data| data.port_state(component, port as usize))
 }
 
 #[no_mangle]
 pub extern "C" fn press_component(data: *mut Data, id: i32) -> SubnetState {
-    let data = unsafe { &mut *data };
-
-   data.press_component(id)
+    with_data(data, SubnetState::Error, |data| data.press_component(id))
 }
 
 #[no_mangle]
 pub extern "C" fn release_component(data: *mut Data, id: i32) -> SubnetState {
-    let data = unsafe { &mut *data };
+    with_data(data, SubnetState::Error, |data| data.release_component(id))
+}
+/// Writes the circuit as a netlist to the file at `path`
+#[no_mangle]
+pub extern "C" fn save_to_path(data: *mut Data, path: *const c_char) -> bool {
+    with_data(data, false, |data| {
+        std::fs::write(read_str(path)?, data.save())?;
+        Ok(true)
+    })
+}
+
+/// Writes the circuit as a nul terminated netlist into `buffer` which has room for `len` bytes.
+/// Returns the length of the netlist without the terminator, or -1 on error. If the netlist is
+/// longer than the buffer nothing is written, so the call can be repeated with a larger buffer
+#[no_mangle]
+pub extern "C" fn save_to_buffer(data: *mut Data, buffer: *mut u8, len: i32) -> i32 {
+    with_data(data, -1, |data| Ok(write_str(buffer, len, &data.save())))
+}
+
+/// Replaces the circuit of an instance with the netlist in the file at `path`. The instance is left
+/// untouched if the netlist can't be read
+#[no_mangle]
+pub extern "C" fn load_from_path(data: *mut Data, path: *const c_char) -> bool {
+    with_data(data, false, |data| {
+        let netlist = std::fs::read_to_string(read_str(path)?)?;
+        *data = Data::load(&netlist)?;
+        Ok(true)
+    })
+}
+
+/// Replaces the circuit of an instance with the netlist in `buffer`, which holds `len` bytes
+#[no_mangle]
+pub extern "C" fn load_from_buffer(data: *mut Data, buffer: *const u8, len: i32) -> bool {
+    with_data(data, false, |data| {
+        if buffer.is_null() {
+            return Err(LogikError::Io(String::from("the buffer is null")));
+        }
+        
+        let bytes = read_buffer(buffer, len);
+        let netlist = std::str::from_utf8(bytes)
+            .map_err(|_| LogikError::Io(String::from("the netlist is not valid UTF-8")))?;
+        *data = Data::load(netlist)?;
+        Ok(true)
+    })
+}
+
+/// Stores a copy of the circuit of the instance `circuit` in `data`, so that it can be placed as a
+/// subcircuit called `name`. Its `Input` and `Output` components become the ports of the instances
+#[no_mangle]
+pub extern "C" fn define_subcircuit(data: *mut Data, name: *const c_char, circuit: *mut Data) -> bool {
+    with_data(data, false, |data| {
+        check_handle(circuit)?;
+        if std::ptr::eq(data, circuit) {
+            return Err(LogikError::InvalidHandle);
+        }
+        
+        data.define_subcircuit(read_str(name)?, unsafe { &*circuit })?;
+        Ok(true)
+    })
+}
+
+/// Places an instance of a subcircuit, returns its id or -1 on error
+#[no_mangle]
+pub extern "C" fn add_subcircuit(data: *mut Data, name: *const c_char) -> i32 {
+    with_data(data, -1, |data| data.add_subcircuit(read_str(name)?))
+}
+
+/// Like `subnet_value`, but for a subnet inside of a subcircuit. `path` holds `depth` component
+/// ids, the first is a subcircuit in this circuit, the next one a subcircuit inside of that one and
+/// so on
+#[no_mangle]
+pub extern "C" fn subcircuit_subnet_value(
+    data: *mut Data,
+    path: *const i32,
+    depth: i32,
+    subnet: i32,
+    values: *mut SubnetState,
+    len: i32
+) -> i32 {
+    with_data(data, -1, |data| {
+        let path = read_buffer(path, depth);
+        
+        let value = data.subnet_value_at(path, subnet)?;
+        write_buffer(values, len, value.bits());
+        Ok(value.width() as i32)
+    })
+}
+
+/// Starts recording every change of every subnet, throwing away what was recorded before
+#[no_mangle]
+pub extern "C" fn start_recording(data: *mut Data) {
+    with_data(data, (), |data| {
+        data.start_recording();
+        Ok(())
+    })
+}
+
+/// Stops recording, what was recorded can still be saved afterwards
+#[no_mangle]
+pub extern "C" fn stop_recording(data: *mut Data) {
+    with_data(data, (), |data| {
+        data.stop_recording();
+        Ok(())
+    })
+}
+
+/// Returns how many subnet changes have been recorded, or -1 if nothing has been recorded
+#[no_mangle]
+pub extern "C" fn recorded_changes(data: *mut Data) -> i32 {
+    with_data(data, -1, |data| Ok(data.trace()?.entries().len() as i32))
+}
+
+/// Writes what has been recorded as a Value Change Dump to the file at `path`, the subnets inside
+/// of subcircuits are in a scope for every subcircuit
+#[no_mangle]
+pub extern "C" fn save_vcd(data: *mut Data, path: *const c_char) -> bool {
+    with_data(data, false, |data| {
+        std::fs::write(read_str(path)?, data.to_vcd()?)?;
+        Ok(true)
+    })
+}
+
+/// Writes the circuit as a Verilog module called `module` to the file at `path`, preceded by the
+/// modules of the subcircuits it contains
+#[no_mangle]
+pub extern "C" fn save_verilog(data: *mut Data, module: *const c_char, path: *const c_char) -> bool {
+    with_data(data, false, |data| {
+        std::fs::write(read_str(path)?, data.to_verilog(read_str(module)?)?)?;
+        Ok(true)
+    })
+}
+
+/// Writes the circuit as nul terminated Verilog into `buffer` the way `save_to_buffer` writes
+/// netlists, returns the length of the text or -1 on error
+#[no_mangle]
+pub extern "C" fn save_verilog_to_buffer(data: *mut Data, module: *const c_char, buffer: *mut u8, len: i32) -> i32 {
+    with_data(data, -1, |data| Ok(write_str(buffer, len, &data.to_verilog(read_str(module)?)?)))
+}
+
+/// Replaces the circuit of an instance with the Verilog in the file at `path`, see
+/// `Data::from_verilog` for what is understood. The instance is left untouched if it can't be read
+#[no_mangle]
+pub extern "C" fn load_verilog_from_path(data: *mut Data, path: *const c_char) -> bool {
+    with_data(data, false, |data| {
+        let verilog = std::fs::read_to_string(read_str(path)?)?;
+        *data = Data::from_verilog(&verilog)?;
+        Ok(true)
+    })
+}
+
+/// Replaces the circuit of an instance with the Verilog in `buffer`, which holds `len` bytes
+#[no_mangle]
+pub extern "C" fn load_verilog_from_buffer(data: *mut Data, buffer: *const u8, len: i32) -> bool {
+    with_data(data, false, |data| {
+        if buffer.is_null() {
+            return Err(LogikError::Io(String::from("the buffer is null")));
+        }
+        
+        let bytes = read_buffer(buffer, len);
+        let verilog = std::str::from_utf8(bytes)
+            .map_err(|_| LogikError::Io(String::from("the Verilog is not valid UTF-8")))?;
+        *data = Data::from_verilog(verilog)?;
+        Ok(true)
+    })
+}
+
+/// Replaces the circuit of an instance with the main circuit of the Logisim file at `path`, see
+/// `Data::from_logisim` for what is understood. The instance is left untouched if it can't be read
+#[no_mangle]
+pub extern "C" fn load_logisim_from_path(data: *mut Data, path: *const c_char) -> bool {
+    with_data(data, false, |data| {
+        let circuit = std::fs::read_to_string(read_str(path)?)?;
+        *data = Data::from_logisim(&circuit)?;
+        Ok(true)
+    })
+}
+
+/// Replaces the circuit of an instance with the main circuit of the Logisim file in `buffer`,
+/// which holds `len` bytes
+#[no_mangle]
+pub extern "C" fn load_logisim_from_buffer(data: *mut Data, buffer: *const u8, len: i32) -> bool {
+    with_data(data, false, |data| {
+        if buffer.is_null() {
+            return Err(LogikError::Io(String::from("the buffer is null")));
+        }
+        
+        let bytes = read_buffer(buffer, len);
+        let circuit = std::str::from_utf8(bytes)
+            .map_err(|_| LogikError::Io(String::from("the circuit is not valid UTF-8")))?;
+        *data = Data::from_logisim(circuit)?;
+        Ok(true)
+    })
+}
 
-    data.release_component(id)
-}
\ No newline at end of file
+/// Sets for how long a clock is high and low, and by how much its first rising edge is delayed
+/// beyond the low time
+#[no_mangle]
+pub extern "C" fn set_clock_period(data: *mut Data, clock: i32, high: u32, low: u32, phase: u32) -> bool {
+    with_data(data, false, |data| {
+        data.set_clock_period(clock, ClockPeriod { high, low, phase })?;
+        Ok(true)
+    })
+}
+
+/// Writes the schedule of a clock into `high`, `low` and `phase`, any of which can be null
+#[no_mangle]
+pub extern "C" fn clock_period(data: *mut Data, clock: i32, high: *mut u32, low: *mut u32, phase: *mut u32) -> bool {
+    with_data(data, false, |data| {
+        let period = data.clock_period(clock)?;
+        write_buffer(high, 1, &[period.high]);
+        write_buffer(low, 1, &[period.low]);
+        write_buffer(phase, 1, &[period.phase]);
+        Ok(true)
+    })
+}
+
+/// Returns the time of the next edge of any clock, which is where the next `tick` goes to, or -1
+/// if there are no clocks
+#[no_mangle]
+pub extern "C" fn next_clock_edge(data: *mut Data) -> i64 {
+    with_data(data, -1, |data| Ok(data.next_clock_edge().map(|edge| edge as i64).unwrap_or(-1)))
+}
+
+/// Writes the bits a register, counter, shift register or flip-flop holds into `values`, least
+/// significant bit first, and returns how many bits it holds
+#[no_mangle]
+pub extern "C" fn stored_value(data: *mut Data, component: i32, values: *mut SubnetState, len: i32) -> i32 {
+    with_data(data, -1, |data| {
+        let value = data.stored_value(component)?;
+        write_buffer(values, len, value.bits());
+        Ok(value.width() as i32)
+    })
+}
+
+/// Writes the word stored at `address` of a RAM or ROM into `word`
+#[no_mangle]
+pub extern "C" fn read_memory(data: *mut Data, component: i32, address: u64, word: *mut u64) -> bool {
+    with_data(data, false, |data| {
+        write_buffer(word, 1, &[data.read_memory(component, address)?]);
+        Ok(true)
+    })
+}
+
+/// Changes a word of a RAM or ROM, meant to be used while the simulation is paused
+#[no_mangle]
+pub extern "C" fn write_memory(data: *mut Data, component: i32, address: u64, word: u64) -> bool {
+    with_data(data, false, |data| data.write_memory(component, address, word).map(|_| true))
+}
+
+/// Replaces the contents of a RAM or ROM with the image in the file at `path`, `format` is one of
+/// the image formats listed in INTEROP.md
+#[no_mangle]
+pub extern "C" fn load_memory_from_path(data: *mut Data, component: i32, path: *const c_char, format: i32) -> bool {
+    with_data(data, false, |data| {
+        let format = ImageFormat::try_from(format)?;
+        let image = std::fs::read(read_str(path)?)?;
+        data.load_memory(component, &image, format).map(|_| true)
+    })
+}
+
+/// Replaces the contents of a RAM or ROM with the image in the `len` bytes at `image`
+#[no_mangle]
+pub extern "C" fn load_memory_from_buffer(data: *mut Data, component: i32, image: *const u8, len: i32, format: i32) -> bool {
+    with_data(data, false, |data| {
+        let format = ImageFormat::try_from(format)?;
+        let image = read_buffer(image, len);
+        data.load_memory(component, image, format).map(|_| true)
+    })
+}
+
+/// Simulates every combination of the `input_count` subnets at `inputs` and writes the bits of
+/// the `output_count` subnets at `outputs` into `states`, row by row and output by output with the
+/// least significant bit first. Returns how many states there are, or -1 if it failed
+#[no_mangle]
+pub extern "C" fn truth_table(
+    data: *mut Data,
+    inputs: *const i32,
+    input_count: i32,
+    outputs: *const i32,
+    output_count: i32,
+    states: *mut SubnetState,
+    len: i32
+) -> i32 {
+    with_data(data, -1, |data| {
+        let table = data.truth_table(read_buffer(inputs, input_count), read_buffer(outputs, output_count))?;
+        let bits = table.rows.iter()
+            .flat_map(|(_, outputs)| outputs.iter().flat_map(|output| output.bits().iter().copied()))
+            .collect::<Vec<_>>();
+        write_buffer(states, len, &bits);
+        Ok(bits.len() as i32)
+    })
+}
+
+/// Writes the truth table of the `inputs` and `outputs` as CSV into `buffer`, the same way
+/// `save_to_buffer` writes a netlist
+#[no_mangle]
+pub extern "C" fn truth_table_csv(
+    data: *mut Data,
+    inputs: *const i32,
+    input_count: i32,
+    outputs: *const i32,
+    output_count: i32,
+    buffer: *mut u8,
+    len: i32
+) -> i32 {
+    with_data(data, -1, |data| {
+        let table = data.truth_table(read_buffer(inputs, input_count), read_buffer(outputs, output_count))?;
+        Ok(write_str(buffer, len, &table.to_csv()))
+    })
+}
+
+/// Writes the truth table of the `inputs` and `outputs` as a Markdown table into `buffer`
+#[no_mangle]
+pub extern "C" fn truth_table_markdown(
+    data: *mut Data,
+    inputs: *const i32,
+    input_count: i32,
+    outputs: *const i32,
+    output_count: i32,
+    buffer: *mut u8,
+    len: i32
+) -> i32 {
+    with_data(data, -1, |data| {
+        let table = data.truth_table(read_buffer(inputs, input_count), read_buffer(outputs, output_count))?;
+        Ok(write_str(buffer, len, &table.to_markdown()))
+    })
+}
+
+/// Minimizes a boolean expression like `y = (a & !b) | c` and adds it to the circuit, see
+/// `Function::parse` for the syntax. `form` is 0 for a sum of products and 1 for a product of sums,
+/// built out of NAND gates only if `nand_only` is set. The ids of the new components and subnets
+/// are written into `components` and `subnets` and the number of new subnets into
+/// `subnet_count`. Returns the number of new components, or -1 if it failed. If either buffer is
+/// too small nothing is added, so the call can be repeated with bigger buffers
+#[no_mangle]
+pub extern "C" fn synthesize_expression(
+    data: *mut Data,
+    expression: *const c_char,
+    form: i32,
+    nand_only: bool,
+    components: *mut i32,
+    components_len: i32,
+    subnets: *mut i32,
+    subnets_len: i32,
+    subnet_count: *mut i32
+) -> i32 {
+    with_data(data, -1, |data| {
+        let function = Function::parse(read_str(expression)?)?;
+        let synthesis = data.synthesize(&function, Form::try_from(form)?, nand_only)?;
+        write_synthesis(data, synthesis, components, components_len, subnets, subnets_len, subnet_count)
+    })
+}
+
+/// Like `synthesize_expression`, but minimizes what the `output` subnet does for every value of
+/// the `inputs`, see `truth_table`. Rows where the output is floating or an error don't matter
+#[no_mangle]
+pub extern "C" fn synthesize_truth_table(
+    data: *mut Data,
+    inputs: *const i32,
+    input_count: i32,
+    output: i32,
+    form: i32,
+    nand_only: bool,
+    components: *mut i32,
+    components_len: i32,
+    subnets: *mut i32,
+    subnets_len: i32,
+    subnet_count: *mut i32
+) -> i32 {
+    with_data(data, -1, |data| {
+        let table = data.truth_table(read_buffer(inputs, input_count), &[output])?;
+        let function = Function::from_truth_table(&table, 0)?;
+        let synthesis = data.synthesize(&function, Form::try_from(form)?, nand_only)?;
+        write_synthesis(data, synthesis, components, components_len, subnets, subnets_len, subnet_count)
+    })
+}
+
+/// Writes the ids of what was synthesized, or takes it out again if they don't fit
+fn write_synthesis(
+    data: &mut Data,
+    synthesis: Synthesis,
+    components: *mut i32,
+    components_len: i32,
+    subnets: *mut i32,
+    subnets_len: i32,
+    subnet_count: *mut i32
+) -> Result<i32, LogikError> {
+    write_buffer(subnet_count, 1, &[synthesis.subnets.len() as i32]);
+    if synthesis.components.len() > components_len.max(0) as usize || synthesis.subnets.len() > subnets_len.max(0) as usize {
+        for component in &synthesis.components {
+            data.remove_component(*component)?;
+        }
+        for subnet in &synthesis.subnets {
+            data.remove_subnet(*subnet)?;
+        }
+    } else {
+        write_buffer(components, components_len, &synthesis.components);
+        write_buffer(subnets, subnets_len, &synthesis.subnets);
+    }
+    Ok(synthesis.components.len() as i32)
+}
+
+/// Checks the circuit for combinational loops, conflicting or missing drivers and unlinked ports,
+/// see `Data::lint`. Every problem is written into `buffer` as its kind, the number of subnets, the
+/// number of ports, the subnets and then a component id and a port number for every port. Returns
+/// how many numbers that takes, they are only written if all of them fit into `len`
+#[no_mangle]
+pub extern "C" fn lint(data: *mut Data, buffer: *mut i32, len: i32) -> i32 {
+    with_data(data, -1, |data| {
+        let mut records = Vec::new();
+        for diagnostic in data.lint() {
+            records.extend(&[diagnostic.kind as i32, diagnostic.subnets.len() as i32, diagnostic.ports.len() as i32]);
+            records.extend(&diagnostic.subnets);
+            records.extend(diagnostic.ports.iter().flat_map(|(component, port)| vec![*component, *port as i32]));
+        }
+        if records.len() <= len.max(0) as usize {
+            write_buffer(buffer, len, &records);
+        }
+        Ok(records.len() as i32)
+    })
+}
+
+/// Finds the slowest path from an input or flip-flop to an output or flip-flop, see `Data::timing`.
+/// The `delay_count` entries of `kinds` and `delays` give the delay of every component of that
+/// kind, the others keep their own delay. The components on the path are written into
+/// `components` and the subnets they drive into `subnets` if they fit into `len`, and the time
+/// the path takes into `delay`. Returns the number of components on the path, or -1 if it failed
+#[no_mangle]
+pub extern "C" fn critical_path(
+    data: *mut Data,
+    kinds: *const i32,
+    delays: *const u32,
+    delay_count: i32,
+    components: *mut i32,
+    subnets: *mut i32,
+    len: i32,
+    delay: *mut u64
+) -> i32 {
+    with_data(data, -1, |data| {
+        let path = data.timing(&read_delays(kinds, delays, delay_count)?)?.critical_path;
+        if path.components.len() <= len.max(0) as usize {
+            write_buffer(components, len, &path.components);
+            write_buffer(subnets, len, &path.subnets);
+        }
+        write_buffer(delay, 1, &[path.delay]);
+        Ok(path.components.len() as i32)
+    })
+}
+
+/// The fastest the clocks can run with every flip-flop seeing its inputs settled, in cycles per
+/// unit of time, with the delays given as for `critical_path`. The shortest clock period is
+/// written into `period`. Returns 0 if there are no flip-flops and -1 if it failed
+#[no_mangle]
+pub extern "C" fn max_clock_frequency(data: *mut Data, kinds: *const i32, delays: *const u32, delay_count: i32, period: *mut u64) -> f64 {
+    with_data(data, -1.0, |data| {
+        let timing = data.timing(&read_delays(kinds, delays, delay_count)?)?;
+        write_buffer(period, 1, &[timing.clock_period.unwrap_or(0)]);
+        Ok(timing.max_frequency().unwrap_or(0.0))
+    })
+}
+
+/// Writes when each of the `count` subnets settles into `times`, with the delays given as for
+/// `critical_path`. Subnets that no input or flip-flop reaches are written as -1
+#[no_mangle]
+pub extern "C" fn arrival_times(
+    data: *mut Data,
+    kinds: *const i32,
+    delays: *const u32,
+    delay_count: i32,
+    subnets: *const i32,
+    count: i32,
+    times: *mut i64
+) -> bool {
+    with_data(data, false, |data| {
+        let timing = data.timing(&read_delays(kinds, delays, delay_count)?)?;
+        let mut result = Vec::with_capacity(count.max(0) as usize);
+        for subnet in read_buffer(subnets, count) {
+            data.subnet_value(*subnet)?;
+            result.push(timing.arrivals.get(subnet).map_or(-1, |time| *time as i64));
+        }
+        write_buffer(times, count, &result);
+        Ok(true)
+    })
+}
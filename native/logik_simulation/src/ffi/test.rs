@@ -1,11 +1,12 @@
 use super::*;
+use std::ffi::CString;
 
 #[test]
 fn test_unlinking_unlinked() {
     let data = init();
     
     assert!(add_subnet(data, 0));
-    let comp = add_component(data, ComponentId::Buffer);
+    let comp = add_component(data, ComponentId::Buffer as i32);
     
     assert!(!unlink(data, comp, 0, 0));
     
@@ -20,7 +21,7 @@ fn test_removing_all_links() {
     assert!(add_subnet(data, 2));
     assert!(add_subnet(data, 3));
     
-    let id = add_component(data, ComponentId::And);
+    let id = add_component(data, ComponentId::And as i32);
     
     assert!(link(data, id, 0, 1));
     assert!(link(data, id, 1, 2));
@@ -39,8 +40,8 @@ fn test_linking_looped() {
     
     assert!(add_subnet(data, 1));
     
-    let not = add_component(data, ComponentId::Not);
-    let constant = add_component(data, ComponentId::Constant);
+    let not = add_component(data, ComponentId::Not as i32);
+    let constant = add_component(data, ComponentId::Constant as i32);
     
     assert!(link(data, constant, 0, 1));
     assert!(link(data, not, 0, 1));
@@ -56,8 +57,611 @@ fn test_relinking() {
     assert!(add_subnet(data, 1));
     assert!(add_subnet(data, 2));
     
-    let constant = add_component(data, ComponentId::Constant);
+    let constant = add_component(data, ComponentId::Constant as i32);
     
     assert!(link(data, constant, 0, 1));
     assert!(link(data, constant, 0, 2));
-}
\ No newline at end of file
+}
+#[test]
+fn test_subnet_value() {
+    let data = init();
+    
+    assert!(add_subnet_with_width(data, 1, 8));
+    assert!(!add_subnet_with_width(data, 2, 0));
+    
+    let mut values = [SubnetState::Error; 4];
+    assert_eq!(subnet_value(data, 1, values.as_mut_ptr(), values.len() as i32), 8);
+    assert_eq!(values, [SubnetState::Floating; 4]);
+    assert_eq!(subnet_value(data, 2, values.as_mut_ptr(), values.len() as i32), -1);
+    
+    exit(data);
+}
+
+#[test]
+fn test_advancing_time() {
+    let data = init();
+    
+    assert!(add_subnet(data, 1));
+    assert!(add_subnet(data, 2));
+    
+    let constant = add_component(data, ComponentId::Constant as i32);
+    let buffer = add_component(data, ComponentId::Buffer as i32);
+    
+    assert!(link(data, constant, 0, 1));
+    assert!(link(data, buffer, 0, 1));
+    assert!(link(data, buffer, 1, 2));
+    
+    assert!(set_component_delay(data, buffer, 5));
+    assert!(!set_component_delay(data, 42, 5));
+    assert_eq!(component_delay(data, buffer), 5);
+    assert_eq!(component_delay(data, 42), -1);
+    
+    set_stepping(data, true);
+    press_component(data, constant);
+    
+    let start = simulation_time(data);
+    advance_time(data, 5);
+    assert_eq!(subnet_state(data, 2), SubnetState::Off);
+    advance_time(data, 1);
+    assert_eq!(subnet_state(data, 2), SubnetState::On);
+    assert_eq!(simulation_time(data), start + 6);
+    
+    exit(data);
+}
+
+#[test]
+fn test_oscillating_subnets() {
+    let data = init();
+    
+    assert!(add_subnet(data, 1));
+    assert!(add_subnet(data, 2));
+    
+    let constant = add_component(data, ComponentId::Constant as i32);
+    let nand = add_component(data, ComponentId::Nand as i32);
+    
+    assert!(link(data, constant, 0, 1));
+    assert!(link(data, nand, 0, 1));
+    assert!(link(data, nand, 1, 2));
+    assert!(link(data, nand, 2, 2));
+    
+    assert_eq!(oscillating_subnets(data, std::ptr::null_mut(), 0), 0);
+    
+    press_component(data, constant);
+    
+    let mut subnets = [0; 4];
+    assert_eq!(oscillating_subnets(data, subnets.as_mut_ptr(), subnets.len() as i32), 1);
+    assert_eq!(subnets[0], 2);
+    assert_eq!(subnet_state(data, 2), SubnetState::Error);
+    
+    exit(data);
+}
+
+#[test]
+fn test_last_error() {
+    let data = init();
+    
+    assert!(add_subnet(data, 1));
+    assert_eq!(last_error(), 0);
+    assert_eq!(last_error_message(std::ptr::null_mut(), 0), 0);
+    
+    assert!(!add_subnet(data, 1));
+    assert_eq!(last_error(), LogikError::SubnetAlreadyExists(1).code());
+    
+    let mut buffer = [0xffu8; 64];
+    let len = last_error_message(buffer.as_mut_ptr(), buffer.len() as i32) as usize;
+    assert_eq!(&buffer[..len], b"there already is a subnet with id 1");
+    assert_eq!(buffer[len], 0);
+    
+    let mut short = [0xffu8; 6];
+    assert_eq!(last_error_message(short.as_mut_ptr(), short.len() as i32) as usize, len);
+    assert_eq!(&short, b"there\0");
+    
+    assert_eq!(subnet_state(data, 2), SubnetState::Floating);
+    assert_eq!(last_error(), LogikError::UnknownSubnet(2).code());
+    
+    assert_eq!(press_component(data, 5), SubnetState::Error);
+    assert_eq!(last_error(), LogikError::UnknownComponent(5).code());
+    
+    exit(data);
+}
+
+#[test]
+fn test_invalid_handles() {
+    assert!(!add_subnet(std::ptr::null_mut(), 1));
+    assert_eq!(last_error(), LogikError::InvalidHandle.code());
+    
+    // freed handles can't be used here since tests running in parallel might get the same address
+    let unregistered = Box::into_raw(Box::new(Data::new()));
+    
+    assert_eq!(add_component(unregistered, ComponentId::Not as i32), -1);
+    assert_eq!(last_error(), LogikError::InvalidHandle.code());
+    
+    exit(unregistered);
+    assert_eq!(last_error(), LogikError::InvalidHandle.code());
+    
+    unsafe { drop(Box::from_raw(unregistered)) };
+}
+
+#[test]
+fn test_unknown_component_type() {
+    let data = init();
+    
+    assert_eq!(add_component(data, 4), -1);
+    assert_eq!(last_error(), LogikError::UnknownComponentType(4).code());
+    
+    exit(data);
+}
+
+#[test]
+fn test_panics_poison_the_instance() {
+    let data = init();
+    
+    assert!(!with_data(data, false, |_| panic!("broken invariant")));
+    assert_eq!(last_error(), LogikError::Panicked(String::new()).code());
+    
+    let mut message = [0u8; 64];
+    let len = last_error_message(message.as_mut_ptr(), message.len() as i32) as usize;
+    assert!(std::str::from_utf8(&message[..len]).unwrap().ends_with("broken invariant"));
+    
+    assert!(!add_subnet(data, 1));
+    assert_eq!(last_error(), LogikError::Poisoned.code());
+    
+    exit(data);
+    assert_eq!(last_error(), 0);
+}
+
+#[test]
+fn test_saving_and_loading() {
+    let data = init();
+    
+    assert!(add_subnet(data, 1));
+    let constant = add_component(data, ComponentId::Constant as i32);
+    assert!(link(data, constant, 0, 1));
+    assert_eq!(press_component(data, constant), SubnetState::On);
+    
+    let len = save_to_buffer(data, std::ptr::null_mut(), 0);
+    let mut small = [1u8; 4];
+    assert_eq!(save_to_buffer(data, small.as_mut_ptr(), small.len() as i32), len);
+    assert_eq!(small, [1u8; 4]);
+    
+    let mut buffer = vec![0u8; len as usize + 1];
+    assert_eq!(save_to_buffer(data, buffer.as_mut_ptr(), buffer.len() as i32), len);
+    assert_eq!(buffer[len as usize], 0);
+    
+    let path = std::env::temp_dir().join(format!("logik_netlist_{}.txt", std::process::id()));
+    let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+    assert!(save_to_path(data, c_path.as_ptr()));
+    assert_eq!(std::fs::read(&path).unwrap(), &buffer[..len as usize]);
+    
+    let other = init();
+    assert!(load_from_path(other, c_path.as_ptr()));
+    assert_eq!(subnet_state(other, 1), SubnetState::On);
+    std::fs::remove_file(&path).unwrap();
+    
+    let missing = std::ffi::CString::new(format!("{}.missing", path.to_str().unwrap())).unwrap();
+    assert!(!load_from_path(other, missing.as_ptr()));
+    assert_eq!(last_error(), LogikError::Io(String::new()).code());
+    assert_eq!(subnet_state(other, 1), SubnetState::On);
+    
+    let netlist = b"logik 1\nsubnet 5 1\n";
+    assert!(load_from_buffer(other, netlist.as_ptr(), netlist.len() as i32));
+    assert_eq!(subnet_state(other, 1), SubnetState::Floating);
+    assert_eq!(last_error(), LogikError::UnknownSubnet(1).code());
+    assert_eq!(subnet_state(other, 5), SubnetState::Floating);
+    
+    exit(other);
+    exit(data);
+}
+
+#[test]
+fn test_subcircuits() {
+    let inverter = init();
+    assert!(add_subnet(inverter, 1));
+    assert!(add_subnet(inverter, 2));
+    let input = add_component(inverter, ComponentId::Input as i32);
+    let not = add_component(inverter, ComponentId::Not as i32);
+    let output = add_component(inverter, ComponentId::Output as i32);
+    assert!(link(inverter, input, 0, 1));
+    assert!(link(inverter, not, 0, 1));
+    assert!(link(inverter, not, 1, 2));
+    assert!(link(inverter, output, 0, 2));
+    
+    let data = init();
+    let name = std::ffi::CString::new("inverter").unwrap();
+    assert!(define_subcircuit(data, name.as_ptr(), inverter));
+    assert!(!define_subcircuit(data, name.as_ptr(), data));
+    assert_eq!(last_error(), LogikError::InvalidHandle.code());
+    exit(inverter);
+    
+    assert!(add_subnet(data, 1));
+    assert!(add_subnet(data, 2));
+    let constant = add_component(data, ComponentId::Constant as i32);
+    let instance = add_subcircuit(data, name.as_ptr());
+    assert!(link(data, constant, 0, 1));
+    assert!(link(data, instance, 0, 1));
+    assert!(link(data, instance, 1, 2));
+    assert_eq!(subnet_state(data, 2), SubnetState::On);
+    
+    let mut value = [SubnetState::Error; 1];
+    let path = [instance];
+    assert_eq!(subcircuit_subnet_value(data, path.as_ptr(), 1, 1, value.as_mut_ptr(), 1), 1);
+    assert_eq!(value, [SubnetState::Off]);
+    
+    let missing = std::ffi::CString::new("buffer").unwrap();
+    assert_eq!(add_subcircuit(data, missing.as_ptr()), -1);
+    assert_eq!(last_error(), LogikError::UnknownSubcircuit(String::new()).code());
+    
+    exit(data);
+}
+
+#[test]
+fn test_recording() {
+    let data = init();
+    
+    assert!(add_subnet(data, 1));
+    let constant = add_component(data, ComponentId::Constant as i32);
+    assert!(link(data, constant, 0, 1));
+    
+    assert_eq!(recorded_changes(data), -1);
+    assert_eq!(last_error(), LogikError::NoTrace.code());
+    
+    start_recording(data);
+    press_component(data, constant);
+    press_component(data, constant);
+    stop_recording(data);
+    press_component(data, constant);
+    assert_eq!(recorded_changes(data), 2);
+    
+    let path = std::env::temp_dir().join(format!("logik_trace_{}.vcd", std::process::id()));
+    let c_path = std::ffi::CString::new(path.to_str().unwrap()).unwrap();
+    assert!(save_vcd(data, c_path.as_ptr()));
+    assert!(std::fs::read_to_string(&path).unwrap().contains("$var wire 1 \" subnet_1 $end"));
+    std::fs::remove_file(&path).unwrap();
+    
+    exit(data);
+}
+
+#[test]
+fn test_clock_periods() {
+    let data = init();
+    
+    assert_eq!(next_clock_edge(data), -1);
+    
+    assert!(add_subnet(data, 1));
+    let clock = add_component(data, ComponentId::Clock as i32);
+    let not = add_component(data, ComponentId::Not as i32);
+    assert!(link(data, clock, 0, 1));
+    
+    assert!(set_clock_period(data, clock, 3, 2, 1));
+    assert!(!set_clock_period(data, not, 3, 2, 1));
+    assert_eq!(last_error(), LogikError::NotAClock(not).code());
+    
+    let (mut high, mut low) = (0, 0);
+    assert!(clock_period(data, clock, &mut high, &mut low, std::ptr::null_mut()));
+    assert_eq!((high, low), (3, 2));
+    
+    assert_eq!(subnet_state(data, 1), SubnetState::On);
+    assert_eq!(next_clock_edge(data), 1);
+    tick(data);
+    assert_eq!(subnet_state(data, 1), SubnetState::Off);
+    assert_eq!(next_clock_edge(data), 3);
+    tick(data);
+    assert_eq!(subnet_state(data, 1), SubnetState::On);
+    assert_eq!(next_clock_edge(data), 6);
+    
+    exit(data);
+}
+
+#[test]
+fn test_components_with_params() {
+    let data = init();
+    
+    for subnet in 1..=4 {
+        assert!(add_subnet(data, subnet));
+    }
+    let params = [3, 1];
+    let xor = add_component_with_params(data, ComponentId::Xor as i32, params.as_ptr(), 2);
+    assert!(xor > 0);
+    assert!(link(data, xor, 3, 4));
+    assert!(!link(data, xor, 4, 4));
+    assert_eq!(last_error(), LogikError::PortOutOfRange { component: xor, port: 4 }.code());
+    
+    let and = add_component_with_params(data, ComponentId::And as i32, std::ptr::null(), 0);
+    assert!(and > 0);
+    assert!(!link(data, and, 3, 4));
+    
+    let params = [33];
+    assert_eq!(add_component_with_params(data, ComponentId::Or as i32, params.as_ptr(), 1), -1);
+    assert_eq!(last_error(), LogikError::InvalidParameter(String::new()).code());
+    assert_eq!(add_component_with_params(data, ComponentId::Probe as i32, params.as_ptr(), 1), -1);
+    assert_eq!(last_error(), LogikError::InvalidParameter(String::new()).code());
+    
+    exit(data);
+}
+
+#[test]
+fn test_parameters() {
+    let data = init();
+    
+    assert!(add_subnet(data, 1));
+    let xor = add_component(data, ComponentId::Xor as i32);
+    let constant = add_component(data, ComponentId::Constant as i32);
+    let output = add_component(data, ComponentId::Output as i32);
+    assert!(link(data, xor, 2, 1));
+    
+    assert_eq!(component_parameter_count(data, xor), 2);
+    let mut name = [0u8; 16];
+    assert_eq!(component_parameter_name(data, xor, 1, name.as_mut_ptr(), name.len() as i32), 7);
+    assert_eq!(&name[..8], b"one_hot\0");
+    let one_hot = CString::new("one_hot").unwrap();
+    assert_eq!(component_parameter_kind(data, xor, one_hot.as_ptr()), 1);
+    assert_eq!(component_parameter_name(data, xor, 2, name.as_mut_ptr(), name.len() as i32), -1);
+    
+    let inputs = CString::new("inputs").unwrap();
+    assert_eq!(component_parameter_kind(data, xor, inputs.as_ptr()), 0);
+    let (mut min, mut max) = (0, 0);
+    assert!(component_parameter_range(data, xor, inputs.as_ptr(), &mut min, &mut max));
+    assert_eq!((min, max), (2, 32));
+    
+    // the output of the gate has to move to port 4, which isn't linked yet
+    assert!(set_parameter_int(data, xor, inputs.as_ptr(), 4));
+    let mut value = 0;
+    assert!(get_parameter_int(data, xor, inputs.as_ptr(), &mut value));
+    assert_eq!(value, 4);
+    assert!(!set_parameter_bool(data, xor, inputs.as_ptr(), true));
+    assert_eq!(last_error(), LogikError::InvalidParameter(String::new()).code());
+    let missing = CString::new("missing").unwrap();
+    assert!(!get_parameter_int(data, xor, missing.as_ptr(), &mut value));
+    assert_eq!(last_error(), LogikError::UnknownParameter(String::new()).code());
+    
+    let bits = CString::new("value").unwrap();
+    assert_eq!(component_parameter_kind(data, constant, bits.as_ptr()), 3);
+    assert!(set_parameter_bits(data, constant, bits.as_ptr(), [2u8, 1, 2].as_ptr(), 3));
+    let mut states = [SubnetState::Floating; 4];
+    assert_eq!(get_parameter_bits(data, constant, bits.as_ptr(), states.as_mut_ptr(), 4), 3);
+    assert_eq!(states[..3], [SubnetState::On, SubnetState::Off, SubnetState::On]);
+    assert!(!set_parameter_bits(data, constant, bits.as_ptr(), [3u8].as_ptr(), 1));
+    
+    let label = CString::new("label").unwrap();
+    let sum = CString::new("sum").unwrap();
+    assert!(set_parameter_string(data, output, label.as_ptr(), sum.as_ptr()));
+    let mut buffer = [0u8; 2];
+    assert_eq!(get_parameter_string(data, output, label.as_ptr(), buffer.as_mut_ptr(), 2), 3);
+    assert_eq!(buffer, [0, 0]);
+    
+    exit(data);
+}
+
+#[test]
+fn test_memory() {
+    let data = init();
+    
+    let ram = add_component(data, ComponentId::RAM as i32);
+    let not = add_component(data, ComponentId::Not as i32);
+    
+    assert!(write_memory(data, ram, 0x10, 0xab));
+    let mut word = 0;
+    assert!(read_memory(data, ram, 0x10, &mut word));
+    assert_eq!(word, 0xab);
+    assert!(!read_memory(data, ram, 0x100, &mut word));
+    assert_eq!(last_error(), LogikError::AddressOutOfRange(0x100).code());
+    assert!(!write_memory(data, not, 0, 0));
+    assert_eq!(last_error(), LogikError::NotAMemory(not).code());
+    
+    let image = b"1 2 3";
+    assert!(load_memory_from_buffer(data, ram, image.as_ptr(), image.len() as i32, 1));
+    assert!(read_memory(data, ram, 2, &mut word));
+    assert_eq!(word, 3);
+    assert!(read_memory(data, ram, 0x10, &mut word));
+    assert_eq!(word, 0);
+    assert!(!load_memory_from_buffer(data, ram, image.as_ptr(), image.len() as i32, 7));
+    assert_eq!(last_error(), LogikError::InvalidImage(String::new()).code());
+    
+    exit(data);
+}
+
+#[test]
+fn test_stored_value() {
+    let data = init();
+    
+    let counter = add_component_with_params(data, ComponentId::Counter as i32, [4].as_ptr(), 1);
+    let flip_flop = add_component(data, ComponentId::DFlipFlop as i32);
+    let not = add_component(data, ComponentId::Not as i32);
+    
+    let mut values = [SubnetState::Error; 8];
+    assert_eq!(stored_value(data, counter, values.as_mut_ptr(), values.len() as i32), 4);
+    assert_eq!(values[..5], [SubnetState::Off, SubnetState::Off, SubnetState::Off, SubnetState::Off, SubnetState::Error]);
+    assert_eq!(stored_value(data, flip_flop, values.as_mut_ptr(), values.len() as i32), 1);
+    assert_eq!(stored_value(data, not, values.as_mut_ptr(), values.len() as i32), -1);
+    assert_eq!(last_error(), LogikError::NoStoredValue(not).code());
+    
+    exit(data);
+}
+
+#[test]
+fn test_verilog() {
+    let data = init();
+    
+    add_subnet(data, 1);
+    let not = add_component(data, ComponentId::Not as i32);
+    link(data, not, 1, 1);
+    
+    let module = CString::new("inverter").unwrap();
+    let len = save_verilog_to_buffer(data, module.as_ptr(), std::ptr::null_mut(), 0);
+    let mut buffer = vec![0u8; len as usize + 1];
+    assert_eq!(save_verilog_to_buffer(data, module.as_ptr(), buffer.as_mut_ptr(), buffer.len() as i32), len);
+    let verilog = std::str::from_utf8(&buffer[..len as usize]).unwrap();
+    assert!(verilog.starts_with("module inverter;\n"));
+    assert!(verilog.contains("assign s1 = ~1'bz;"));
+    
+    let invalid = CString::new("two words").unwrap();
+    assert_eq!(save_verilog_to_buffer(data, invalid.as_ptr(), buffer.as_mut_ptr(), buffer.len() as i32), -1);
+    assert_eq!(last_error(), LogikError::InvalidName(String::new()).code());
+    
+    let other = init();
+    assert!(load_verilog_from_buffer(other, buffer.as_ptr(), len));
+    let broken = b"module broken(input a);";
+    assert!(!load_verilog_from_buffer(other, broken.as_ptr(), broken.len() as i32));
+    assert_eq!(last_error(), LogikError::InvalidVerilog { line: 0, reason: String::new() }.code());
+    
+    exit(other);
+    exit(data);
+}
+
+#[test]
+fn test_load_logisim() {
+    let data = init();
+    
+    let circuit = br##"<project>
+        <lib desc="#Wiring" name="0"/>
+        <circuit name="main">
+          <wire from="(0,0)" to="(40,0)"/>
+          <comp lib="0" loc="(0,0)" name="Constant"/>
+          <comp lib="0" loc="(40,0)" name="Probe"/>
+        </circuit>
+      </project>"##;
+    assert!(load_logisim_from_buffer(data, circuit.as_ptr(), circuit.len() as i32));
+    assert_eq!(subnet_state(data, 1), SubnetState::On);
+    
+    let broken = b"<project><circuit name=\"main\"></project>";
+    assert!(!load_logisim_from_buffer(data, broken.as_ptr(), broken.len() as i32));
+    assert_eq!(last_error(), LogikError::InvalidLogisim(String::new()).code());
+    assert_eq!(subnet_state(data, 1), SubnetState::On);
+    
+    exit(data);
+}
+
+#[test]
+fn test_truth_table() {
+    let data = init();
+    
+    add_subnet(data, 1);
+    add_subnet(data, 2);
+    let input = add_component(data, ComponentId::Input as i32);
+    link(data, input, 0, 1);
+    let not = add_component(data, ComponentId::Not as i32);
+    link(data, not, 0, 1);
+    link(data, not, 1, 2);
+    
+    let mut states = [SubnetState::Floating; 2];
+    assert_eq!(truth_table(data, [1].as_ptr(), 1, [2].as_ptr(), 1, states.as_mut_ptr(), 2), 2);
+    assert_eq!(states, [SubnetState::On, SubnetState::Off]);
+    
+    let len = truth_table_csv(data, [1].as_ptr(), 1, [2].as_ptr(), 1, std::ptr::null_mut(), 0);
+    let mut buffer = vec![0u8; len as usize + 1];
+    assert_eq!(truth_table_csv(data, [1].as_ptr(), 1, [2].as_ptr(), 1, buffer.as_mut_ptr(), buffer.len() as i32), len);
+    assert_eq!(&buffer[..len as usize], b"subnet 1,subnet 2\n0,1\n1,0\n");
+    assert!(truth_table_markdown(data, [1].as_ptr(), 1, [2].as_ptr(), 1, std::ptr::null_mut(), 0) > 0);
+    
+    assert_eq!(truth_table(data, [2].as_ptr(), 1, std::ptr::null(), 0, std::ptr::null_mut(), 0), -1);
+    assert_eq!(last_error(), LogikError::NotAnInput(2).code());
+    
+    exit(data);
+}
+
+#[test]
+fn test_synthesize_expression() {
+    let data = init();
+    
+    let expression = CString::new("y = a & !b").unwrap();
+    let mut count = 0;
+    assert_eq!(synthesize_expression(data, expression.as_ptr(), 0, false, std::ptr::null_mut(), 0, std::ptr::null_mut(), 0, &mut count), 5);
+    assert_eq!(count, 4);
+    assert!(!remove_subnet(data, 1));
+    
+    // a NAND gate per inversion and another one to invert the product
+    let mut components = [0; 6];
+    let mut subnets = [0; 5];
+    assert_eq!(synthesize_expression(data, expression.as_ptr(), 1, true, components.as_mut_ptr(), 6, subnets.as_mut_ptr(), 5, &mut count), 6);
+    assert_eq!(subnets, [1, 2, 3, 4, 5]);
+    let mut states = [SubnetState::Floating; 4];
+    assert_eq!(truth_table(data, [1, 2].as_ptr(), 2, [5].as_ptr(), 1, states.as_mut_ptr(), 4), 4);
+    assert_eq!(states, [SubnetState::Off, SubnetState::Off, SubnetState::On, SubnetState::Off]);
+    
+    // the same function again as a sum of products, from what the circuit does
+    let mut components = [0; 5];
+    let mut subnets = [0; 4];
+    assert_eq!(synthesize_truth_table(data, [1, 2].as_ptr(), 2, 5, 0, false, components.as_mut_ptr(), 5, subnets.as_mut_ptr(), 4, &mut count), 5);
+    assert_eq!(subnets, [6, 7, 8, 9]);
+    assert_eq!(truth_table(data, [6, 7].as_ptr(), 2, [9].as_ptr(), 1, states.as_mut_ptr(), 4), 4);
+    assert_eq!(states, [SubnetState::Off, SubnetState::Off, SubnetState::On, SubnetState::Off]);
+    
+    let broken = CString::new("a & (b").unwrap();
+    assert_eq!(synthesize_expression(data, broken.as_ptr(), 0, false, std::ptr::null_mut(), 0, std::ptr::null_mut(), 0, &mut count), -1);
+    assert_eq!(last_error(), LogikError::InvalidExpression { column: 0, reason: String::new() }.code());
+    assert_eq!(synthesize_expression(data, expression.as_ptr(), 2, false, std::ptr::null_mut(), 0, std::ptr::null_mut(), 0, &mut count), -1);
+    assert_eq!(last_error(), LogikError::InvalidParameter(String::new()).code());
+    
+    exit(data);
+}
+
+#[test]
+fn test_lint() {
+    let data = init();
+    
+    add_subnet(data, 1);
+    add_subnet(data, 2);
+    let first = add_component(data, ComponentId::Not as i32);
+    link(data, first, 0, 1);
+    link(data, first, 1, 2);
+    let mut records = [-1; 12];
+    assert_eq!(lint(data, records.as_mut_ptr(), 12), 12);
+    assert_eq!(records, [2, 1, 1, 1, first, 0, 4, 1, 1, 2, first, 1]);
+    
+    let second = add_component(data, ComponentId::Not as i32);
+    link(data, second, 0, 2);
+    link(data, second, 1, 1);
+    let len = lint(data, std::ptr::null_mut(), 0);
+    let mut records = vec![-1; len as usize];
+    assert_eq!(lint(data, records.as_mut_ptr(), len), len);
+    assert_eq!(records, [0, 2, 2, 1, 2, first, 1, second, 1]);
+    
+    assert_eq!(lint(std::ptr::null_mut(), std::ptr::null_mut(), 0), -1);
+    
+    exit(data);
+}
+
+#[test]
+fn test_timing() {
+    let data = init();
+    
+    for subnet in 1..=3 {
+        add_subnet(data, subnet);
+    }
+    let input = add_component(data, ComponentId::Input as i32);
+    link(data, input, 0, 1);
+    let not = add_component(data, ComponentId::Not as i32);
+    link(data, not, 0, 1);
+    link(data, not, 1, 2);
+    let flip_flop = add_component(data, ComponentId::DFlipFlop as i32);
+    link(data, flip_flop, 0, 2);
+    link(data, flip_flop, 3, 3);
+    
+    let kinds = [ComponentId::Not as i32];
+    let delays = [5];
+    let mut components = [0; 2];
+    let mut subnets = [0; 2];
+    let mut delay = 0;
+    assert_eq!(critical_path(data, kinds.as_ptr(), delays.as_ptr(), 1, components.as_mut_ptr(), subnets.as_mut_ptr(), 2, &mut delay), 2);
+    assert_eq!((components, subnets, delay), ([input, not], [1, 2], 5));
+    
+    let mut period = 0;
+    assert_eq!(max_clock_frequency(data, kinds.as_ptr(), delays.as_ptr(), 1, &mut period), 0.2);
+    assert_eq!(period, 5);
+    
+    let mut times = [0; 3];
+    assert!(arrival_times(data, kinds.as_ptr(), delays.as_ptr(), 1, [1, 2, 3].as_ptr(), 3, times.as_mut_ptr()));
+    assert_eq!(times, [0, 5, 1]);
+    assert!(!arrival_times(data, std::ptr::null(), std::ptr::null(), 0, [4].as_ptr(), 1, times.as_mut_ptr()));
+    assert_eq!(last_error(), LogikError::UnknownSubnet(4).code());
+    
+    assert_eq!(critical_path(data, [-1].as_ptr(), [1].as_ptr(), 1, std::ptr::null_mut(), std::ptr::null_mut(), 0, &mut delay), -1);
+    assert_eq!(last_error(), LogikError::UnknownComponentType(-1).code());
+    let loop_back = add_component(data, ComponentId::Not as i32);
+    link(data, loop_back, 0, 2);
+    link(data, loop_back, 1, 1);
+    assert_eq!(max_clock_frequency(data, std::ptr::null(), std::ptr::null(), 0, &mut period), -1.0);
+    assert_eq!(last_error(), LogikError::CombinationalLoop(0).code());
+    
+    exit(data);
+}
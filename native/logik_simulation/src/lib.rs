@@ -1,3 +1,5 @@
+#![allow(clippy::upper_case_acronyms)]
+
 mod data;
 mod ffi;
 mod macros;
\ No newline at end of file